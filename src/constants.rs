@@ -9,9 +9,12 @@ pub const USER_AGENT: &'static str = "PixivAndroidApp/5.0.234 (Android 11; Pixel
 pub const X_CLIENT_TIME: &'static str = "X-Client-Time";
 pub const X_CLIENT_HASH: &'static str = "X-Client-Hash";
 pub const FOR_IOS: &'static str = "for_ios";
+// i.pximg.net refuses requests without a pixiv referer.
+pub const IMAGE_REFERER: &'static str = "https://app-api.pixiv.net/";
 
 // Header Keys
 pub const ILLUST_ID: &'static str = "illust_id";
+pub const NOVEL_ID: &'static str = "novel_id";
 pub const RESTRICT: &'static str = "restrict";
 pub const TAGS: &'static str = "tags[]";
 pub const OFFSET: &'static str = "offset";
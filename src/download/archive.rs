@@ -0,0 +1,219 @@
+use crate::enums::ImageSize;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Identifies a downloaded page: the illustration, the page index and the size.
+///
+/// It is written as `<illust id>_p<page> <size>`, e.g. `75523989_p0 original`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArchiveKey {
+    illust_id: u32,
+    page: u32,
+    size: ImageSize,
+}
+
+impl ArchiveKey {
+    pub fn new(illust_id: u32, page: u32, size: ImageSize) -> Self {
+        ArchiveKey {
+            illust_id,
+            page,
+            size,
+        }
+    }
+
+    pub fn illust_id(&self) -> u32 {
+        self.illust_id
+    }
+
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    pub fn size(&self) -> ImageSize {
+        self.size
+    }
+}
+
+impl fmt::Display for ArchiveKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}_p{} {}",
+            self.illust_id,
+            self.page,
+            self.size.as_str()
+        )
+    }
+}
+
+impl FromStr for ArchiveKey {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid archive entry: {:?}", s),
+            )
+        };
+        let (page, size) = s.trim().split_once(' ').ok_or_else(invalid)?;
+        let (illust_id, page) = parse_page_name(page).ok_or_else(invalid)?;
+        let size = match size.trim() {
+            "square_medium" => ImageSize::SquareMedium,
+            "medium" => ImageSize::Medium,
+            "large" => ImageSize::Large,
+            "original" => ImageSize::Original,
+            _ => return Err(invalid()),
+        };
+        Ok(ArchiveKey::new(illust_id, page, size))
+    }
+}
+
+/// Parses the `<illust id>_p<page>` at the start of pixiv's file names, such as
+/// `75523989_p0.png` or `75523989_p0_master1200.jpg`.
+fn parse_page_name(name: &str) -> Option<(u32, u32)> {
+    let (illust_id, rest) = name.split_once("_p")?;
+    let page: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    Some((illust_id.parse().ok()?, page.parse().ok()?))
+}
+
+#[derive(Debug, Default)]
+struct ArchiveState {
+    keys: HashSet<ArchiveKey>,
+    file: Option<File>,
+}
+
+/// Record of the pages that were downloaded, so that downloading the same works again only
+/// fetches what is missing. See `DownloadQueue::set_archive`.
+///
+/// An archive opened from a file appends every new key to it right away, one per line, so
+/// the record survives a crash halfway through a download. Lines starting with `#` are
+/// ignored.
+#[derive(Debug, Default)]
+pub struct DownloadArchive {
+    state: Mutex<ArchiveState>,
+}
+
+impl DownloadArchive {
+    /// An archive that is not saved anywhere.
+    pub fn in_memory() -> Self {
+        Default::default()
+    }
+
+    /// Opens the archive at `path`, creating it if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        let keys = read_keys(BufReader::new(&file))?;
+        Ok(DownloadArchive {
+            state: Mutex::new(ArchiveState {
+                keys,
+                file: Some(file),
+            }),
+        })
+    }
+
+    pub fn contains(&self, key: &ArchiveKey) -> bool {
+        self.state.lock().unwrap().keys.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records a key, returning whether it was new.
+    pub fn insert(&self, key: ArchiveKey) -> io::Result<bool> {
+        self.extend(std::iter::once(key)).map(|added| added == 1)
+    }
+
+    /// Records every key, returning how many were new.
+    pub fn extend<I: IntoIterator<Item = ArchiveKey>>(&self, keys: I) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        let added: Vec<ArchiveKey> = keys
+            .into_iter()
+            .filter(|key| state.keys.insert(*key))
+            .collect();
+        if let Some(file) = state.file.as_mut() {
+            let lines: String = added.iter().map(|key| format!("{}\n", key)).collect();
+            file.write_all(lines.as_bytes())?;
+            file.flush()?;
+        }
+        Ok(added.len())
+    }
+
+    /// Returns every key, sorted.
+    pub fn keys(&self) -> Vec<ArchiveKey> {
+        let mut keys: Vec<ArchiveKey> = self.state.lock().unwrap().keys.iter().copied().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Writes every key to `writer`, in the format read by `import`.
+    pub fn export<W: Write>(&self, mut writer: W) -> io::Result<W> {
+        for key in self.keys() {
+            writeln!(writer, "{}", key)?;
+        }
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    /// Adds the keys exported by another archive, returning how many were new.
+    pub fn import<R: BufRead>(&self, reader: R) -> io::Result<usize> {
+        self.extend(read_keys(reader)?)
+    }
+
+    /// Adds a key for every file under `path` named like pixiv names its files
+    /// (`<illust id>_p<page>...`), as downloaded at the given size. Returns how many were new.
+    pub fn import_directory<P: AsRef<Path>>(&self, path: P, size: ImageSize) -> io::Result<usize> {
+        let mut keys = Vec::new();
+        let mut directories = vec![path.as_ref().to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(directory)? {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    directories.push(entry.path());
+                    continue;
+                }
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if name.ends_with(".part") || name.ends_with(".part.json") {
+                    continue;
+                }
+                if let Some((illust_id, page)) = parse_page_name(&name) {
+                    keys.push(ArchiveKey::new(illust_id, page, size));
+                }
+            }
+        }
+        self.extend(keys)
+    }
+}
+
+fn read_keys<R: BufRead>(reader: R) -> io::Result<HashSet<ArchiveKey>> {
+    let mut keys = HashSet::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        keys.insert(line.parse()?);
+    }
+    Ok(keys)
+}
@@ -0,0 +1,322 @@
+use crate::enums::ImageSize;
+use crate::errors::CbzError;
+use crate::pixiv::client::PixivClient;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::utils::{caption_to_text, escape_html};
+
+use chrono::Datelike;
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Metadata written to the ComicInfo.xml of a CBZ, the format read by most comic readers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComicInfo {
+    title: String,
+    series: Option<String>,
+    number: Option<u32>,
+    count: Option<u32>,
+    summary: String,
+    writer: String,
+    tags: Vec<String>,
+    web: String,
+    date: Option<(i32, u32, u32)>,
+    age_rating: Option<&'static str>,
+    language: String,
+}
+
+impl ComicInfo {
+    pub fn new<T: Into<String>>(title: T) -> Self {
+        ComicInfo {
+            title: title.into(),
+            language: String::from("ja"),
+            ..Default::default()
+        }
+    }
+
+    /// Metadata of a single work: its title, series, caption, artist, tags, link, creation date
+    /// and age rating.
+    pub fn from_illustration(illustration: &Illustration) -> Self {
+        let mut info = ComicInfo::new(illustration.title().as_str())
+            .set_summary(caption_to_text(illustration.caption()))
+            .set_writer(illustration.user().name().as_str())
+            .set_tags(
+                illustration
+                    .tags()
+                    .iter()
+                    .map(|tag| tag.name().clone())
+                    .collect(),
+            )
+            .set_web(illustration.artwork_url())
+            .set_date(illustration.create_date());
+        if let Some(series) = illustration.series() {
+            info = info.set_series(series.title());
+        }
+        info.age_rating = match illustration.x_restrict() {
+            0 => None,
+            1 => Some("R18+"),
+            _ => Some("Adults Only 18+"),
+        };
+        info
+    }
+
+    pub fn set_series<T: Into<String>>(mut self, value: T) -> Self {
+        self.series = Some(value.into());
+        self
+    }
+
+    /// Position of the work in its series, starting at 1.
+    pub fn set_number(mut self, value: u32) -> Self {
+        self.number = Some(value);
+        self
+    }
+
+    /// Amount of works in the series.
+    pub fn set_count(mut self, value: u32) -> Self {
+        self.count = Some(value);
+        self
+    }
+
+    pub fn set_summary<T: Into<String>>(mut self, value: T) -> Self {
+        self.summary = value.into();
+        self
+    }
+
+    pub fn set_writer<T: Into<String>>(mut self, value: T) -> Self {
+        self.writer = value.into();
+        self
+    }
+
+    pub fn set_tags(mut self, value: Vec<String>) -> Self {
+        self.tags = value;
+        self
+    }
+
+    pub fn set_web<T: Into<String>>(mut self, value: T) -> Self {
+        self.web = value.into();
+        self
+    }
+
+    /// Creation date, formatted as in pixiv responses (RFC 3339). Other formats are ignored.
+    pub fn set_date(mut self, value: &str) -> Self {
+        self.date = chrono::DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|date| (date.year(), date.month(), date.day()));
+        self
+    }
+
+    /// ISO 639 language code, `ja` by default.
+    pub fn set_language<T: Into<String>>(mut self, value: T) -> Self {
+        self.language = value.into();
+        self
+    }
+
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn series(&self) -> Option<&String> {
+        self.series.as_ref()
+    }
+
+    /// Renders ComicInfo.xml for a book of `page_count` pages.
+    pub fn to_xml(&self, page_count: usize) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n",
+            "<ComicInfo xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\"",
+            " xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n"
+        ));
+        let mut element = |name: &str, value: &str| {
+            if !value.is_empty() {
+                let _ = writeln!(xml, "  <{0}>{1}</{0}>", name, escape_html(value));
+            }
+        };
+
+        element("Title", &self.title);
+        element("Series", self.series.as_deref().unwrap_or_default());
+        element(
+            "Number",
+            &self.number.map(|n| n.to_string()).unwrap_or_default(),
+        );
+        element(
+            "Count",
+            &self.count.map(|n| n.to_string()).unwrap_or_default(),
+        );
+        element("Summary", &self.summary);
+        if let Some((year, month, day)) = self.date {
+            element("Year", &year.to_string());
+            element("Month", &month.to_string());
+            element("Day", &day.to_string());
+        }
+        element("Writer", &self.writer);
+        element("Publisher", "pixiv");
+        element("Tags", &self.tags.join(","));
+        element("Web", &self.web);
+        element("PageCount", &page_count.to_string());
+        element("LanguageISO", &self.language);
+        element("Manga", "Yes");
+        element("AgeRating", self.age_rating.unwrap_or_default());
+
+        xml.push_str("  <Pages>\n");
+        for page in 0..page_count {
+            if page == 0 {
+                xml.push_str("    <Page Image=\"0\" Type=\"FrontCover\" />\n");
+            } else {
+                let _ = writeln!(xml, "    <Page Image=\"{}\" />", page);
+            }
+        }
+        xml.push_str("  </Pages>\n</ComicInfo>\n");
+        xml
+    }
+}
+
+/// A CBZ under construction. Pages are stored in the order they are added, named by their
+/// position so that readers sorting by file name keep it.
+#[derive(Debug, Clone)]
+pub struct Cbz {
+    info: ComicInfo,
+    pages: Vec<(String, Vec<u8>)>,
+}
+
+impl Cbz {
+    pub fn new(info: ComicInfo) -> Self {
+        Cbz {
+            info,
+            pages: Vec::new(),
+        }
+    }
+
+    pub fn info(&self) -> &ComicInfo {
+        &self.info
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Adds a page. `file_name` is only used for its extension.
+    pub fn add_page(&mut self, file_name: &str, data: Vec<u8>) {
+        let extension = file_name
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_ascii_lowercase())
+            .unwrap_or_else(|| String::from("jpg"));
+        self.pages.push((extension, data));
+    }
+
+    /// Adds a page from a file, such as one downloaded by a `DownloadQueue`.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), CbzError> {
+        let path = path.as_ref();
+        self.add_page(&path.to_string_lossy(), fs::read(path)?);
+        Ok(())
+    }
+
+    /// Returns the name of the page at `index` in the archive.
+    pub fn page_name(&self, index: usize) -> Option<String> {
+        self.pages
+            .get(index)
+            .map(|(extension, _)| self.numbered_name(index, extension))
+    }
+
+    /// Pages are numbered from 1, with enough leading zeros for every page to sort by name.
+    fn numbered_name(&self, index: usize, extension: &str) -> String {
+        let digits = self.pages.len().to_string().len().max(3);
+        format!("{:0width$}.{}", index + 1, extension, width = digits)
+    }
+
+    /// Writes the archive and returns the writer.
+    pub fn write<W>(&self, mut writer: W) -> Result<W, CbzError>
+    where
+        W: Write,
+    {
+        // The archive is assembled in memory since its central directory needs seeking.
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        // Images are already compressed.
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        for (index, (extension, data)) in self.pages.iter().enumerate() {
+            zip.start_file(self.numbered_name(index, extension), stored)?;
+            zip.write_all(data)?;
+        }
+        zip.start_file("ComicInfo.xml", deflated)?;
+        zip.write_all(self.info.to_xml(self.pages.len()).as_bytes())?;
+
+        let archive = zip.finish()?.into_inner();
+        writer.write_all(&archive)?;
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+/// Packages the original pages of a manga, downloading them with `client`.
+pub fn export_illustration<W>(
+    client: &PixivClient,
+    illustration: &Illustration,
+    writer: W,
+) -> Result<W, CbzError>
+where
+    W: Write,
+{
+    let mut cbz = Cbz::new(ComicInfo::from_illustration(illustration));
+    add_pages(&mut cbz, client, illustration)?;
+    cbz.write(writer)
+}
+
+/// Packages the works of a manga series into one book, in the given order. The metadata is
+/// the one of the first work, titled after the series, with the tags of every work.
+/// Fails with `CbzError::Empty` when `illustrations` is empty.
+pub fn export_series<W>(
+    client: &PixivClient,
+    illustrations: &[Illustration],
+    writer: W,
+) -> Result<W, CbzError>
+where
+    W: Write,
+{
+    let mut info = match illustrations.first() {
+        Some(first) => ComicInfo::from_illustration(first),
+        None => return Err(CbzError::Empty),
+    };
+    if let Some(series) = info.series.clone() {
+        info.title = series;
+    }
+    let mut tags: Vec<String> = Vec::new();
+    for tag in illustrations
+        .iter()
+        .flat_map(|illustration| illustration.tags())
+    {
+        if !tags.contains(tag.name()) {
+            tags.push(tag.name().clone());
+        }
+    }
+    info.tags = tags;
+
+    let mut cbz = Cbz::new(info);
+    for illustration in illustrations {
+        add_pages(&mut cbz, client, illustration)?;
+    }
+    cbz.write(writer)
+}
+
+fn add_pages(
+    cbz: &mut Cbz,
+    client: &PixivClient,
+    illustration: &Illustration,
+) -> Result<(), CbzError> {
+    let downloader = client.downloader();
+    for url in illustration
+        .page_urls(ImageSize::Original)
+        .into_iter()
+        .flatten()
+    {
+        cbz.add_page(url, downloader.fetch(url)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use crate::constants::IMAGE_REFERER;
+use crate::download::report::{DownloadReport, FileReport};
+use crate::errors::DownloadError;
+
+use http::StatusCode;
+use reqwest::blocking::Response;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Downloader for images and other files hosted on pximg.net.
+///
+/// Responses are streamed to `<file name>.part` next to the destination, synced to disk and
+/// then renamed over the destination, so an interrupted download never leaves a truncated file
+/// under the final name. When the server announces a Content-Length the amount of bytes
+/// received is checked against it.
+///
+/// An interrupted download keeps its part file along with the ETag/Last-Modified of the
+/// response in `<file name>.part.json`. The next download of the same url resumes from the end
+/// of the part file with a `Range` request guarded by `If-Range`, so a file that changed on
+/// the server in the meantime is downloaded again from scratch, as is a file from a server
+/// without range support.
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: reqwest::blocking::Client,
+    referer: String,
+    resume: bool,
+}
+
+/// Validators of the response a part file was written from.
+#[derive(Debug, Serialize, Deserialize)]
+struct PartValidator {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl PartValidator {
+    fn from_response(url: &str, response: &Response) -> Option<Self> {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let validator = PartValidator {
+            url: String::from(url),
+            // Weak ETags can't be used in If-Range.
+            etag: header(header::ETAG).filter(|etag| !etag.starts_with("W/")),
+            last_modified: header(header::LAST_MODIFIED),
+        };
+        validator.if_range().is_some().then_some(validator)
+    }
+
+    fn if_range(&self) -> Option<&String> {
+        self.etag.as_ref().or(self.last_modified.as_ref())
+    }
+}
+
+impl Downloader {
+    pub fn new(client: reqwest::blocking::Client) -> Self {
+        Downloader {
+            client,
+            referer: String::from(IMAGE_REFERER),
+            resume: true,
+        }
+    }
+
+    /// pximg.net refuses requests without a pixiv referer, this defaults to `IMAGE_REFERER`.
+    pub fn set_referer<T: ToString>(self, referer: T) -> Self {
+        Downloader {
+            referer: referer.to_string(),
+            ..self
+        }
+    }
+
+    /// Whether interrupted downloads are kept and resumed, enabled by default.
+    pub fn set_resume(self, resume: bool) -> Self {
+        Downloader { resume, ..self }
+    }
+
+    pub fn client(&self) -> &reqwest::blocking::Client {
+        &self.client
+    }
+
+    pub fn referer(&self) -> &String {
+        &self.referer
+    }
+
+    pub fn resume(&self) -> bool {
+        self.resume
+    }
+
+    /// Fetches `url` into memory, for small files that are used right away such as thumbnails
+    /// or pages packaged into an archive.
+    pub fn fetch(&self, url: &str) -> Result<Vec<u8>, reqwest::Error> {
+        let response = self
+            .client
+            .get(url)
+            .header(header::REFERER, self.referer.as_str())
+            .send()?
+            .error_for_status()?;
+        Ok(response.bytes()?.to_vec())
+    }
+
+    /// Returns the path of the temporary file used while downloading to `dest`.
+    pub fn part_path(dest: &Path) -> PathBuf {
+        with_suffix(dest, ".part")
+    }
+
+    /// Returns the path of the file holding the validators of the part file of `dest`.
+    pub fn validator_path(dest: &Path) -> PathBuf {
+        with_suffix(dest, ".part.json")
+    }
+
+    /// Downloads `url` to `dest`, returning the size of the file.
+    pub fn download(&self, url: &str, dest: &Path) -> Result<u64, DownloadError> {
+        self.download_with_progress(url, dest, |_, _| true)
+    }
+
+    /// Same as `download`, calling `progress` with the amount of bytes of the file on disk and
+    /// the expected size every time a chunk is written. Returning `false` from `progress`
+    /// aborts the download with `DownloadError::Cancelled`, keeping the part file for resuming.
+    pub fn download_with_progress<F>(
+        &self,
+        url: &str,
+        dest: &Path,
+        mut progress: F,
+    ) -> Result<u64, DownloadError>
+    where
+        F: FnMut(u64, Option<u64>) -> bool,
+    {
+        let part = Self::part_path(dest);
+        let validator = Self::validator_path(dest);
+        let result = self.download_to(url, &part, &validator, dest, &mut progress);
+        let keep = match &result {
+            Ok(_) => false,
+            Err(e) => self.resume && is_resumable(e) && validator.exists(),
+        };
+        if !keep {
+            let _ = fs::remove_file(&part);
+            let _ = fs::remove_file(&validator);
+        }
+        result
+    }
+
+    /// Downloads every `(url, dest)` pair, one after the other. A failure does not stop the
+    /// remaining downloads, it is recorded in the report instead.
+    pub fn download_all<I, U>(&self, targets: I) -> DownloadReport
+    where
+        I: IntoIterator<Item = (U, PathBuf)>,
+        U: AsRef<str>,
+    {
+        targets
+            .into_iter()
+            .map(|(url, dest)| {
+                let result = self.download(url.as_ref(), &dest);
+                FileReport::new(url.as_ref(), dest, result)
+            })
+            .collect()
+    }
+
+    /// Returns the size of the part file and its validator if it can be resumed.
+    fn resumable_part(
+        &self,
+        url: &str,
+        part: &Path,
+        validator: &Path,
+    ) -> Option<(u64, PartValidator)> {
+        if !self.resume {
+            return None;
+        }
+        let offset = fs::metadata(part).ok()?.len();
+        let validator: PartValidator = serde_json::from_slice(&fs::read(validator).ok()?).ok()?;
+        if offset == 0 || validator.url != url || validator.if_range().is_none() {
+            return None;
+        }
+        Some((offset, validator))
+    }
+
+    fn download_to(
+        &self,
+        url: &str,
+        part: &Path,
+        validator: &Path,
+        dest: &Path,
+        progress: &mut dyn FnMut(u64, Option<u64>) -> bool,
+    ) -> Result<u64, DownloadError> {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let resume = self.resumable_part(url, part, validator);
+
+        let mut request = self
+            .client
+            .get(url)
+            .header(header::REFERER, self.referer.as_str());
+        if let Some((offset, ref previous)) = resume {
+            request = request
+                .header(header::RANGE, format!("bytes={}-", offset))
+                .header(header::IF_RANGE, previous.if_range().unwrap().as_str());
+        }
+        let response = request.send()?;
+
+        let offset = match resume {
+            Some((offset, _)) if response.status() == StatusCode::PARTIAL_CONTENT => {
+                if content_range_start(&response) != Some(offset) {
+                    return self.restart(url, part, validator, dest, progress);
+                }
+                offset
+            }
+            Some(_) if response.status() == StatusCode::RANGE_NOT_SATISFIABLE => {
+                return self.restart(url, part, validator, dest, progress);
+            }
+            _ => 0,
+        };
+        let mut response = response.error_for_status()?;
+
+        let file = if offset > 0 {
+            OpenOptions::new().append(true).open(part)?
+        } else {
+            match PartValidator::from_response(url, &response) {
+                Some(current) if self.resume => {
+                    let current = serde_json::to_vec(&current).map_err(std::io::Error::from)?;
+                    fs::write(validator, current)?
+                }
+                _ => {
+                    let _ = fs::remove_file(validator);
+                }
+            }
+            File::create(part)?
+        };
+        let expected = response.content_length().map(|length| offset + length);
+
+        if !progress(offset, expected) {
+            return Err(DownloadError::Cancelled);
+        }
+        let mut writer = ProgressWriter {
+            inner: BufWriter::new(file),
+            written: offset,
+            expected,
+            progress,
+            cancelled: false,
+        };
+        let copied = response.copy_to(&mut writer);
+        if writer.cancelled {
+            return Err(DownloadError::Cancelled);
+        }
+        let written = offset + copied?;
+        let mut writer = writer.inner;
+        writer.flush()?;
+
+        if let Some(expected) = expected {
+            if expected != written {
+                return Err(DownloadError::LengthMismatch { expected, written });
+            }
+        }
+
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(part, dest)?;
+        let _ = fs::remove_file(validator);
+        sync_parent(dest);
+        Ok(written)
+    }
+
+    /// Throws away the part file and downloads the whole file again.
+    fn restart(
+        &self,
+        url: &str,
+        part: &Path,
+        validator: &Path,
+        dest: &Path,
+        progress: &mut dyn FnMut(u64, Option<u64>) -> bool,
+    ) -> Result<u64, DownloadError> {
+        fs::remove_file(part)?;
+        let _ = fs::remove_file(validator);
+        self.download_to(url, part, validator, dest, progress)
+    }
+}
+
+/// Forwards writes to `inner`, reporting the amount of bytes written so far to `progress`.
+struct ProgressWriter<'a, W: Write> {
+    inner: W,
+    written: u64,
+    expected: Option<u64>,
+    progress: &'a mut dyn FnMut(u64, Option<u64>) -> bool,
+    cancelled: bool,
+}
+
+impl<W: Write> Write for ProgressWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        if !(self.progress)(self.written, self.expected) {
+            self.cancelled = true;
+            return Err(io::Error::other("download cancelled"));
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Whether the part file left behind by this error can be continued.
+fn is_resumable(e: &DownloadError) -> bool {
+    match e {
+        DownloadError::Io(_) | DownloadError::Cancelled => true,
+        DownloadError::Request(e) => !e.is_status(),
+        DownloadError::LengthMismatch { expected, written } => written < expected,
+        DownloadError::Duplicate(_) => false,
+    }
+}
+
+/// Returns the first byte position of a `Content-Range: bytes <start>-<end>/<size>` header.
+fn content_range_start(response: &Response) -> Option<u64> {
+    let range = response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let range = range.trim().strip_prefix("bytes")?.trim_start();
+    range.split('-').next()?.trim().parse().ok()
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Makes the rename durable. Directories can't be opened for syncing on every platform, so
+/// this is best effort.
+fn sync_parent(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
@@ -0,0 +1,355 @@
+use crate::errors::MetadataError;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::utils::{caption_to_text, escape_html};
+
+use std::fs;
+use std::path::Path;
+
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+/// Largest payload of a JPEG segment, its length field counts itself.
+const MAX_SEGMENT_LENGTH: usize = 0xFFFF - 2;
+
+/// Metadata of a work written into its downloaded images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedMetadata {
+    title: String,
+    artist_name: String,
+    artist_id: u32,
+    tags: Vec<String>,
+    source_url: String,
+    create_date: String,
+    description: String,
+}
+
+impl EmbeddedMetadata {
+    /// Tags are the tag names followed by their translations.
+    pub fn from_illustration(illustration: &Illustration) -> Self {
+        let mut tags: Vec<String> = illustration
+            .tags()
+            .iter()
+            .map(|tag| tag.name().clone())
+            .collect();
+        for tag in illustration.tags() {
+            for translated in tag.translated_name().iter().flatten() {
+                if !tags.contains(translated) {
+                    tags.push(translated.clone());
+                }
+            }
+        }
+
+        EmbeddedMetadata {
+            title: illustration.title().clone(),
+            artist_name: illustration.user().name().clone(),
+            artist_id: illustration.user().id(),
+            tags,
+            source_url: illustration.artwork_url(),
+            create_date: illustration.create_date().clone(),
+            description: caption_to_text(illustration.caption()),
+        }
+    }
+
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn artist_name(&self) -> &String {
+        &self.artist_name
+    }
+
+    pub fn artist_id(&self) -> u32 {
+        self.artist_id
+    }
+
+    pub fn tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    pub fn source_url(&self) -> &String {
+        &self.source_url
+    }
+
+    /// ISO 8601, as returned by the API.
+    pub fn create_date(&self) -> &String {
+        &self.create_date
+    }
+
+    pub fn description(&self) -> &String {
+        &self.description
+    }
+
+    /// Returns the XMP packet holding the metadata.
+    ///
+    /// The title, artist, tags, source url and caption are written to Dublin Core
+    /// (`dc:title`, `dc:creator`, `dc:subject`, `dc:source`, `dc:description`), the artist page
+    /// to `dc:relation` and the creation date to `xmp:CreateDate`.
+    pub fn to_xmp(&self) -> String {
+        let tags: String = self
+            .tags
+            .iter()
+            .map(|tag| format!("<rdf:li>{}</rdf:li>", escape_html(tag)))
+            .collect();
+        format!(
+            concat!(
+                "<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>",
+                "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">",
+                "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+                "<rdf:Description rdf:about=\"\"",
+                " xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+                " xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">",
+                "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>",
+                "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+                "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>",
+                "<dc:subject><rdf:Bag>{}</rdf:Bag></dc:subject>",
+                "<dc:source>{}</dc:source>",
+                "<dc:relation><rdf:Bag><rdf:li>https://www.pixiv.net/users/{}</rdf:li></rdf:Bag></dc:relation>",
+                "<xmp:CreateDate>{}</xmp:CreateDate>",
+                "</rdf:Description>",
+                "</rdf:RDF>",
+                "</x:xmpmeta>",
+                "<?xpacket end=\"w\"?>"
+            ),
+            escape_html(&self.title),
+            escape_html(&self.artist_name),
+            escape_html(&self.description),
+            tags,
+            escape_html(&self.source_url),
+            self.artist_id,
+            escape_html(&self.create_date),
+        )
+    }
+
+    /// Returns the EXIF creation date, `YYYY:MM:DD HH:MM:SS` in the time zone of the API.
+    fn exif_date(&self) -> Option<String> {
+        let date = chrono::DateTime::parse_from_rfc3339(&self.create_date).ok()?;
+        Some(date.format("%Y:%m:%d %H:%M:%S").to_string())
+    }
+
+    /// Returns the TIFF structure of an EXIF segment holding the metadata, in IFD0:
+    /// ImageDescription, Artist, DateTime and the Windows XPTitle, XPAuthor, XPKeywords and
+    /// XPComment (the source url) tags. Text tags are UTF-8, the Windows ones UTF-16.
+    fn to_exif(&self) -> Vec<u8> {
+        let ascii = |text: &str| {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.push(0);
+            (2u16, bytes)
+        };
+        let utf16 = |text: &str| {
+            let mut bytes: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            bytes.extend_from_slice(&[0, 0]);
+            (1u16, bytes)
+        };
+
+        let mut entries: Vec<(u16, (u16, Vec<u8>))> = vec![
+            (0x010E, ascii(&self.title)),
+            (0x013B, ascii(&self.artist_name)),
+            (0x9C9B, utf16(&self.title)),
+            (0x9C9C, utf16(&self.source_url)),
+            (0x9C9D, utf16(&self.artist_name)),
+            (0x9C9E, utf16(&self.tags.join(";"))),
+        ];
+        if let Some(date) = self.exif_date() {
+            entries.push((0x0132, ascii(&date)));
+        }
+        entries.sort_by_key(|(tag, _)| *tag);
+
+        let ifd_length = 2 + entries.len() * 12 + 4;
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        let mut data = Vec::new();
+        tiff.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, (kind, value)) in &entries {
+            tiff.extend_from_slice(&tag.to_le_bytes());
+            tiff.extend_from_slice(&kind.to_le_bytes());
+            tiff.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            if value.len() <= 4 {
+                let mut inline = value.clone();
+                inline.resize(4, 0);
+                tiff.extend_from_slice(&inline);
+            } else {
+                let offset = 8 + ifd_length + data.len();
+                tiff.extend_from_slice(&(offset as u32).to_le_bytes());
+                data.extend_from_slice(value);
+                if data.len() % 2 == 1 {
+                    data.push(0);
+                }
+            }
+        }
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(&data);
+        tiff
+    }
+}
+
+/// Returns the image with the metadata written into it, without touching the pixel data.
+///
+/// JPEG files get an EXIF and an XMP APP1 segment, PNG files an iTXt chunk with the XMP packet
+/// and iTXt chunks with the `Title`, `Author`, `Description`, `Creation Time` and `Source`
+/// keywords. Metadata written before by this function is replaced, so embedding twice gives
+/// the same file.
+pub fn embed(image: &[u8], metadata: &EmbeddedMetadata) -> Result<Vec<u8>, MetadataError> {
+    if image.starts_with(&JPEG_SOI) {
+        embed_jpeg(image, metadata)
+    } else if image.starts_with(&PNG_SIGNATURE) {
+        embed_png(image, metadata)
+    } else {
+        Err(MetadataError::UnsupportedFormat)
+    }
+}
+
+/// Embeds the metadata into the image at `path`. The file is replaced atomically.
+pub fn embed_file<P: AsRef<Path>>(
+    path: P,
+    metadata: &EmbeddedMetadata,
+) -> Result<(), MetadataError> {
+    let path = path.as_ref();
+    let image = embed(&fs::read(path)?, metadata)?;
+
+    let mut temporary = path.as_os_str().to_os_string();
+    temporary.push(".metadata");
+    let temporary = Path::new(&temporary);
+    let written = fs::write(temporary, image)
+        .and_then(|_| fs::File::open(temporary)?.sync_all())
+        .and_then(|_| fs::rename(temporary, path));
+    if written.is_err() {
+        let _ = fs::remove_file(temporary);
+    }
+    Ok(written?)
+}
+
+fn jpeg_segment(marker: u8, header: &[u8], payload: &[u8]) -> Result<Vec<u8>, MetadataError> {
+    let length = header.len() + payload.len();
+    if length > MAX_SEGMENT_LENGTH {
+        return Err(MetadataError::TooLarge);
+    }
+    let mut segment = vec![0xFF, marker];
+    segment.extend_from_slice(&((length + 2) as u16).to_be_bytes());
+    segment.extend_from_slice(header);
+    segment.extend_from_slice(payload);
+    Ok(segment)
+}
+
+fn embed_jpeg(image: &[u8], metadata: &EmbeddedMetadata) -> Result<Vec<u8>, MetadataError> {
+    let exif = jpeg_segment(0xE1, EXIF_HEADER, &metadata.to_exif())?;
+    let xmp = jpeg_segment(0xE1, XMP_HEADER, metadata.to_xmp().as_bytes())?;
+
+    let mut output = Vec::with_capacity(image.len() + exif.len() + xmp.len());
+    output.extend_from_slice(&JPEG_SOI);
+    let mut inserted = false;
+    let mut position = 2;
+    loop {
+        if position + 4 > image.len() || image[position] != 0xFF {
+            return Err(MetadataError::Malformed("truncated JPEG header"));
+        }
+        let marker = image[position + 1];
+        if marker == 0xFF {
+            // Fill byte.
+            position += 1;
+            continue;
+        }
+        // The JFIF segment has to stay first, everything else goes after the new segments.
+        if marker != 0xE0 && !inserted {
+            output.extend_from_slice(&exif);
+            output.extend_from_slice(&xmp);
+            inserted = true;
+        }
+        if marker == 0xDA {
+            output.extend_from_slice(&image[position..]);
+            return Ok(output);
+        }
+
+        let length = u16::from_be_bytes([image[position + 2], image[position + 3]]) as usize;
+        let end = position + 2 + length;
+        if length < 2 || end > image.len() {
+            return Err(MetadataError::Malformed(
+                "JPEG segment past the end of the file",
+            ));
+        }
+        let payload = &image[position + 4..end];
+        let replaced =
+            marker == 0xE1 && (payload.starts_with(EXIF_HEADER) || payload.starts_with(XMP_HEADER));
+        if !replaced {
+            output.extend_from_slice(&image[position..end]);
+        }
+        position = end;
+    }
+}
+
+fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(kind);
+    hasher.update(data);
+    chunk.extend_from_slice(&hasher.finalize().to_be_bytes());
+    chunk
+}
+
+/// Uncompressed iTXt chunk without language tag.
+fn itxt_chunk(keyword: &str, text: &str) -> Vec<u8> {
+    let mut data = keyword.as_bytes().to_vec();
+    data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    data.extend_from_slice(text.as_bytes());
+    png_chunk(b"iTXt", &data)
+}
+
+fn embed_png(image: &[u8], metadata: &EmbeddedMetadata) -> Result<Vec<u8>, MetadataError> {
+    let mut texts = vec![
+        (XMP_KEYWORD, metadata.to_xmp()),
+        ("Title", metadata.title.clone()),
+        ("Author", metadata.artist_name.clone()),
+        ("Source", metadata.source_url.clone()),
+        ("Creation Time", metadata.create_date.clone()),
+    ];
+    if !metadata.description.is_empty() {
+        texts.push(("Description", metadata.description.clone()));
+    }
+    let keywords: Vec<&str> = texts.iter().map(|(keyword, _)| *keyword).collect();
+    let chunks: Vec<u8> = texts
+        .iter()
+        .flat_map(|(keyword, text)| itxt_chunk(keyword, text))
+        .collect();
+
+    let mut output = Vec::with_capacity(image.len() + chunks.len());
+    output.extend_from_slice(&PNG_SIGNATURE);
+    let mut inserted = false;
+    let mut position = PNG_SIGNATURE.len();
+    while position < image.len() {
+        if position + 12 > image.len() {
+            return Err(MetadataError::Malformed("truncated PNG chunk"));
+        }
+        let length = u32::from_be_bytes(image[position..position + 4].try_into().unwrap()) as usize;
+        let kind = &image[position + 4..position + 8];
+        let end = position + 12 + length;
+        if end > image.len() {
+            return Err(MetadataError::Malformed(
+                "PNG chunk past the end of the file",
+            ));
+        }
+        let data = &image[position + 8..position + 8 + length];
+
+        if (kind == b"IDAT" || kind == b"IEND") && !inserted {
+            output.extend_from_slice(&chunks);
+            inserted = true;
+        }
+        let replaced = matches!(kind, b"tEXt" | b"zTXt" | b"iTXt")
+            && data
+                .split(|byte| *byte == 0)
+                .next()
+                .is_some_and(|keyword| keywords.iter().any(|k| k.as_bytes() == keyword));
+        if !replaced {
+            output.extend_from_slice(&image[position..end]);
+        }
+        position = end;
+    }
+
+    if !inserted {
+        return Err(MetadataError::Malformed("PNG without image data"));
+    }
+    Ok(output)
+}
@@ -0,0 +1,11 @@
+pub mod archive;
+pub mod cbz;
+pub mod downloader;
+pub mod metadata;
+#[cfg(feature = "phash")]
+pub mod phash;
+pub mod queue;
+pub mod report;
+pub mod sidecar;
+pub mod store;
+pub mod template;
@@ -0,0 +1,304 @@
+use crate::download::archive::ArchiveKey;
+use crate::enums::HashAlgorithm;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// A 64 bit perceptual hash. Similar images have hashes differing by few bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageHash {
+    algorithm: HashAlgorithm,
+    bits: u64,
+}
+
+impl ImageHash {
+    pub fn new(algorithm: HashAlgorithm, bits: u64) -> Self {
+        ImageHash { algorithm, bits }
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Returns the amount of differing bits, or `None` for hashes of different algorithms.
+    /// Up to about 10 usually means the same picture.
+    pub fn distance(&self, other: &ImageHash) -> Option<u32> {
+        (self.algorithm == other.algorithm).then(|| (self.bits ^ other.bits).count_ones())
+    }
+}
+
+impl fmt::Display for ImageHash {
+    /// `<algorithm>:<16 hex digits>`, e.g. `phash:c3d1e0f0f0e0d1c3`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{:016x}", self.algorithm.as_str(), self.bits)
+    }
+}
+
+impl FromStr for ImageHash {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid image hash: {:?}", s),
+            )
+        };
+        let (algorithm, bits) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let algorithm = match algorithm {
+            "ahash" => HashAlgorithm::Average,
+            "dhash" => HashAlgorithm::Difference,
+            "phash" => HashAlgorithm::Perceptual,
+            _ => return Err(invalid()),
+        };
+        if bits.len() != 16 {
+            return Err(invalid());
+        }
+        let bits = u64::from_str_radix(bits, 16).map_err(|_| invalid())?;
+        Ok(ImageHash::new(algorithm, bits))
+    }
+}
+
+/// Computes the hash of an image.
+pub fn hash_image(image: &DynamicImage, algorithm: HashAlgorithm) -> ImageHash {
+    let bits = match algorithm {
+        HashAlgorithm::Average => average_hash(image),
+        HashAlgorithm::Difference => difference_hash(image),
+        HashAlgorithm::Perceptual => perceptual_hash(image),
+    };
+    ImageHash::new(algorithm, bits)
+}
+
+/// Computes the hash of an encoded image.
+pub fn hash_bytes(data: &[u8], algorithm: HashAlgorithm) -> Result<ImageHash, image::ImageError> {
+    Ok(hash_image(&image::load_from_memory(data)?, algorithm))
+}
+
+/// Computes the hash of an image file.
+pub fn hash_file<P: AsRef<Path>>(
+    path: P,
+    algorithm: HashAlgorithm,
+) -> Result<ImageHash, image::ImageError> {
+    Ok(hash_image(&image::open(path)?, algorithm))
+}
+
+fn grayscale(image: &DynamicImage, width: u32, height: u32) -> GrayImage {
+    image
+        .resize_exact(width, height, FilterType::Triangle)
+        .into_luma8()
+}
+
+fn average_hash(image: &DynamicImage) -> u64 {
+    let pixels = grayscale(image, 8, 8).into_raw();
+    let mean = pixels.iter().map(|p| *p as u32).sum::<u32>() / 64;
+    pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| **p as u32 > mean)
+        .fold(0, |bits, (i, _)| bits | 1 << i)
+}
+
+fn difference_hash(image: &DynamicImage) -> u64 {
+    let pixels = grayscale(image, 9, 8);
+    let mut bits = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            if pixels.get_pixel(x, y)[0] > pixels.get_pixel(x + 1, y)[0] {
+                bits |= 1 << (y * 8 + x);
+            }
+        }
+    }
+    bits
+}
+
+fn perceptual_hash(image: &DynamicImage) -> u64 {
+    const SIZE: usize = 32;
+    let pixels: Vec<f64> = grayscale(image, SIZE as u32, SIZE as u32)
+        .into_raw()
+        .into_iter()
+        .map(f64::from)
+        .collect();
+
+    // Only the 8x8 lowest frequencies of the 2D DCT-II are needed.
+    let cosines: Vec<f64> = (0..8)
+        .flat_map(|u| {
+            (0..SIZE).map(move |x| {
+                ((2 * x + 1) as f64 * u as f64 * std::f64::consts::PI / (2 * SIZE) as f64).cos()
+            })
+        })
+        .collect();
+    let mut rows = vec![0f64; 8 * SIZE];
+    for y in 0..SIZE {
+        for u in 0..8 {
+            rows[u * SIZE + y] = (0..SIZE)
+                .map(|x| pixels[y * SIZE + x] * cosines[u * SIZE + x])
+                .sum();
+        }
+    }
+    let mut coefficients = [0f64; 64];
+    for v in 0..8 {
+        for u in 0..8 {
+            coefficients[v * 8 + u] = (0..SIZE)
+                .map(|y| rows[u * SIZE + y] * cosines[v * SIZE + y])
+                .sum();
+        }
+    }
+
+    // The DC coefficient is the overall brightness, it is left out of the median.
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = (sorted[31] + sorted[32]) / 2.0;
+    coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c > median)
+        .fold(0, |bits, (i, _)| bits | 1 << i)
+}
+
+#[derive(Debug, Default)]
+struct IndexState {
+    entries: Vec<(ArchiveKey, u64)>,
+    file: Option<File>,
+}
+
+/// Index of image hashes to look up near-duplicates, see `DownloadQueue::set_duplicate_filter`.
+///
+/// An index opened from a file appends every new hash to it right away, one
+/// `<hash> <key>` per line.
+#[derive(Debug)]
+pub struct HashIndex {
+    algorithm: HashAlgorithm,
+    state: Mutex<IndexState>,
+}
+
+impl HashIndex {
+    /// An index that is not saved anywhere.
+    pub fn in_memory(algorithm: HashAlgorithm) -> Self {
+        HashIndex {
+            algorithm,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Opens the index at `path`, creating it if it doesn't exist. Hashes of other algorithms
+    /// in the file are ignored.
+    pub fn open<P: AsRef<Path>>(path: P, algorithm: HashAlgorithm) -> io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        let mut entries = Vec::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (hash, key) = line.split_once(' ').ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid index entry: {:?}", line),
+                )
+            })?;
+            let hash: ImageHash = hash.parse()?;
+            if hash.algorithm() == algorithm {
+                entries.push((key.parse()?, hash.bits()));
+            }
+        }
+
+        Ok(HashIndex {
+            algorithm,
+            state: Mutex::new(IndexState {
+                entries,
+                file: Some(file),
+            }),
+        })
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds the hash of a page. Hashes of another algorithm than the index's are refused.
+    pub fn insert(&self, key: ArchiveKey, hash: ImageHash) -> io::Result<()> {
+        if hash.algorithm() != self.algorithm {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("expected a {} hash", self.algorithm.as_str()),
+            ));
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Some(file) = state.file.as_mut() {
+            writeln!(file, "{} {}", hash, key)?;
+            file.flush()?;
+        }
+        state.entries.push((key, hash.bits()));
+        Ok(())
+    }
+
+    /// Returns the pages whose hash is at most `max_distance` bits away, closest first.
+    pub fn find(&self, hash: &ImageHash, max_distance: u32) -> Vec<(ArchiveKey, u32)> {
+        if hash.algorithm() != self.algorithm {
+            return Vec::new();
+        }
+        let mut found: Vec<(ArchiveKey, u32)> = self
+            .state
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|(key, bits)| (*key, (bits ^ hash.bits()).count_ones()))
+            .filter(|(_, distance)| *distance <= max_distance)
+            .collect();
+        found.sort_by_key(|(key, distance)| (*distance, *key));
+        found.dedup_by_key(|(key, _)| *key);
+        found
+    }
+
+    pub fn contains_near(&self, hash: &ImageHash, max_distance: u32) -> bool {
+        !self.find(hash, max_distance).is_empty()
+    }
+
+    /// Returns every pair of pages from different illustrations whose hashes are at most
+    /// `max_distance` bits apart.
+    pub fn near_duplicates(&self, max_distance: u32) -> Vec<(ArchiveKey, ArchiveKey, u32)> {
+        let state = self.state.lock().unwrap();
+        let entries = &state.entries;
+        let mut pairs = Vec::new();
+        for (i, (key, bits)) in entries.iter().enumerate() {
+            for (other, other_bits) in &entries[i + 1..] {
+                let distance = (bits ^ other_bits).count_ones();
+                if distance <= max_distance && key.illust_id() != other.illust_id() {
+                    pairs.push((*key, *other, distance));
+                }
+            }
+        }
+        pairs.sort_by_key(|(key, other, distance)| (*distance, *key, *other));
+        pairs
+    }
+}
@@ -0,0 +1,581 @@
+use crate::download::archive::{ArchiveKey, DownloadArchive};
+use crate::download::downloader::Downloader;
+use crate::download::metadata::{embed_file, EmbeddedMetadata};
+#[cfg(feature = "phash")]
+use crate::download::phash::{hash_bytes, hash_file, HashIndex};
+use crate::download::report::{DownloadReport, FileReport};
+use crate::download::sidecar::SidecarWriter;
+use crate::download::store::ContentStore;
+use crate::download::template::DownloadLayout;
+use crate::enums::{ImageSize, LinkKind};
+use crate::errors::{DownloadError, MetadataError};
+use crate::pixiv::helper_structs::illustration::Illustration;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+#[cfg(feature = "phash")]
+use std::sync::OnceLock;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Events emitted while a `DownloadQueue` runs. `id` is the value returned when the file was
+/// pushed onto the queue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadEvent {
+    Queued {
+        id: usize,
+        url: String,
+        path: PathBuf,
+    },
+    Started {
+        id: usize,
+    },
+    /// `downloaded` counts every byte of the file on disk, including resumed ones.
+    Progress {
+        id: usize,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    Finished {
+        id: usize,
+        size: u64,
+    },
+    /// The reason is the `Display` of the `DownloadError`, the error itself is in the report.
+    Failed {
+        id: usize,
+        reason: String,
+    },
+    /// The work of the file looks like the one of `duplicate_of`, the file was not downloaded.
+    Skipped {
+        id: usize,
+        duplicate_of: ArchiveKey,
+    },
+    /// The metadata could not be written into the downloaded file. The download still counts
+    /// as finished, the file is kept without metadata.
+    EmbedFailed {
+        id: usize,
+        reason: String,
+    },
+    /// The hash of the file could not be added to the hash index. The download still counts
+    /// as finished.
+    IndexFailed {
+        id: usize,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    paused: bool,
+    cancelled: bool,
+}
+
+/// Handle to pause, resume or cancel a running `DownloadQueue` from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct QueueControl {
+    state: Arc<(Mutex<QueueState>, Condvar)>,
+}
+
+impl QueueControl {
+    /// Stops workers from writing more data until `resume` is called. Connections stay open,
+    /// so pausing for long may still get them dropped by the server.
+    pub fn pause(&self) {
+        self.update(|state| state.paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.update(|state| state.paused = false);
+    }
+
+    /// Aborts the running downloads and fails the remaining ones with
+    /// `DownloadError::Cancelled`. Part files of aborted downloads are kept for resuming.
+    pub fn cancel(&self) {
+        self.update(|state| state.cancelled = true);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state.0.lock().unwrap().paused
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.0.lock().unwrap().cancelled
+    }
+
+    fn update<F: FnOnce(&mut QueueState)>(&self, f: F) {
+        let (lock, condvar) = &*self.state;
+        f(&mut lock.lock().unwrap());
+        condvar.notify_all();
+    }
+
+    /// Blocks while paused, returns whether the work should go on.
+    fn wait(&self) -> bool {
+        let (lock, condvar) = &*self.state;
+        let state = condvar
+            .wait_while(lock.lock().unwrap(), |state| {
+                state.paused && !state.cancelled
+            })
+            .unwrap();
+        !state.cancelled
+    }
+}
+
+#[derive(Debug)]
+struct Job {
+    url: String,
+    path: PathBuf,
+    key: Option<ArchiveKey>,
+    metadata: Option<Arc<EmbeddedMetadata>>,
+    #[cfg(feature = "phash")]
+    duplicate_check: Option<Arc<DuplicateCheck>>,
+}
+
+/// Whether a work is a near duplicate, shared by the jobs of its pages. The first worker to
+/// get one of the pages decides it, the others wait for the verdict.
+#[cfg(feature = "phash")]
+#[derive(Debug)]
+struct DuplicateCheck {
+    illust_id: u32,
+    thumbnail: String,
+    duplicate_of: OnceLock<Option<ArchiveKey>>,
+}
+
+/// Sidecars of a work, written once one of its pages is downloaded.
+#[derive(Debug)]
+struct SidecarJob {
+    ids: Vec<usize>,
+    url: String,
+    files: Vec<(PathBuf, String)>,
+}
+
+/// Downloads many files with a bounded amount of workers, see `PixivClient::downloader`.
+#[derive(Debug)]
+pub struct DownloadQueue {
+    downloader: Downloader,
+    workers: usize,
+    jobs: Vec<Job>,
+    control: QueueControl,
+    archive: Option<Arc<DownloadArchive>>,
+    sidecars: SidecarWriter,
+    sidecar_jobs: Vec<SidecarJob>,
+    embed_metadata: bool,
+    store: Option<(Arc<ContentStore>, LinkKind)>,
+    #[cfg(feature = "phash")]
+    hash_index: Option<Arc<HashIndex>>,
+    #[cfg(feature = "phash")]
+    max_distance: Option<u32>,
+}
+
+impl DownloadQueue {
+    pub fn new(downloader: Downloader) -> Self {
+        DownloadQueue {
+            downloader,
+            workers: 4,
+            jobs: Vec::new(),
+            control: QueueControl::default(),
+            archive: None,
+            sidecars: SidecarWriter::default(),
+            sidecar_jobs: Vec::new(),
+            embed_metadata: false,
+            store: None,
+            #[cfg(feature = "phash")]
+            hash_index: None,
+            #[cfg(feature = "phash")]
+            max_distance: None,
+        }
+    }
+
+    /// Amount of files downloaded at the same time, at least one. Defaults to 4.
+    pub fn set_workers(self, workers: usize) -> Self {
+        DownloadQueue {
+            workers: workers.max(1),
+            ..self
+        }
+    }
+
+    /// Pages of illustrations found in the archive are not queued, and the ones downloaded
+    /// are recorded in it. Only files pushed after this is set are checked.
+    pub fn set_archive(self, archive: Arc<DownloadArchive>) -> Self {
+        DownloadQueue {
+            archive: Some(archive),
+            ..self
+        }
+    }
+
+    pub fn archive(&self) -> Option<&Arc<DownloadArchive>> {
+        self.archive.as_ref()
+    }
+
+    /// Illustrations pushed after this is set get their sidecars written in the directory of
+    /// their first page, once at least one of their pages is downloaded. The sidecars are
+    /// listed in the report after the pages.
+    pub fn set_sidecars(self, sidecars: SidecarWriter) -> Self {
+        DownloadQueue { sidecars, ..self }
+    }
+
+    pub fn sidecars(&self) -> &SidecarWriter {
+        &self.sidecars
+    }
+
+    /// Whether the metadata of illustrations pushed after this is set is written into their
+    /// JPEG and PNG pages once downloaded, see `metadata::embed`. Disabled by default.
+    pub fn set_embed_metadata(self, embed_metadata: bool) -> Self {
+        DownloadQueue {
+            embed_metadata,
+            ..self
+        }
+    }
+
+    pub fn embed_metadata(&self) -> bool {
+        self.embed_metadata
+    }
+
+    /// Pages of illustrations are moved into `store` once downloaded, and exposed at their
+    /// download path with a link of the given kind. Pages that get metadata embedded, see
+    /// `set_embed_metadata`, are exposed as copies instead.
+    pub fn set_store(self, store: Arc<ContentStore>, link: LinkKind) -> Self {
+        DownloadQueue {
+            store: Some((store, link)),
+            ..self
+        }
+    }
+
+    pub fn store(&self) -> Option<&Arc<ContentStore>> {
+        self.store.as_ref().map(|(store, _)| store)
+    }
+
+    /// Pages of illustrations pushed after this is set are hashed once downloaded, and added to
+    /// `index` under their archive key. Pages that can't be decoded are left out.
+    #[cfg(feature = "phash")]
+    pub fn set_hash_index(self, index: Arc<HashIndex>) -> Self {
+        DownloadQueue {
+            hash_index: Some(index),
+            ..self
+        }
+    }
+
+    #[cfg(feature = "phash")]
+    pub fn hash_index(&self) -> Option<&Arc<HashIndex>> {
+        self.hash_index.as_ref()
+    }
+
+    /// Same as `set_hash_index`, and before the first page of an illustration is downloaded its
+    /// square thumbnail is fetched and hashed. Works whose thumbnail is within `max_distance`
+    /// bits of an image of another work in `index` are skipped, the thumbnails of the others
+    /// are added to the index. Works whose thumbnail can't be fetched are downloaded.
+    #[cfg(feature = "phash")]
+    pub fn set_duplicate_filter(self, index: Arc<HashIndex>, max_distance: u32) -> Self {
+        DownloadQueue {
+            hash_index: Some(index),
+            max_distance: Some(max_distance),
+            ..self
+        }
+    }
+
+    /// Returns the key of the indexed image the thumbnail of the work looks like, if any.
+    #[cfg(feature = "phash")]
+    fn duplicate_of<F>(&self, id: usize, check: &DuplicateCheck, on_event: &F) -> Option<ArchiveKey>
+    where
+        F: Fn(DownloadEvent) + Sync,
+    {
+        *check.duplicate_of.get_or_init(|| {
+            let index = self.hash_index.as_ref()?;
+            let max_distance = self.max_distance?;
+            let thumbnail = self.downloader.fetch(&check.thumbnail).ok()?;
+            let hash = hash_bytes(&thumbnail, index.algorithm()).ok()?;
+
+            let found = index.find(&hash, max_distance);
+            if let Some((key, _)) = found
+                .iter()
+                .find(|(key, _)| key.illust_id() != check.illust_id)
+            {
+                return Some(*key);
+            }
+            if found.is_empty() {
+                let key = ArchiveKey::new(check.illust_id, 0, ImageSize::SquareMedium);
+                if let Err(e) = index.insert(key, hash) {
+                    on_event(DownloadEvent::IndexFailed {
+                        id,
+                        reason: e.to_string(),
+                    });
+                }
+            }
+            None
+        })
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    pub fn control(&self) -> QueueControl {
+        self.control.clone()
+    }
+
+    /// Adds a file to the queue, returning its id.
+    pub fn push<U: ToString, P: AsRef<Path>>(&mut self, url: U, path: P) -> usize {
+        self.push_job(url.to_string(), path.as_ref().to_path_buf(), None)
+    }
+
+    /// Adds every `(url, path)` pair, returning their ids.
+    pub fn push_all<I, U>(&mut self, targets: I) -> Vec<usize>
+    where
+        I: IntoIterator<Item = (U, PathBuf)>,
+        U: ToString,
+    {
+        targets
+            .into_iter()
+            .map(|(url, path)| self.push(url, path))
+            .collect()
+    }
+
+    /// Adds every page of an illustration at the given size, named like
+    /// `Illustration::download_pages` does, returning their ids.
+    pub fn push_illustration(
+        &mut self,
+        illustration: &Illustration,
+        path: &Path,
+        size: ImageSize,
+    ) -> Vec<usize> {
+        self.push_pages(illustration, size, |illustration, page, url| {
+            Some(path.join(illustration.page_file_name(page, url)))
+        })
+    }
+
+    /// Adds every page of an illustration at the given size, where `layout` puts them,
+    /// returning their ids.
+    pub fn push_illustration_with_layout(
+        &mut self,
+        illustration: &Illustration,
+        layout: &mut DownloadLayout,
+        size: ImageSize,
+    ) -> Vec<usize> {
+        self.push_pages(illustration, size, |illustration, page, url| {
+            layout.path(illustration, page, url, size)
+        })
+    }
+
+    fn push_pages<F>(
+        &mut self,
+        illustration: &Illustration,
+        size: ImageSize,
+        mut path: F,
+    ) -> Vec<usize>
+    where
+        F: FnMut(&Illustration, usize, &str) -> Option<PathBuf>,
+    {
+        #[cfg(feature = "phash")]
+        let duplicate_check = self
+            .max_distance
+            .and(illustration.image_urls().square_medium.as_ref())
+            .map(|thumbnail| {
+                Arc::new(DuplicateCheck {
+                    illust_id: illustration.id(),
+                    thumbnail: thumbnail.clone(),
+                    duplicate_of: OnceLock::new(),
+                })
+            });
+        let metadata = self
+            .embed_metadata
+            .then(|| Arc::new(EmbeddedMetadata::from_illustration(illustration)));
+        let mut ids = Vec::new();
+        for (page, url) in illustration.page_urls(size).into_iter().enumerate() {
+            let url = match url {
+                Some(url) => url,
+                None => continue,
+            };
+            let key = ArchiveKey::new(illustration.id(), page as u32, size);
+            if self
+                .archive
+                .as_ref()
+                .is_some_and(|archive| archive.contains(&key))
+            {
+                continue;
+            }
+            if let Some(path) = path(illustration, page, url) {
+                let id = self.push_job(url.clone(), path, Some(key));
+                self.jobs[id].metadata = metadata.clone();
+                #[cfg(feature = "phash")]
+                {
+                    self.jobs[id].duplicate_check = duplicate_check.clone();
+                }
+                ids.push(id);
+            }
+        }
+
+        if let (Some(first), false) = (ids.first(), self.sidecars.is_empty()) {
+            let dir = self.jobs[*first].path.parent().unwrap_or(Path::new(""));
+            let files = self.sidecars.render(illustration, dir);
+            self.sidecar_jobs.push(SidecarJob {
+                ids: ids.clone(),
+                url: illustration.artwork_url(),
+                files,
+            });
+        }
+        ids
+    }
+
+    fn push_job(&mut self, url: String, path: PathBuf, key: Option<ArchiveKey>) -> usize {
+        self.jobs.push(Job {
+            url,
+            path,
+            key,
+            metadata: None,
+            #[cfg(feature = "phash")]
+            duplicate_check: None,
+        });
+        self.jobs.len() - 1
+    }
+
+    /// Downloads every file, calling `on_event` from the worker threads. Returns once all the
+    /// files are done, the report lists them in the order they were pushed.
+    pub fn run<F>(self, on_event: F) -> DownloadReport
+    where
+        F: Fn(DownloadEvent) + Sync,
+    {
+        for (id, job) in self.jobs.iter().enumerate() {
+            on_event(DownloadEvent::Queued {
+                id,
+                url: job.url.clone(),
+                path: job.path.clone(),
+            });
+        }
+
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<Result<u64, DownloadError>>>> =
+            Mutex::new((0..self.jobs.len()).map(|_| None).collect());
+        let workers = self.workers.min(self.jobs.len());
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let id = next.fetch_add(1, Ordering::SeqCst);
+                    let job = match self.jobs.get(id) {
+                        Some(job) => job,
+                        None => break,
+                    };
+                    let result = self.download(id, job, &on_event);
+                    match &result {
+                        Ok(size) => on_event(DownloadEvent::Finished { id, size: *size }),
+                        Err(DownloadError::Duplicate(key)) => on_event(DownloadEvent::Skipped {
+                            id,
+                            duplicate_of: *key,
+                        }),
+                        Err(e) => on_event(DownloadEvent::Failed {
+                            id,
+                            reason: e.to_string(),
+                        }),
+                    }
+                    results.lock().unwrap()[id] = Some(result);
+                });
+            }
+        });
+
+        let results: Vec<Result<u64, DownloadError>> = results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.unwrap_or(Err(DownloadError::Cancelled)))
+            .collect();
+        let sidecars: Vec<FileReport> = self
+            .sidecar_jobs
+            .into_iter()
+            .filter(|sidecar| sidecar.ids.iter().any(|id| results[*id].is_ok()))
+            .flat_map(|sidecar| {
+                let url = sidecar.url;
+                sidecar.files.into_iter().map(move |(path, content)| {
+                    let result = fs::write(&path, &content)
+                        .map(|_| content.len() as u64)
+                        .map_err(DownloadError::from);
+                    FileReport::new(&url, path, result)
+                })
+            })
+            .collect();
+
+        self.jobs
+            .into_iter()
+            .zip(results)
+            .map(|(job, result)| FileReport::new(job.url, job.path, result))
+            .chain(sidecars)
+            .collect()
+    }
+
+    /// Same as `run`, sending the events through `sender`. Events are dropped once the
+    /// receiving end hangs up.
+    pub fn run_with_channel(self, sender: Sender<DownloadEvent>) -> DownloadReport {
+        let sender = Mutex::new(sender);
+        self.run(|event| {
+            let _ = sender.lock().unwrap().send(event);
+        })
+    }
+
+    fn download<F>(&self, id: usize, job: &Job, on_event: &F) -> Result<u64, DownloadError>
+    where
+        F: Fn(DownloadEvent) + Sync,
+    {
+        if !self.control.wait() {
+            return Err(DownloadError::Cancelled);
+        }
+        #[cfg(feature = "phash")]
+        if let Some(key) = job
+            .duplicate_check
+            .as_ref()
+            .and_then(|check| self.duplicate_of(id, check, on_event))
+        {
+            return Err(DownloadError::Duplicate(key));
+        }
+        on_event(DownloadEvent::Started { id });
+        let size =
+            self.downloader
+                .download_with_progress(&job.url, &job.path, |downloaded, total| {
+                    on_event(DownloadEvent::Progress {
+                        id,
+                        downloaded,
+                        total,
+                    });
+                    self.control.wait()
+                })?;
+        // The store keeps the bytes as served, so that reposts share storage. A page that gets
+        // metadata written into it is exposed as a copy, leaving the stored object alone.
+        if let (Some((store, link)), Some(key)) = (&self.store, job.key) {
+            store.insert_file(key, &job.path)?;
+            let link = match job.metadata {
+                Some(_) => LinkKind::Copy,
+                None => *link,
+            };
+            store.link(&key, &job.path, link)?;
+        }
+        if let Some(metadata) = &job.metadata {
+            match embed_file(&job.path, metadata) {
+                Ok(()) | Err(MetadataError::UnsupportedFormat) => {}
+                Err(e) => on_event(DownloadEvent::EmbedFailed {
+                    id,
+                    reason: e.to_string(),
+                }),
+            }
+        }
+        #[cfg(feature = "phash")]
+        if let (Some(index), Some(key)) = (&self.hash_index, job.key) {
+            if let Ok(hash) = hash_file(&job.path, index.algorithm()) {
+                if let Err(e) = index.insert(key, hash) {
+                    on_event(DownloadEvent::IndexFailed {
+                        id,
+                        reason: e.to_string(),
+                    });
+                }
+            }
+        }
+        if let (Some(archive), Some(key)) = (&self.archive, job.key) {
+            archive.insert(key)?;
+        }
+        Ok(size)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::errors::DownloadError;
+
+use std::iter::FromIterator;
+use std::path::PathBuf;
+
+/// Outcome of downloading a single file.
+#[derive(Debug)]
+pub struct FileReport {
+    url: String,
+    path: PathBuf,
+    result: Result<u64, DownloadError>,
+}
+
+impl FileReport {
+    pub fn new<T: ToString>(url: T, path: PathBuf, result: Result<u64, DownloadError>) -> Self {
+        FileReport {
+            url: url.to_string(),
+            path,
+            result,
+        }
+    }
+
+    pub fn url(&self) -> &String {
+        &self.url
+    }
+
+    /// The destination of the file, it only exists if the download succeeded.
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// The amount of bytes written, or the reason of the failure.
+    pub fn result(&self) -> &Result<u64, DownloadError> {
+        &self.result
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Whether the file was left out on purpose, see `DownloadQueue::set_duplicate_filter`.
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.result, Err(DownloadError::Duplicate(_)))
+    }
+
+    pub fn error(&self) -> Option<&DownloadError> {
+        self.result.as_ref().err()
+    }
+}
+
+/// Outcome of downloading a batch of files, in the order they were requested.
+#[derive(Debug, Default)]
+pub struct DownloadReport {
+    files: Vec<FileReport>,
+}
+
+impl DownloadReport {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, file: FileReport) {
+        self.files.push(file);
+    }
+
+    pub fn files(&self) -> &Vec<FileReport> {
+        &self.files
+    }
+
+    pub fn succeeded(&self) -> impl Iterator<Item = &FileReport> {
+        self.files.iter().filter(|file| file.is_success())
+    }
+
+    /// Files that were neither downloaded nor skipped.
+    pub fn failed(&self) -> impl Iterator<Item = &FileReport> {
+        self.files
+            .iter()
+            .filter(|file| !file.is_success() && !file.is_skipped())
+    }
+
+    pub fn skipped(&self) -> impl Iterator<Item = &FileReport> {
+        self.files.iter().filter(|file| file.is_skipped())
+    }
+
+    /// Whether every file was downloaded or skipped.
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    /// Paths of the files that were downloaded.
+    pub fn paths(&self) -> Vec<&PathBuf> {
+        self.succeeded().map(FileReport::path).collect()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.files
+            .iter()
+            .filter_map(|file| file.result.as_ref().ok())
+            .sum()
+    }
+
+    pub fn into_inner(self) -> Vec<FileReport> {
+        self.files
+    }
+}
+
+impl FromIterator<FileReport> for DownloadReport {
+    fn from_iter<I: IntoIterator<Item = FileReport>>(iter: I) -> Self {
+        DownloadReport {
+            files: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for DownloadReport {
+    type Item = FileReport;
+    type IntoIter = std::vec::IntoIter<FileReport>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files.into_iter()
+    }
+}
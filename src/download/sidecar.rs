@@ -0,0 +1,210 @@
+use crate::enums::SidecarFormat;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::utils::caption_to_text;
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Writes the metadata of works next to their downloaded pages, in one or more formats.
+///
+/// The files are named after the illustration id, `<id>.json`, `<id>.toml` and `<id>.txt`.
+/// See `DownloadQueue::set_sidecars` to write them as part of a download.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SidecarWriter {
+    formats: Vec<SidecarFormat>,
+}
+
+impl SidecarWriter {
+    pub fn new<I: IntoIterator<Item = SidecarFormat>>(formats: I) -> Self {
+        let mut writer = SidecarWriter::default();
+        for format in formats {
+            writer = writer.add_format(format);
+        }
+        writer
+    }
+
+    pub fn add_format(mut self, format: SidecarFormat) -> Self {
+        if !self.formats.contains(&format) {
+            self.formats.push(format);
+        }
+        self
+    }
+
+    pub fn formats(&self) -> &Vec<SidecarFormat> {
+        &self.formats
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.formats.is_empty()
+    }
+
+    /// Returns the path and the content of every sidecar of an illustration in `dir`.
+    pub fn render(&self, illustration: &Illustration, dir: &Path) -> Vec<(PathBuf, String)> {
+        self.formats
+            .iter()
+            .map(|format| {
+                let path = dir.join(format!("{}.{}", illustration.id(), format.extension()));
+                (path, render(illustration, *format))
+            })
+            .collect()
+    }
+
+    /// Writes every sidecar of an illustration in `dir`, returning their paths.
+    pub fn write(&self, illustration: &Illustration, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
+        self.render(illustration, dir)
+            .into_iter()
+            .map(|(path, content)| fs::write(&path, content).map(|_| path))
+            .collect()
+    }
+}
+
+/// Renders the metadata of an illustration in the given format.
+pub fn render(illustration: &Illustration, format: SidecarFormat) -> String {
+    match format {
+        SidecarFormat::Json => serde_json::to_string_pretty(illustration).unwrap(),
+        SidecarFormat::Toml => to_toml(illustration),
+        SidecarFormat::Text => to_text(illustration),
+    }
+}
+
+fn to_toml(illustration: &Illustration) -> String {
+    let mut toml = String::new();
+    let user = illustration.user();
+
+    let _ = writeln!(toml, "id = {}", illustration.id());
+    let _ = writeln!(toml, "title = {}", toml_string(illustration.title()));
+    let _ = writeln!(toml, "url = {}", toml_string(&illustration.artwork_url()));
+    let _ = writeln!(
+        toml,
+        "type = {}",
+        toml_string(illustration.content_type().as_str())
+    );
+    let _ = writeln!(
+        toml,
+        "caption = {}",
+        toml_string(&caption_to_text(illustration.caption()))
+    );
+    let _ = writeln!(
+        toml,
+        "create_date = {}",
+        toml_string(illustration.create_date())
+    );
+    let _ = writeln!(toml, "page_count = {}", illustration.page_count());
+    let _ = writeln!(toml, "width = {}", illustration.width());
+    let _ = writeln!(toml, "height = {}", illustration.height());
+    let _ = writeln!(toml, "sanity_level = {}", illustration.sanity_level());
+    let _ = writeln!(toml, "x_restrict = {}", illustration.x_restrict());
+    let _ = writeln!(toml, "total_view = {}", illustration.total_view());
+    let _ = writeln!(toml, "total_bookmarks = {}", illustration.total_bookmarks());
+    let tools: Vec<String> = illustration
+        .tools()
+        .iter()
+        .map(|tool| toml_string(tool))
+        .collect();
+    let _ = writeln!(toml, "tools = [{}]", tools.join(", "));
+
+    let _ = writeln!(toml, "\n[user]");
+    let _ = writeln!(toml, "id = {}", user.id());
+    let _ = writeln!(toml, "name = {}", toml_string(user.name()));
+    let _ = writeln!(toml, "account = {}", toml_string(user.account()));
+
+    if let Some(series) = illustration.series() {
+        let _ = writeln!(toml, "\n[series]");
+        let _ = writeln!(toml, "id = {}", series.id());
+        let _ = writeln!(toml, "title = {}", toml_string(series.title()));
+    }
+
+    for tag in illustration.tags() {
+        let _ = writeln!(toml, "\n[[tags]]");
+        let _ = writeln!(toml, "name = {}", toml_string(tag.name()));
+        if let Some(translated) = tag
+            .translated_name()
+            .as_ref()
+            .and_then(|names| names.first())
+        {
+            let _ = writeln!(toml, "translated_name = {}", toml_string(translated));
+        }
+    }
+    toml
+}
+
+/// Quotes `value` as a TOML basic string.
+fn toml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04X}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn to_text(illustration: &Illustration) -> String {
+    let mut text = String::new();
+    let user = illustration.user();
+
+    let _ = writeln!(text, "Title: {}", illustration.title());
+    let _ = writeln!(text, "URL: {}", illustration.artwork_url());
+    let _ = writeln!(
+        text,
+        "Artist: {} (id {}, @{})",
+        user.name(),
+        user.id(),
+        user.account()
+    );
+    let _ = writeln!(text, "Date: {}", illustration.create_date());
+    let _ = writeln!(
+        text,
+        "Type: {}, {} page(s), {}x{}",
+        illustration.content_type().as_str(),
+        illustration.page_count(),
+        illustration.width(),
+        illustration.height()
+    );
+    if let Some(series) = illustration.series() {
+        let _ = writeln!(text, "Series: {} (id {})", series.title(), series.id());
+    }
+    let tags: Vec<String> = illustration
+        .tags()
+        .iter()
+        .map(|tag| {
+            match tag
+                .translated_name()
+                .as_ref()
+                .and_then(|names| names.first())
+            {
+                Some(translated) => format!("{} ({})", tag.name(), translated),
+                None => tag.name().clone(),
+            }
+        })
+        .collect();
+    let _ = writeln!(text, "Tags: {}", tags.join(", "));
+    if !illustration.tools().is_empty() {
+        let _ = writeln!(text, "Tools: {}", illustration.tools().join(", "));
+    }
+    let _ = writeln!(
+        text,
+        "Views: {}, Bookmarks: {}",
+        illustration.total_view(),
+        illustration.total_bookmarks()
+    );
+
+    let caption = caption_to_text(illustration.caption());
+    if !caption.trim().is_empty() {
+        let _ = writeln!(text, "\n{}", caption.trim_end());
+    }
+    text
+}
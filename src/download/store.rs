@@ -0,0 +1,226 @@
+use crate::download::archive::ArchiveKey;
+use crate::enums::LinkKind;
+
+use sha2::{Digest, Sha256};
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug)]
+struct StoreIndex {
+    hashes: HashMap<ArchiveKey, String>,
+    file: File,
+}
+
+/// Content-addressable store for downloaded files.
+///
+/// Files are kept once per distinct content under `objects/<first two hex digits>/<sha-256>`
+/// in the store directory, and `index.txt` maps each page (illust id, page, size) to the
+/// hash of its content, one `<sha-256> <key>` per line. The download paths become views onto
+/// the stored files, see `link`.
+///
+/// When combined with `DownloadQueue::set_embed_metadata`, the file is stored as downloaded
+/// and the metadata is only embedded into a copy at the download path.
+#[derive(Debug)]
+pub struct ContentStore {
+    root: PathBuf,
+    index: Mutex<StoreIndex>,
+}
+
+impl ContentStore {
+    /// Opens the store in the directory `root`, creating it if needed.
+    pub fn open<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+        fs::create_dir_all(root.as_ref().join("objects"))?;
+        let root = root.as_ref().canonicalize()?;
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(root.join("index.txt"))?;
+
+        let mut hashes = HashMap::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid index entry: {:?}", line),
+                )
+            };
+            let (hash, key) = line.split_once(' ').ok_or_else(invalid)?;
+            if !is_hash(hash) {
+                return Err(invalid());
+            }
+            // A page stored again later replaces its previous content.
+            hashes.insert(key.parse()?, String::from(hash));
+        }
+
+        Ok(ContentStore {
+            root,
+            index: Mutex::new(StoreIndex { hashes, file }),
+        })
+    }
+
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    /// Returns where the content with the given hash is stored.
+    pub fn object_path(&self, hash: &str) -> PathBuf {
+        self.root
+            .join("objects")
+            .join(&hash[..2.min(hash.len())])
+            .join(hash)
+    }
+
+    pub fn contains_object(&self, hash: &str) -> bool {
+        is_hash(hash) && self.object_path(hash).is_file()
+    }
+
+    /// Returns the hash of the content of a page.
+    pub fn hash(&self, key: &ArchiveKey) -> Option<String> {
+        self.index.lock().unwrap().hashes.get(key).cloned()
+    }
+
+    /// Returns every page whose content has the given hash.
+    pub fn keys(&self, hash: &str) -> Vec<ArchiveKey> {
+        let mut keys: Vec<ArchiveKey> = self
+            .index
+            .lock()
+            .unwrap()
+            .hashes
+            .iter()
+            .filter(|(_, stored)| *stored == hash)
+            .map(|(key, _)| *key)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Returns the whole mapping from pages to hashes.
+    pub fn entries(&self) -> HashMap<ArchiveKey, String> {
+        self.index.lock().unwrap().hashes.clone()
+    }
+
+    /// Moves the file at `path` into the store as the content of a page, returning its hash.
+    /// If the content is already stored, the file is removed instead.
+    pub fn insert_file<P: AsRef<Path>>(&self, key: ArchiveKey, path: P) -> io::Result<String> {
+        let path = path.as_ref();
+        let hash = hash_reader(File::open(path)?)?;
+        let object = self.object_path(&hash);
+        if object.is_file() {
+            fs::remove_file(path)?;
+        } else {
+            fs::create_dir_all(object.parent().unwrap())?;
+            if fs::rename(path, &object).is_err() {
+                // Renaming fails across filesystems.
+                fs::copy(path, &object)?;
+                fs::remove_file(path)?;
+            }
+        }
+        self.record(key, &hash)?;
+        Ok(hash)
+    }
+
+    /// Stores bytes as the content of a page, returning their hash.
+    pub fn insert_bytes(&self, key: ArchiveKey, data: &[u8]) -> io::Result<String> {
+        let hash = hex(&Sha256::digest(data));
+        let object = self.object_path(&hash);
+        if !object.is_file() {
+            fs::create_dir_all(object.parent().unwrap())?;
+            let mut temporary = object.clone().into_os_string();
+            temporary.push(".tmp");
+            fs::write(&temporary, data)?;
+            fs::rename(&temporary, &object)?;
+        }
+        self.record(key, &hash)?;
+        Ok(hash)
+    }
+
+    /// Exposes the content of a page at `dest`, replacing what is there.
+    pub fn link<P: AsRef<Path>>(
+        &self,
+        key: &ArchiveKey,
+        dest: P,
+        kind: LinkKind,
+    ) -> io::Result<()> {
+        let hash = self.hash(key).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not in the store", key),
+            )
+        })?;
+        let object = self.object_path(&hash);
+        let dest = dest.as_ref();
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::symlink_metadata(dest).is_ok() {
+            fs::remove_file(dest)?;
+        }
+
+        match kind {
+            LinkKind::HardLink => fs::hard_link(&object, dest),
+            LinkKind::Symlink => symlink(&object, dest),
+            LinkKind::Copy => fs::copy(&object, dest).map(|_| ()),
+        }
+    }
+
+    fn record(&self, key: ArchiveKey, hash: &str) -> io::Result<()> {
+        let mut index = self.index.lock().unwrap();
+        if index.hashes.get(&key).map(String::as_str) == Some(hash) {
+            return Ok(());
+        }
+        writeln!(index.file, "{} {}", hash, key)?;
+        index.file.flush()?;
+        index.hashes.insert(key, String::from(hash));
+        Ok(())
+    }
+}
+
+/// Returns the SHA-256 of everything read from `reader`, in lowercase hex.
+pub fn hash_reader<R: Read>(mut reader: R) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn is_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+#[cfg(unix)]
+fn symlink(object: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(object, dest)
+}
+
+#[cfg(windows)]
+fn symlink(object: &Path, dest: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(object, dest)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_: &Path, _: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symbolic links are not supported on this platform",
+    ))
+}
@@ -0,0 +1,453 @@
+use crate::enums::{CollisionPolicy, ImageSize};
+use crate::errors::TemplateError;
+use crate::pixiv::helper_structs::illustration::Illustration;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Longest file or directory name produced, in bytes. Most filesystems allow 255 bytes, the
+/// rest is left for the `.part.json` files of the downloader.
+pub const MAX_NAME_LENGTH: usize = 200;
+
+/// Names Windows refuses, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Field {
+    Id,
+    Title,
+    Type,
+    Page,
+    PageCount,
+    Ext,
+    Size,
+    Width,
+    Height,
+    Date,
+    CreateDate,
+    SanityLevel,
+    XRestrict,
+    TotalBookmarks,
+    TotalView,
+    UserId,
+    UserName,
+    UserAccount,
+    SeriesId,
+    SeriesTitle,
+    Tags,
+    TagName(usize),
+    TagTranslatedName(usize),
+}
+
+impl FromStr for Field {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let field = match s {
+            "id" => Field::Id,
+            "title" => Field::Title,
+            "type" => Field::Type,
+            "page" => Field::Page,
+            "page_count" => Field::PageCount,
+            "ext" => Field::Ext,
+            "size" => Field::Size,
+            "width" => Field::Width,
+            "height" => Field::Height,
+            "date" => Field::Date,
+            "create_date" => Field::CreateDate,
+            "sanity_level" => Field::SanityLevel,
+            "x_restrict" => Field::XRestrict,
+            "total_bookmarks" => Field::TotalBookmarks,
+            "total_view" => Field::TotalView,
+            "user.id" => Field::UserId,
+            "user.name" => Field::UserName,
+            "user.account" => Field::UserAccount,
+            "series.id" => Field::SeriesId,
+            "series.title" => Field::SeriesTitle,
+            "tags" => Field::Tags,
+            _ => {
+                let unknown = || TemplateError::UnknownField(String::from(s));
+                let mut parts = s.splitn(3, '.');
+                if parts.next() != Some("tags") {
+                    return Err(unknown());
+                }
+                let index = parts
+                    .next()
+                    .and_then(|index| index.parse().ok())
+                    .ok_or_else(unknown)?;
+                match parts.next() {
+                    None | Some("name") => Field::TagName(index),
+                    Some("translated_name") => Field::TagTranslatedName(index),
+                    Some(_) => return Err(unknown()),
+                }
+            }
+        };
+        Ok(field)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// Values a template is rendered with.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateContext<'a> {
+    illustration: &'a Illustration,
+    page: usize,
+    url: &'a str,
+    size: ImageSize,
+}
+
+impl<'a> TemplateContext<'a> {
+    /// `url` is the url the page is downloaded from, it gives the extension.
+    pub fn new(illustration: &'a Illustration, page: usize, url: &'a str, size: ImageSize) -> Self {
+        TemplateContext {
+            illustration,
+            page,
+            url,
+            size,
+        }
+    }
+
+    fn value(&self, field: &Field) -> String {
+        let illust = self.illustration;
+        let tag = |index: usize| illust.tags().get(index);
+        match field {
+            Field::Id => illust.id().to_string(),
+            Field::Title => illust.title().clone(),
+            Field::Type => String::from(illust.content_type().as_str()),
+            Field::Page => self.page.to_string(),
+            Field::PageCount => illust.page_count().to_string(),
+            Field::Ext => {
+                let name = illust.page_file_name(self.page, self.url);
+                String::from(name.rsplit('.').next().unwrap_or_default())
+            }
+            Field::Size => String::from(self.size.as_str()),
+            Field::Width => illust.width().to_string(),
+            Field::Height => illust.height().to_string(),
+            Field::Date => illust
+                .create_date()
+                .split('T')
+                .next()
+                .map(String::from)
+                .unwrap_or_default(),
+            Field::CreateDate => illust.create_date().clone(),
+            Field::SanityLevel => illust.sanity_level().to_string(),
+            Field::XRestrict => illust.x_restrict().to_string(),
+            Field::TotalBookmarks => illust.total_bookmarks().to_string(),
+            Field::TotalView => illust.total_view().to_string(),
+            Field::UserId => illust.user().id().to_string(),
+            Field::UserName => illust.user().name().clone(),
+            Field::UserAccount => illust.user().account().clone(),
+            Field::SeriesId => illust
+                .series()
+                .map(|series| series.id().to_string())
+                .unwrap_or_default(),
+            Field::SeriesTitle => illust
+                .series()
+                .map(|series| String::from(series.title()))
+                .unwrap_or_default(),
+            Field::Tags => illust
+                .tags()
+                .iter()
+                .map(|tag| tag.name().as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            Field::TagName(index) => tag(*index)
+                .map(|tag| tag.name().clone())
+                .unwrap_or_default(),
+            Field::TagTranslatedName(index) => tag(*index)
+                .and_then(|tag| tag.translated_name().as_ref())
+                .and_then(|names| names.first())
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Template for the paths of downloaded files, such as `{user.name}/{id}_{title}_p{page}.{ext}`.
+///
+/// `/` separates directories, fields are written between braces and `{{`, `}}` stand for
+/// literal braces. The fields are `id`, `title`, `type`, `page`, `page_count`, `ext`, `size`,
+/// `width`, `height`, `date` (`YYYY-MM-DD`), `create_date`, `sanity_level`, `x_restrict`,
+/// `total_bookmarks`, `total_view`, `user.id`, `user.name`, `user.account`, `series.id`,
+/// `series.title`, `tags` (every tag name separated by spaces), `tags.N` or `tags.N.name` and
+/// `tags.N.translated_name`.
+///
+/// Field values never introduce directories, see `sanitize_name` for what is replaced.
+/// Directories that render empty are left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    segments: Vec<Segment>,
+    max_name_length: usize,
+}
+
+impl Default for PathTemplate {
+    /// `{id}_p{page}.{ext}`, the naming of `Illustration::download_pages`.
+    fn default() -> Self {
+        PathTemplate::parse("{id}_p{page}.{ext}").unwrap()
+    }
+}
+
+impl FromStr for PathTemplate {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PathTemplate::parse(s)
+    }
+}
+
+impl PathTemplate {
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((at, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|(_, c)| *c) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|(_, c)| *c) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let end = template[at..]
+                        .find('}')
+                        .map(|end| at + end)
+                        .ok_or(TemplateError::UnclosedField(at))?;
+                    let field = template[at + 1..end].trim().parse()?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                    while chars.peek().is_some_and(|(next, _)| *next <= end) {
+                        chars.next();
+                    }
+                }
+                '}' => return Err(TemplateError::UnmatchedBrace(at)),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(PathTemplate {
+            segments,
+            max_name_length: MAX_NAME_LENGTH,
+        })
+    }
+
+    /// Longest file or directory name produced, in bytes. Defaults to `MAX_NAME_LENGTH`.
+    pub fn set_max_name_length(self, max_name_length: usize) -> Self {
+        PathTemplate {
+            max_name_length: max_name_length.max(1),
+            ..self
+        }
+    }
+
+    pub fn max_name_length(&self) -> usize {
+        self.max_name_length
+    }
+
+    /// Renders the template into a relative path.
+    pub fn render(&self, context: &TemplateContext) -> PathBuf {
+        let mut components = vec![String::new()];
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => {
+                    let mut parts = literal.split('/');
+                    components
+                        .last_mut()
+                        .unwrap()
+                        .push_str(parts.next().unwrap());
+                    components.extend(parts.map(String::from));
+                }
+                Segment::Field(field) => {
+                    let value = context.value(field).replace(['/', '\\'], "_");
+                    components.last_mut().unwrap().push_str(&value);
+                }
+            }
+        }
+
+        let file_name = components.pop().unwrap();
+        let mut path: PathBuf = components
+            .iter()
+            .filter(|component| !component.trim().is_empty())
+            .map(|component| sanitize_name(component, self.max_name_length))
+            .collect();
+        path.push(sanitize_name(&file_name, self.max_name_length));
+        path
+    }
+}
+
+/// Makes `name` usable as a file or directory name on Linux, macOS and Windows.
+///
+/// Path separators, characters Windows refuses (`:*?"<>|`) and control characters are replaced
+/// with `_`, trailing dots and spaces are removed, reserved names such as `CON` or `nul.txt`
+/// get a `_` appended, and names longer than `max_length` bytes are shortened while keeping
+/// their extension. Empty names, `.` and `..` become `_`.
+pub fn sanitize_name(name: &str, max_length: usize) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let mut name = String::from(name.trim_end_matches(['.', ' ']));
+
+    let stem = name.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        name.insert(stem.len(), '_');
+    }
+
+    if name.len() > max_length {
+        name = shorten(&name, max_length);
+    }
+    if name.is_empty() || name == "." || name == ".." {
+        name = String::from("_");
+    }
+    name
+}
+
+/// Cuts `name` down to `max_length` bytes on a char boundary, keeping a short extension.
+fn shorten(name: &str, max_length: usize) -> String {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && ext.len() + 1 < max_length && ext.len() <= 16 => {
+            (stem, &name[stem.len()..])
+        }
+        _ => (name, ""),
+    };
+    let mut end = max_length - ext.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", stem[..end].trim_end_matches(['.', ' ']), ext)
+}
+
+/// `stem (n)ext`, cutting the stem so that the name still fits in `max_length` bytes.
+fn numbered_name(stem: &str, n: usize, ext: &str, max_length: usize) -> String {
+    let suffix = format!(" ({})", n);
+    let mut end = stem
+        .len()
+        .min(max_length.saturating_sub(suffix.len() + ext.len()));
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    let name = format!(
+        "{}{}{}",
+        stem[..end].trim_end_matches(['.', ' ']),
+        suffix,
+        ext
+    );
+    if name.len() > max_length {
+        shorten(&name, max_length)
+    } else {
+        name
+    }
+}
+
+/// Decides where the pages of illustrations are downloaded to, from a `PathTemplate` under a
+/// root directory, resolving collisions with existing files and with the paths it handed out
+/// before according to its `CollisionPolicy`.
+#[derive(Debug, Clone)]
+pub struct DownloadLayout {
+    root: PathBuf,
+    template: PathTemplate,
+    collision: CollisionPolicy,
+    claimed: HashSet<PathBuf>,
+}
+
+impl DownloadLayout {
+    pub fn new<P: AsRef<Path>>(root: P, template: PathTemplate) -> Self {
+        DownloadLayout {
+            root: root.as_ref().to_path_buf(),
+            template,
+            collision: CollisionPolicy::default(),
+            claimed: HashSet::new(),
+        }
+    }
+
+    pub fn set_collision(self, collision: CollisionPolicy) -> Self {
+        DownloadLayout { collision, ..self }
+    }
+
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    pub fn template(&self) -> &PathTemplate {
+        &self.template
+    }
+
+    pub fn collision(&self) -> CollisionPolicy {
+        self.collision
+    }
+
+    /// Returns the path a page should be downloaded to, or `None` if the policy is to skip it.
+    pub fn path(
+        &mut self,
+        illustration: &Illustration,
+        page: usize,
+        url: &str,
+        size: ImageSize,
+    ) -> Option<PathBuf> {
+        let context = TemplateContext::new(illustration, page, url, size);
+        let path = self.root.join(self.template.render(&context));
+        let taken = |path: &PathBuf| self.claimed.contains(path) || path.exists();
+
+        let path = if !taken(&path) || self.collision == CollisionPolicy::Overwrite {
+            path
+        } else if self.collision == CollisionPolicy::Skip {
+            return None;
+        } else {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let ext = path
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            let max_length = self.template.max_name_length();
+            (1..)
+                .map(|n| path.with_file_name(numbered_name(&stem, n, &ext, max_length)))
+                .find(|candidate| !taken(candidate))
+                .unwrap()
+        };
+        self.claimed.insert(path.clone());
+        Some(path)
+    }
+
+    /// Returns the `(url, path)` of every page of an illustration at the given size that isn't
+    /// skipped, ready for `Downloader::download_all` or `DownloadQueue::push_all`.
+    pub fn targets(
+        &mut self,
+        illustration: &Illustration,
+        size: ImageSize,
+    ) -> Vec<(String, PathBuf)> {
+        illustration
+            .page_urls(size)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(page, url)| url.map(|url| (page, url)))
+            .filter_map(|(page, url)| {
+                self.path(illustration, page, url, size)
+                    .map(|path| (url.clone(), path))
+            })
+            .collect()
+    }
+}
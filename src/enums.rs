@@ -1,11 +1,15 @@
 use serde::{Deserialize, Serialize};
 
 // TODO: Specificy how serde should deserialize these...
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SearchTarget {
     TagsPartial,
     TagsExact,
     TitleAndCaption,
+    /// Full text search, only supported by novel search.
+    Text,
+    /// Keyword search, only supported by novel search.
+    Keyword,
 }
 
 impl SearchTarget {
@@ -14,6 +18,8 @@ impl SearchTarget {
             SearchTarget::TagsPartial => "partial_match_for_tags",
             SearchTarget::TagsExact => "exact_match_for_tags",
             SearchTarget::TitleAndCaption => "title_and_caption",
+            SearchTarget::Text => "text",
+            SearchTarget::Keyword => "keyword",
         }
     }
 
@@ -22,7 +28,7 @@ impl SearchTarget {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ContentType {
     #[serde(rename = "illust")]
     Illustration,
@@ -117,6 +123,34 @@ impl RankingMode {
     }
 }
 
+/// Enum to set novel ranking mode param.
+/// Novels are only ranked over these periods, see `RankingMode` for illustrations.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum NovelRankingMode {
+    #[serde(rename = "day")]
+    Daily,
+    #[serde(rename = "week")]
+    Weekly,
+    #[serde(rename = "day_male")]
+    DayMale,
+    #[serde(rename = "day_female")]
+    DayFemale,
+    #[serde(rename = "week_rookie")]
+    WeekRookie,
+}
+
+impl NovelRankingMode {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            NovelRankingMode::Daily => "day",
+            NovelRankingMode::Weekly => "week",
+            NovelRankingMode::DayMale => "day_male",
+            NovelRankingMode::DayFemale => "day_female",
+            NovelRankingMode::WeekRookie => "week_rookie",
+        }
+    }
+}
+
 /// Enum to set search period param.
 #[derive(Debug, Clone, Copy)]
 pub enum SearchPeriod {
@@ -206,7 +240,7 @@ impl Duration {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Visibility {
     #[serde(rename = "public")]
     Public,
@@ -244,3 +278,142 @@ impl Filter {
         }
     }
 }
+
+/// Enum to pick the resolution of the frames of an ugoira.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum UgoiraSize {
+    /// Frames fitting in 600x600.
+    Medium,
+    /// Frames fitting in 1920x1080.
+    Original,
+}
+
+/// Enum to pick the format of an ugoira converted into an animation.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+    WebP,
+}
+
+impl AnimationFormat {
+    /// File extension used for this format.
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+            AnimationFormat::WebP => "webp",
+        }
+    }
+}
+
+/// Enum to pick the size of the images of an illustration.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub enum ImageSize {
+    #[serde(rename = "square_medium")]
+    SquareMedium,
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "large")]
+    Large,
+    #[default]
+    #[serde(rename = "original")]
+    Original,
+}
+
+impl ImageSize {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ImageSize::SquareMedium => "square_medium",
+            ImageSize::Medium => "medium",
+            ImageSize::Large => "large",
+            ImageSize::Original => "original",
+        }
+    }
+}
+
+/// What to do when the path a file would be downloaded to is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionPolicy {
+    /// Download over the existing file.
+    Overwrite,
+    /// Don't download the file.
+    Skip,
+    /// Append ` (1)`, ` (2)`, ... to the file name until it is free.
+    #[default]
+    Rename,
+}
+
+/// Formats of the metadata files written next to downloaded works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SidecarFormat {
+    /// The `Illustration` as returned by the API.
+    Json,
+    Toml,
+    /// Plain text meant to be read by people.
+    Text,
+}
+
+impl SidecarFormat {
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            SidecarFormat::Json => "json",
+            SidecarFormat::Toml => "toml",
+            SidecarFormat::Text => "txt",
+        }
+    }
+}
+
+/// Formats of bookmark backups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackupFormat {
+    /// Keeps the full `Illustration` of every bookmark.
+    #[default]
+    Json,
+    /// One row per bookmark with the main metadata, for spreadsheets.
+    Csv,
+}
+
+impl BackupFormat {
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            BackupFormat::Json => "json",
+            BackupFormat::Csv => "csv",
+        }
+    }
+}
+
+/// How a `ContentStore` exposes stored files at their download paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkKind {
+    /// Requires the view to be on the same filesystem as the store.
+    #[default]
+    HardLink,
+    Symlink,
+    /// Duplicates the bytes, for filesystems without links.
+    Copy,
+}
+
+/// Perceptual hash algorithms, see `download::phash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HashAlgorithm {
+    /// aHash: pixels brighter than the mean of an 8x8 thumbnail.
+    Average,
+    /// dHash: brightness gradients between neighbouring pixels of a 9x8 thumbnail.
+    Difference,
+    /// pHash: low frequencies of the discrete cosine transform of a 32x32 thumbnail.
+    #[default]
+    Perceptual,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            HashAlgorithm::Average => "ahash",
+            HashAlgorithm::Difference => "dhash",
+            HashAlgorithm::Perceptual => "phash",
+        }
+    }
+}
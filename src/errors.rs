@@ -1,3 +1,5 @@
+use crate::download::archive::ArchiveKey;
+
 use std::error::Error;
 use std::fmt;
 
@@ -33,3 +35,468 @@ impl fmt::Display for AuthError {
         )
     }
 }
+
+/// Error returned on failure to call the pixiv API.
+#[derive(Debug)]
+pub enum ApiError {
+    Request(reqwest::Error),
+    /// The response is not the expected json.
+    Json(serde_json::Error),
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::Request(e) => Some(e),
+            ApiError::Json(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::Request(e) => write!(f, "Failed to request the response. Reason: {}", e),
+            ApiError::Json(e) => write!(f, "Failed to parse the response. Reason: {}", e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        ApiError::Request(e)
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Json(e)
+    }
+}
+
+/// Error returned on failure to export a novel as an EPUB.
+#[derive(Debug)]
+pub enum EpubError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    Request(reqwest::Error),
+}
+
+impl Error for EpubError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            EpubError::Io(e) => Some(e),
+            EpubError::Zip(e) => Some(e),
+            EpubError::Request(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for EpubError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpubError::Io(e) => write!(f, "Failed to write the EPUB. Reason: {}", e),
+            EpubError::Zip(e) => write!(f, "Failed to write the EPUB archive. Reason: {}", e),
+            EpubError::Request(e) => write!(f, "Failed to download an image. Reason: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for EpubError {
+    fn from(e: std::io::Error) -> Self {
+        EpubError::Io(e)
+    }
+}
+
+impl From<zip::result::ZipError> for EpubError {
+    fn from(e: zip::result::ZipError) -> Self {
+        EpubError::Zip(e)
+    }
+}
+
+impl From<reqwest::Error> for EpubError {
+    fn from(e: reqwest::Error) -> Self {
+        EpubError::Request(e)
+    }
+}
+
+/// Error returned on failure to package a manga as a CBZ.
+#[derive(Debug)]
+pub enum CbzError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    Request(reqwest::Error),
+    /// There were no works to package.
+    Empty,
+}
+
+impl Error for CbzError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CbzError::Io(e) => Some(e),
+            CbzError::Zip(e) => Some(e),
+            CbzError::Request(e) => Some(e),
+            CbzError::Empty => None,
+        }
+    }
+}
+
+impl fmt::Display for CbzError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CbzError::Io(e) => write!(f, "Failed to write the CBZ. Reason: {}", e),
+            CbzError::Zip(e) => write!(f, "Failed to write the CBZ archive. Reason: {}", e),
+            CbzError::Request(e) => write!(f, "Failed to download a page. Reason: {}", e),
+            CbzError::Empty => write!(f, "Failed to write the CBZ. Reason: no works to package"),
+        }
+    }
+}
+
+impl From<std::io::Error> for CbzError {
+    fn from(e: std::io::Error) -> Self {
+        CbzError::Io(e)
+    }
+}
+
+impl From<zip::result::ZipError> for CbzError {
+    fn from(e: zip::result::ZipError) -> Self {
+        CbzError::Zip(e)
+    }
+}
+
+impl From<reqwest::Error> for CbzError {
+    fn from(e: reqwest::Error) -> Self {
+        CbzError::Request(e)
+    }
+}
+
+/// Error returned on failure to download a file.
+#[derive(Debug)]
+pub enum DownloadError {
+    Io(std::io::Error),
+    Request(reqwest::Error),
+    /// The connection ended before the announced Content-Length was received.
+    LengthMismatch {
+        expected: u64,
+        written: u64,
+    },
+    /// The download was cancelled before it completed.
+    Cancelled,
+    /// The file was not downloaded, its work looks like the one of this key.
+    Duplicate(ArchiveKey),
+}
+
+impl Error for DownloadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DownloadError::Io(e) => Some(e),
+            DownloadError::Request(e) => Some(e),
+            DownloadError::LengthMismatch { .. }
+            | DownloadError::Cancelled
+            | DownloadError::Duplicate(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DownloadError::Io(e) => write!(f, "Failed to write the file. Reason: {}", e),
+            DownloadError::Request(e) => write!(f, "Failed to download the file. Reason: {}", e),
+            DownloadError::LengthMismatch { expected, written } => write!(
+                f,
+                "Failed to download the file. Reason: expected {} bytes but received {}",
+                expected, written
+            ),
+            DownloadError::Cancelled => write!(f, "Failed to download the file. Reason: cancelled"),
+            DownloadError::Duplicate(key) => {
+                write!(f, "Skipped the file. Reason: near duplicate of {}", key)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for DownloadError {
+    fn from(e: std::io::Error) -> Self {
+        DownloadError::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(e: reqwest::Error) -> Self {
+        DownloadError::Request(e)
+    }
+}
+
+/// Error returned on failure to embed metadata into an image.
+#[derive(Debug)]
+pub enum MetadataError {
+    Io(std::io::Error),
+    /// Only JPEG and PNG files are supported.
+    UnsupportedFormat,
+    /// The file is not a well formed JPEG or PNG, the reason says where it went wrong.
+    Malformed(&'static str),
+    /// The metadata doesn't fit in a JPEG segment.
+    TooLarge,
+}
+
+impl Error for MetadataError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MetadataError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataError::Io(e) => write!(f, "Failed to embed the metadata. Reason: {}", e),
+            MetadataError::UnsupportedFormat => write!(
+                f,
+                "Failed to embed the metadata. Reason: the file is neither a JPEG nor a PNG"
+            ),
+            MetadataError::Malformed(reason) => {
+                write!(f, "Failed to embed the metadata. Reason: {}", reason)
+            }
+            MetadataError::TooLarge => write!(
+                f,
+                "Failed to embed the metadata. Reason: it is larger than a JPEG segment"
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for MetadataError {
+    fn from(e: std::io::Error) -> Self {
+        MetadataError::Io(e)
+    }
+}
+
+/// Error returned on failure to sync with the pixiv account.
+#[derive(Debug)]
+pub enum SyncError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Request(reqwest::Error),
+    Api(ApiError),
+    /// A watchlist sink could not take a hit.
+    Sink(Box<dyn Error + Send + Sync>),
+    /// A backup file that can't be read back.
+    InvalidBackup(String),
+}
+
+impl Error for SyncError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SyncError::Io(e) => Some(e),
+            SyncError::Json(e) => Some(e),
+            SyncError::Request(e) => Some(e),
+            SyncError::Api(e) => Some(e),
+            SyncError::Sink(e) => Some(e.as_ref()),
+            SyncError::InvalidBackup(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyncError::Io(e) => write!(f, "Failed to access the sync state. Reason: {}", e),
+            SyncError::Json(e) => write!(f, "Failed to parse the sync state. Reason: {}", e),
+            SyncError::Request(e) => write!(f, "Failed to fetch from pixiv. Reason: {}", e),
+            SyncError::Api(e) => write!(f, "{}", e),
+            SyncError::Sink(e) => write!(f, "Failed to deliver the hit. Reason: {}", e),
+            SyncError::InvalidBackup(reason) => write!(f, "The backup is invalid: {}", reason),
+        }
+    }
+}
+
+impl From<std::io::Error> for SyncError {
+    fn from(e: std::io::Error) -> Self {
+        SyncError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SyncError {
+    fn from(e: serde_json::Error) -> Self {
+        SyncError::Json(e)
+    }
+}
+
+impl From<reqwest::Error> for SyncError {
+    fn from(e: reqwest::Error) -> Self {
+        SyncError::Request(e)
+    }
+}
+
+impl From<ApiError> for SyncError {
+    fn from(e: ApiError) -> Self {
+        SyncError::Api(e)
+    }
+}
+
+/// Error returned on failure to parse a path template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{` without its closing `}`, at the given byte offset.
+    UnclosedField(usize),
+    /// A `}` that doesn't close a field, at the given byte offset.
+    UnmatchedBrace(usize),
+    UnknownField(String),
+}
+
+impl Error for TemplateError {}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::UnclosedField(at) => write!(
+                f,
+                "Failed to parse the template. Reason: unclosed field at {}",
+                at
+            ),
+            TemplateError::UnmatchedBrace(at) => write!(
+                f,
+                "Failed to parse the template. Reason: unmatched }} at {}",
+                at
+            ),
+            TemplateError::UnknownField(field) => write!(
+                f,
+                "Failed to parse the template. Reason: unknown field {{{}}}",
+                field
+            ),
+        }
+    }
+}
+
+/// Error returned on failure to convert an ugoira into an animation.
+#[cfg(feature = "ugoira")]
+#[derive(Debug)]
+pub enum ConversionError {
+    Io(std::io::Error),
+    Zip(zip::result::ZipError),
+    Image(image::ImageError),
+    Encoding(Box<dyn Error + Send + Sync>),
+    /// A frame listed in the metadata is not in the zip.
+    MissingFrame(String),
+    NoFrames,
+}
+
+#[cfg(feature = "ugoira")]
+impl Error for ConversionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConversionError::Io(e) => Some(e),
+            ConversionError::Zip(e) => Some(e),
+            ConversionError::Image(e) => Some(e),
+            ConversionError::Encoding(e) => Some(e.as_ref()),
+            ConversionError::MissingFrame(_) | ConversionError::NoFrames => None,
+        }
+    }
+}
+
+#[cfg(feature = "ugoira")]
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::Io(e) => write!(f, "Failed to write the animation. Reason: {}", e),
+            ConversionError::Zip(e) => write!(f, "Failed to read the frame zip. Reason: {}", e),
+            ConversionError::Image(e) => write!(f, "Failed to decode a frame. Reason: {}", e),
+            ConversionError::Encoding(e) => {
+                write!(f, "Failed to encode the animation. Reason: {}", e)
+            }
+            ConversionError::MissingFrame(file) => {
+                write!(f, "Frame {:?} is missing from the frame zip.", file)
+            }
+            ConversionError::NoFrames => write!(f, "The ugoira has no frames."),
+        }
+    }
+}
+
+#[cfg(feature = "ugoira")]
+impl From<std::io::Error> for ConversionError {
+    fn from(e: std::io::Error) -> Self {
+        ConversionError::Io(e)
+    }
+}
+
+#[cfg(feature = "ugoira")]
+impl From<zip::result::ZipError> for ConversionError {
+    fn from(e: zip::result::ZipError) -> Self {
+        ConversionError::Zip(e)
+    }
+}
+
+#[cfg(feature = "ugoira")]
+impl From<image::ImageError> for ConversionError {
+    fn from(e: image::ImageError) -> Self {
+        ConversionError::Image(e)
+    }
+}
+
+#[cfg(feature = "ugoira")]
+impl From<gif::EncodingError> for ConversionError {
+    fn from(e: gif::EncodingError) -> Self {
+        ConversionError::Encoding(Box::new(e))
+    }
+}
+
+#[cfg(feature = "ugoira")]
+impl From<png::EncodingError> for ConversionError {
+    fn from(e: png::EncodingError) -> Self {
+        ConversionError::Encoding(Box::new(e))
+    }
+}
+
+#[cfg(feature = "ugoira")]
+impl From<image_webp::EncodingError> for ConversionError {
+    fn from(e: image_webp::EncodingError) -> Self {
+        ConversionError::Encoding(Box::new(e))
+    }
+}
+
+/// Error returned on failure to read or write the illustration catalog.
+#[cfg(feature = "storage")]
+#[derive(Debug)]
+pub enum StorageError {
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+}
+
+#[cfg(feature = "storage")]
+impl Error for StorageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StorageError::Sqlite(e) => Some(e),
+            StorageError::Json(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "storage")]
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::Sqlite(e) => write!(f, "Failed to access the catalog. Reason: {}", e),
+            StorageError::Json(e) => {
+                write!(f, "Failed to (de)serialize a catalog record. Reason: {}", e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "storage")]
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+#[cfg(feature = "storage")]
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Json(e)
+    }
+}
@@ -1,16 +1,28 @@
 extern crate bytes;
 extern crate chrono;
+extern crate crc32fast;
 extern crate dotenv;
 extern crate http;
 extern crate md5;
 extern crate reqwest;
+#[cfg(feature = "storage")]
+extern crate rusqlite;
 extern crate serde;
 extern crate serde_json;
 extern crate serde_urlencoded;
+extern crate sha2;
 extern crate url;
+extern crate zip;
 
 pub mod constants;
+pub mod download;
 pub mod enums;
 pub mod errors;
+pub mod novel;
 pub mod pixiv;
+#[cfg(feature = "storage")]
+pub mod storage;
+pub mod sync;
+#[cfg(feature = "ugoira")]
+pub mod ugoira;
 pub mod utils;
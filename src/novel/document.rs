@@ -0,0 +1,113 @@
+/// A novel text, split into the pages separated by `[newpage]`.
+/// Created by `novel::parser::parse`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Document {
+    pages: Vec<Page>,
+}
+
+impl Document {
+    pub fn new(pages: Vec<Page>) -> Self {
+        Document { pages }
+    }
+
+    pub fn pages(&self) -> &Vec<Page> {
+        &self.pages
+    }
+
+    /// Chapters as `(page index, title)` pairs, in reading order.
+    pub fn chapters(&self) -> Vec<(usize, &Vec<Inline>)> {
+        self.pages
+            .iter()
+            .enumerate()
+            .flat_map(|(index, page)| {
+                page.blocks.iter().filter_map(move |block| match block {
+                    Block::Chapter(title) => Some((index, title)),
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    /// Every image referenced by the text, in reading order.
+    pub fn images(&self) -> Vec<&Image> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.blocks.iter())
+            .filter_map(|block| match block {
+                Block::Image(image) => Some(image),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn into_inner(self) -> Vec<Page> {
+        self.pages
+    }
+}
+
+/// A single page of a novel.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Page {
+    blocks: Vec<Block>,
+}
+
+impl Page {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Page { blocks }
+    }
+
+    pub fn blocks(&self) -> &Vec<Block> {
+        &self.blocks
+    }
+
+    pub fn into_inner(self) -> Vec<Block> {
+        self.blocks
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// `[chapter:...]`
+    Chapter(Vec<Inline>),
+    /// A line of text. Empty lines are kept as empty paragraphs.
+    Paragraph(Vec<Inline>),
+    /// `[pixivimage:...]` or `[uploadedimage:...]`
+    Image(Image),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    /// `[[rb:base > reading]]`
+    Ruby {
+        base: String,
+        reading: String,
+    },
+    /// `[[jumpuri:text > url]]`
+    Link {
+        text: String,
+        url: String,
+    },
+    /// `[jump:page]`, the page number starts at 1.
+    PageJump(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Image {
+    /// An illustration posted on pixiv. `page` starts at 1, `None` means the first page.
+    Pixiv { illust_id: u32, page: Option<u32> },
+    /// An image uploaded along with the novel.
+    Uploaded { image_id: u32 },
+}
+
+impl Inline {
+    /// The text shown to the reader, without ruby readings.
+    pub fn text(&self) -> String {
+        match self {
+            Inline::Text(text) => text.clone(),
+            Inline::Ruby { base, .. } => base.clone(),
+            Inline::Link { text, .. } => text.clone(),
+            Inline::PageJump(page) => page.to_string(),
+        }
+    }
+}
@@ -0,0 +1,566 @@
+use crate::errors::EpubError;
+use crate::novel::document::{Document, Image};
+use crate::novel::parser::parse;
+use crate::novel::render::to_html_with;
+use crate::pixiv::client::PixivClient;
+use crate::pixiv::helper_structs::novel::Novel;
+use crate::pixiv::helper_structs::novel_series_detail::NovelSeriesDetail;
+use crate::pixiv::result::novel_text::NovelText;
+use crate::utils::{caption_to_text, escape_html};
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const STYLESHEET: &str = "body { line-height: 1.8; }\n\
+    h1, h2 { line-height: 1.4; }\n\
+    section { margin-bottom: 2em; }\n\
+    img { max-width: 100%; }\n";
+
+/// Metadata written to the package document of an EPUB.
+#[derive(Debug, Clone)]
+pub struct EpubMetadata {
+    identifier: String,
+    title: String,
+    author: String,
+    language: String,
+    description: String,
+    source: String,
+    tags: Vec<String>,
+    date: String,
+}
+
+impl EpubMetadata {
+    pub fn new<T, U>(identifier: T, title: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        EpubMetadata {
+            identifier: identifier.into(),
+            title: title.into(),
+            author: String::new(),
+            language: String::from("ja"),
+            description: String::new(),
+            source: String::new(),
+            tags: Vec::new(),
+            date: String::new(),
+        }
+    }
+
+    /// Metadata of a single novel: its author, caption, tags and creation date.
+    pub fn from_novel(novel: &Novel) -> Self {
+        EpubMetadata::new(format!("urn:pixiv:novel:{}", novel.id()), novel.title())
+            .set_author(novel.user().name().as_str())
+            .set_description(caption_to_text(novel.caption()))
+            .set_source(format!(
+                "https://www.pixiv.net/novel/show.php?id={}",
+                novel.id()
+            ))
+            .set_tags(novel.tags().iter().map(|tag| tag.name().clone()).collect())
+            .set_date(novel.create_date().as_str())
+    }
+
+    /// Metadata of a novel series. Tags are merged from every novel, the date is the one of
+    /// the first novel.
+    pub fn from_series(series: &NovelSeriesDetail, novels: &[&Novel]) -> Self {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in novels.iter().flat_map(|novel| novel.tags()) {
+            if !tags.contains(tag.name()) {
+                tags.push(tag.name().clone());
+            }
+        }
+        let date = novels
+            .first()
+            .map(|novel| novel.create_date().clone())
+            .unwrap_or_default();
+
+        EpubMetadata::new(
+            format!("urn:pixiv:novel-series:{}", series.id()),
+            series.title(),
+        )
+        .set_author(series.user().name().as_str())
+        .set_description(caption_to_text(series.caption()))
+        .set_source(format!(
+            "https://www.pixiv.net/novel/series/{}",
+            series.id()
+        ))
+        .set_tags(tags)
+        .set_date(date)
+    }
+
+    pub fn set_author<T>(mut self, value: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.author = value.into();
+        self
+    }
+
+    /// BCP 47 language tag, `ja` by default.
+    pub fn set_language<T>(mut self, value: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.language = value.into();
+        self
+    }
+
+    pub fn set_description<T>(mut self, value: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.description = value.into();
+        self
+    }
+
+    pub fn set_source<T>(mut self, value: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.source = value.into();
+        self
+    }
+
+    pub fn set_tags(mut self, value: Vec<String>) -> Self {
+        self.tags = value;
+        self
+    }
+
+    /// Creation date, formatted as in pixiv responses (RFC 3339).
+    pub fn set_date<T>(mut self, value: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.date = value.into();
+        self
+    }
+}
+
+/// A file stored in the archive, other than the generated documents.
+#[derive(Debug, Clone)]
+struct Resource {
+    path: String,
+    media_type: &'static str,
+    data: Vec<u8>,
+}
+
+/// One xhtml document of the book, holding a whole novel.
+#[derive(Debug, Clone)]
+struct Section {
+    title: String,
+    body: String,
+    chapters: Vec<String>,
+}
+
+/// An EPUB 3 book under construction.
+/// Novels are added with `add_document`, images with `add_image`, and the result is written
+/// with `write`.
+#[derive(Debug, Clone)]
+pub struct Epub {
+    metadata: EpubMetadata,
+    cover: Option<Resource>,
+    sections: Vec<Section>,
+    images: Vec<Resource>,
+}
+
+impl Epub {
+    pub fn new(metadata: EpubMetadata) -> Self {
+        Epub {
+            metadata,
+            cover: None,
+            sections: Vec::new(),
+            images: Vec::new(),
+        }
+    }
+
+    /// Sets the cover. `file_name` is only used to guess the media type.
+    pub fn set_cover(&mut self, file_name: &str, data: Vec<u8>) {
+        self.cover = Some(Resource {
+            path: format!("images/cover.{}", extension(file_name)),
+            media_type: media_type(file_name),
+            data,
+        });
+    }
+
+    /// Stores an image and returns the path to use from a document of the book.
+    /// An image added again under the same `file_name` is only stored once.
+    pub fn add_image(&mut self, file_name: &str, data: Vec<u8>) -> String {
+        let path = format!("images/{}", file_name);
+        if self.images.iter().any(|image| image.path == path) {
+            return format!("../{}", path);
+        }
+        self.images.push(Resource {
+            path: path.clone(),
+            media_type: media_type(file_name),
+            data,
+        });
+        format!("../{}", path)
+    }
+
+    /// Adds a novel to the book.
+    /// `image_source` gives the path returned by `add_image` for each image of the document.
+    pub fn add_document<F>(&mut self, title: &str, document: &Document, image_source: F)
+    where
+        F: Fn(&Image) -> Option<String>,
+    {
+        let chapters = document
+            .chapters()
+            .into_iter()
+            .map(|(_, title)| title.iter().map(|inline| inline.text()).collect())
+            .collect();
+        self.sections.push(Section {
+            title: title.to_string(),
+            body: to_html_with(document, image_source),
+            chapters,
+        });
+    }
+
+    /// Writes the book as an EPUB 3 archive and returns the writer.
+    pub fn write<W>(&self, mut writer: W) -> Result<W, EpubError>
+    where
+        W: Write,
+    {
+        // The archive is assembled in memory since its central directory needs seeking.
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        // The mimetype has to be the first entry, uncompressed.
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(b"application/epub+zip")?;
+
+        zip.start_file("META-INF/container.xml", deflated)?;
+        zip.write_all(CONTAINER.as_bytes())?;
+
+        zip.start_file("OEBPS/content.opf", deflated)?;
+        zip.write_all(self.package_document().as_bytes())?;
+
+        zip.start_file("OEBPS/nav.xhtml", deflated)?;
+        zip.write_all(self.navigation_document().as_bytes())?;
+
+        zip.start_file("OEBPS/style.css", deflated)?;
+        zip.write_all(STYLESHEET.as_bytes())?;
+
+        if let Some(cover) = &self.cover {
+            zip.start_file("OEBPS/text/cover.xhtml", deflated)?;
+            let body = format!(
+                "<div class=\"cover\"><img src=\"../{}\" alt=\"{}\" /></div>",
+                cover.path,
+                escape_html(&self.metadata.title)
+            );
+            zip.write_all(
+                self.xhtml(&self.metadata.title, "../style.css", &body)
+                    .as_bytes(),
+            )?;
+
+            zip.start_file(format!("OEBPS/{}", cover.path), stored)?;
+            zip.write_all(&cover.data)?;
+        }
+
+        for (index, section) in self.sections.iter().enumerate() {
+            zip.start_file(format!("OEBPS/{}", section_path(index)), deflated)?;
+            let body = format!("<h1>{}</h1>\n{}", escape_html(&section.title), section.body);
+            zip.write_all(self.xhtml(&section.title, "../style.css", &body).as_bytes())?;
+        }
+
+        // Images are already compressed.
+        for image in self.images.iter() {
+            zip.start_file(format!("OEBPS/{}", image.path), stored)?;
+            zip.write_all(&image.data)?;
+        }
+
+        let archive = zip.finish()?.into_inner();
+        writer.write_all(&archive)?;
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    fn package_document(&self) -> String {
+        let metadata = &self.metadata;
+        let mut opf = String::new();
+
+        opf.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            opf,
+            "<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{}\">",
+            escape_html(&metadata.language)
+        );
+        opf.push_str("<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+        let _ = writeln!(
+            opf,
+            "<dc:identifier id=\"book-id\">{}</dc:identifier>",
+            escape_html(&metadata.identifier)
+        );
+        let _ = writeln!(opf, "<dc:title>{}</dc:title>", escape_html(&metadata.title));
+        let _ = writeln!(
+            opf,
+            "<dc:language>{}</dc:language>",
+            escape_html(&metadata.language)
+        );
+        if !metadata.author.is_empty() {
+            let _ = writeln!(
+                opf,
+                "<dc:creator>{}</dc:creator>",
+                escape_html(&metadata.author)
+            );
+        }
+        if !metadata.date.is_empty() {
+            let _ = writeln!(opf, "<dc:date>{}</dc:date>", escape_html(&metadata.date));
+        }
+        if !metadata.description.is_empty() {
+            let _ = writeln!(
+                opf,
+                "<dc:description>{}</dc:description>",
+                escape_html(&metadata.description)
+            );
+        }
+        if !metadata.source.is_empty() {
+            let _ = writeln!(
+                opf,
+                "<dc:source>{}</dc:source>",
+                escape_html(&metadata.source)
+            );
+        }
+        for tag in metadata.tags.iter() {
+            let _ = writeln!(opf, "<dc:subject>{}</dc:subject>", escape_html(tag));
+        }
+        let _ = writeln!(
+            opf,
+            "<meta property=\"dcterms:modified\">{}</meta>",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+        );
+        if self.cover.is_some() {
+            opf.push_str("<meta name=\"cover\" content=\"cover-image\" />\n");
+        }
+        opf.push_str("</metadata>\n<manifest>\n");
+
+        opf.push_str("<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\" />\n");
+        opf.push_str("<item id=\"style\" href=\"style.css\" media-type=\"text/css\" />\n");
+        if let Some(cover) = &self.cover {
+            let _ = writeln!(
+                opf,
+                "<item id=\"cover-image\" href=\"{}\" media-type=\"{}\" properties=\"cover-image\" />",
+                cover.path, cover.media_type
+            );
+            opf.push_str("<item id=\"cover\" href=\"text/cover.xhtml\" media-type=\"application/xhtml+xml\" />\n");
+        }
+        for index in 0..self.sections.len() {
+            let _ = writeln!(
+                opf,
+                "<item id=\"section-{}\" href=\"{}\" media-type=\"application/xhtml+xml\" />",
+                index + 1,
+                section_path(index)
+            );
+        }
+        for (index, image) in self.images.iter().enumerate() {
+            let _ = writeln!(
+                opf,
+                "<item id=\"image-{}\" href=\"{}\" media-type=\"{}\" />",
+                index + 1,
+                escape_html(&image.path),
+                image.media_type
+            );
+        }
+        opf.push_str("</manifest>\n<spine>\n");
+
+        if self.cover.is_some() {
+            opf.push_str("<itemref idref=\"cover\" linear=\"no\" />\n");
+        }
+        for index in 0..self.sections.len() {
+            let _ = writeln!(opf, "<itemref idref=\"section-{}\" />", index + 1);
+        }
+        opf.push_str("</spine>\n</package>\n");
+
+        opf
+    }
+
+    fn navigation_document(&self) -> String {
+        let mut body =
+            String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+        for (index, section) in self.sections.iter().enumerate() {
+            let path = section_path(index);
+            let _ = write!(
+                body,
+                "<li><a href=\"{}\">{}</a>",
+                path,
+                escape_html(&section.title)
+            );
+            if !section.chapters.is_empty() {
+                body.push_str("\n<ol>\n");
+                for (chapter, title) in section.chapters.iter().enumerate() {
+                    let _ = writeln!(
+                        body,
+                        "<li><a href=\"{}#chapter-{}\">{}</a></li>",
+                        path,
+                        chapter + 1,
+                        escape_html(title)
+                    );
+                }
+                body.push_str("</ol>\n");
+            }
+            body.push_str("</li>\n");
+        }
+        body.push_str("</ol>\n</nav>");
+
+        self.xhtml(&self.metadata.title, "style.css", &body)
+    }
+
+    /// `stylesheet` is relative to the document.
+    fn xhtml(&self, title: &str, stylesheet: &str, body: &str) -> String {
+        let language = escape_html(&self.metadata.language);
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE html>\n\
+             <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{}\" lang=\"{}\">\n\
+             <head>\n<meta charset=\"UTF-8\" />\n<title>{}</title>\n\
+             <link rel=\"stylesheet\" type=\"text/css\" href=\"{}\" />\n</head>\n\
+             <body>\n{}\n</body>\n</html>\n",
+            language,
+            language,
+            escape_html(title),
+            stylesheet,
+            body
+        )
+    }
+}
+
+const CONTAINER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+    <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+    <rootfiles>\n\
+    <rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\" />\n\
+    </rootfiles>\n\
+    </container>\n";
+
+fn section_path(index: usize) -> String {
+    format!("text/section-{}.xhtml", index + 1)
+}
+
+fn extension(file_name: &str) -> &str {
+    file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .unwrap_or("jpg")
+}
+
+fn media_type(file_name: &str) -> &'static str {
+    match extension(file_name).to_ascii_lowercase().as_str() {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => "image/jpeg",
+    }
+}
+
+/// Exports a single novel, downloading its cover and embedded images with `client`.
+pub fn export_novel<W>(
+    client: &PixivClient,
+    novel: &Novel,
+    text: &NovelText,
+    writer: W,
+) -> Result<W, EpubError>
+where
+    W: Write,
+{
+    let mut epub = Epub::new(EpubMetadata::from_novel(novel));
+    add_cover(&mut epub, client, novel)?;
+    add_novel(&mut epub, client, novel, text, &mut HashMap::new())?;
+    epub.write(writer)
+}
+
+/// Exports a novel series, one document per novel in the given order.
+/// The cover is the one of the first novel.
+pub fn export_series<W>(
+    client: &PixivClient,
+    series: &NovelSeriesDetail,
+    novels: &[(Novel, NovelText)],
+    writer: W,
+) -> Result<W, EpubError>
+where
+    W: Write,
+{
+    let metadata = EpubMetadata::from_series(
+        series,
+        &novels
+            .iter()
+            .map(|(novel, _)| novel)
+            .collect::<Vec<&Novel>>(),
+    );
+    let mut epub = Epub::new(metadata);
+    if let Some((novel, _)) = novels.first() {
+        add_cover(&mut epub, client, novel)?;
+    }
+    // Shared by every novel, so that an image embedded in several novels is fetched once.
+    let mut sources = HashMap::new();
+    for (novel, text) in novels.iter() {
+        add_novel(&mut epub, client, novel, text, &mut sources)?;
+    }
+    epub.write(writer)
+}
+
+fn add_cover(epub: &mut Epub, client: &PixivClient, novel: &Novel) -> Result<(), EpubError> {
+    let urls = novel.image_urls();
+    if let Some(url) = urls.large.as_ref().or(urls.medium.as_ref()) {
+        epub.set_cover(url, fetch(client, url)?);
+    }
+    Ok(())
+}
+
+fn add_novel(
+    epub: &mut Epub,
+    client: &PixivClient,
+    novel: &Novel,
+    text: &NovelText,
+    sources: &mut HashMap<Image, String>,
+) -> Result<(), EpubError> {
+    let document = parse(text.text());
+
+    for image in document.images() {
+        if sources.contains_key(image) {
+            continue;
+        }
+        let url = match image_url(text, image) {
+            Some(url) => url,
+            None => continue,
+        };
+        let file_name = match image {
+            Image::Pixiv { illust_id, page } => format!(
+                "pixiv-{}-{}.{}",
+                illust_id,
+                page.unwrap_or(1),
+                extension(&url)
+            ),
+            Image::Uploaded { image_id } => {
+                format!("uploaded-{}-{}.{}", novel.id(), image_id, extension(&url))
+            }
+        };
+        let source = epub.add_image(&file_name, fetch(client, &url)?);
+        sources.insert(image.clone(), source);
+    }
+
+    epub.add_document(novel.title(), &document, |image| {
+        sources.get(image).cloned()
+    });
+    Ok(())
+}
+
+/// Finds the url of an image embedded in a novel in the images sent along with its text.
+/// Returns `None` for images that are not available anymore.
+fn image_url(text: &NovelText, image: &Image) -> Option<String> {
+    let urls = match image {
+        Image::Pixiv { illust_id, page } => text
+            .illust(*illust_id, *page)
+            .and_then(|embedded| embedded.illust())
+            .map(|illust| illust.images()),
+        Image::Uploaded { image_id } => text.image(*image_id).map(|image| image.urls()),
+    };
+    urls.and_then(|urls| urls.largest()).cloned()
+}
+
+/// Downloads an image from pixiv's image servers, which require a pixiv referer.
+fn fetch(client: &PixivClient, url: &str) -> Result<Vec<u8>, EpubError> {
+    Ok(client.downloader().fetch(url)?)
+}
@@ -0,0 +1,4 @@
+pub mod document;
+pub mod epub;
+pub mod parser;
+pub mod render;
@@ -0,0 +1,172 @@
+use crate::novel::document::{Block, Document, Image, Inline, Page};
+
+const NEW_PAGE: &str = "[newpage]";
+
+/// Parses a novel text written with pixiv's novel markup.
+/// Malformed or unknown tags are kept as plain text, so this never fails.
+pub fn parse(text: &str) -> Document {
+    let text = text.replace("\r\n", "\n");
+    Document::new(text.split(NEW_PAGE).map(parse_page).collect())
+}
+
+fn parse_page(text: &str) -> Page {
+    let text = text.trim_matches('\n');
+    if text.is_empty() {
+        return Page::default();
+    }
+    Page::new(text.split('\n').flat_map(parse_line).collect())
+}
+
+/// Element found while scanning a line.
+enum Token {
+    Inline(Inline),
+    Block(Block),
+}
+
+fn parse_line(line: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut inlines = Vec::new();
+
+    for token in tokenize(line) {
+        match token {
+            Token::Inline(inline) => push_inline(&mut inlines, inline),
+            Token::Block(block) => {
+                if !inlines.is_empty() {
+                    blocks.push(Block::Paragraph(std::mem::take(&mut inlines)));
+                }
+                blocks.push(block);
+            }
+        }
+    }
+
+    if !inlines.is_empty() || blocks.is_empty() {
+        blocks.push(Block::Paragraph(inlines));
+    }
+    blocks
+}
+
+/// Merges consecutive text so that literal brackets do not split it.
+fn push_inline(inlines: &mut Vec<Inline>, inline: Inline) {
+    if let (Some(Inline::Text(last)), Inline::Text(text)) = (inlines.last_mut(), &inline) {
+        last.push_str(text);
+        return;
+    }
+    inlines.push(inline);
+}
+
+fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = line;
+
+    while !rest.is_empty() {
+        let start = match rest.find('[') {
+            Some(start) => start,
+            None => {
+                tokens.push(Token::Inline(Inline::Text(rest.to_string())));
+                break;
+            }
+        };
+        if start > 0 {
+            tokens.push(Token::Inline(Inline::Text(rest[..start].to_string())));
+            rest = &rest[start..];
+        }
+
+        match parse_tag(rest) {
+            Some((token, length)) => {
+                tokens.push(token);
+                rest = &rest[length..];
+            }
+            None => {
+                tokens.push(Token::Inline(Inline::Text(String::from("["))));
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    tokens
+}
+
+/// Parses the tag at the start of `text`, returning it with the number of bytes consumed.
+fn parse_tag(text: &str) -> Option<(Token, usize)> {
+    if let Some(body) = text.strip_prefix("[[") {
+        let end = body.find("]]")?;
+        let length = end + 4;
+        let body = &body[..end];
+
+        if let Some(ruby) = body.strip_prefix("rb:") {
+            let (base, reading) = ruby.split_once('>')?;
+            let inline = Inline::Ruby {
+                base: base.trim().to_string(),
+                reading: reading.trim().to_string(),
+            };
+            return Some((Token::Inline(inline), length));
+        }
+        if let Some(link) = body.strip_prefix("jumpuri:") {
+            let (text, url) = link.split_once('>')?;
+            let inline = Inline::Link {
+                text: text.trim().to_string(),
+                url: url.trim().to_string(),
+            };
+            return Some((Token::Inline(inline), length));
+        }
+        return None;
+    }
+
+    let body = &text[1..];
+    if let Some(title) = body.strip_prefix("chapter:") {
+        let end = find_closing_bracket(title)?;
+        let title = tokenize(&title[..end])
+            .into_iter()
+            .fold(Vec::new(), |mut acc, token| {
+                match token {
+                    Token::Inline(inline) => push_inline(&mut acc, inline),
+                    Token::Block(_) => {}
+                }
+                acc
+            });
+        return Some((Token::Block(Block::Chapter(title)), end + 10));
+    }
+
+    let end = body.find(']')?;
+    let length = end + 2;
+    let body = &body[..end];
+
+    if let Some(id) = body.strip_prefix("pixivimage:") {
+        let (illust_id, page) = match id.split_once('-') {
+            Some((illust_id, page)) => (illust_id, Some(page.trim().parse().ok()?)),
+            None => (id, None),
+        };
+        let image = Image::Pixiv {
+            illust_id: illust_id.trim().parse().ok()?,
+            page,
+        };
+        return Some((Token::Block(Block::Image(image)), length));
+    }
+    if let Some(id) = body.strip_prefix("uploadedimage:") {
+        let image = Image::Uploaded {
+            image_id: id.trim().parse().ok()?,
+        };
+        return Some((Token::Block(Block::Image(image)), length));
+    }
+    if let Some(page) = body.strip_prefix("jump:") {
+        let inline = Inline::PageJump(page.trim().parse().ok()?);
+        return Some((Token::Inline(inline), length));
+    }
+    None
+}
+
+/// Finds the `]` closing a chapter, skipping over the `[[...]]` tags of its title.
+fn find_closing_bracket(text: &str) -> Option<usize> {
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text[index..];
+        if rest.starts_with("[[") {
+            index += rest.find("]]")? + 2;
+        } else if rest.starts_with(']') {
+            return Some(index);
+        } else {
+            index += rest.chars().next()?.len_utf8();
+        }
+    }
+    None
+}
@@ -0,0 +1,204 @@
+use crate::novel::document::{Block, Document, Image, Inline};
+use crate::utils::artwork_url;
+pub use crate::utils::escape_html;
+
+use std::fmt::Write;
+
+/// Renders the document as plain text.
+/// Ruby readings are written in parentheses after their base, images are left out
+/// and pages are separated by an empty line.
+pub fn to_plain_text(document: &Document) -> String {
+    let pages: Vec<String> = document
+        .pages()
+        .iter()
+        .map(|page| {
+            page.blocks()
+                .iter()
+                .filter_map(|block| match block {
+                    Block::Chapter(title) | Block::Paragraph(title) => Some(
+                        title
+                            .iter()
+                            .map(|inline| match inline {
+                                Inline::Ruby { base, reading } => format!("{}({})", base, reading),
+                                Inline::Link { text, url } => format!("{} ({})", text, url),
+                                Inline::PageJump(page) => format!("(p. {})", page),
+                                Inline::Text(text) => text.clone(),
+                            })
+                            .collect::<String>(),
+                    ),
+                    Block::Image(_) => None,
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        })
+        .collect();
+    pages.join("\n\n")
+}
+
+/// Renders the document as Markdown, see `to_markdown_with`.
+pub fn to_markdown(document: &Document) -> String {
+    to_markdown_with(document, |_| None)
+}
+
+/// Renders the document as Markdown.
+/// `image_source` gives the url or path of each image. Unresolved pixiv images are rendered
+/// as a link to the artwork, unresolved uploaded images are left out.
+/// Chapters become `##` headings and ruby is written as inline `<ruby>` html. Pages are
+/// separated by a horizontal rule and start with an `<a id="page-N">` anchor for page jumps.
+pub fn to_markdown_with<F>(document: &Document, image_source: F) -> String
+where
+    F: Fn(&Image) -> Option<String>,
+{
+    let mut output = String::new();
+
+    for (index, page) in document.pages().iter().enumerate() {
+        if index > 0 {
+            output.push_str("\n---\n\n");
+        }
+        let _ = writeln!(output, "<a id=\"page-{}\"></a>\n", index + 1);
+        for block in page.blocks() {
+            match block {
+                Block::Chapter(title) => {
+                    let _ = write!(output, "\n## {}\n\n", markdown_inlines(title));
+                }
+                Block::Paragraph(inlines) => {
+                    let _ = writeln!(output, "{}  ", markdown_inlines(inlines));
+                }
+                Block::Image(image) => match (image_source(image), image) {
+                    (Some(source), _) => {
+                        let _ = write!(output, "\n![]({})\n\n", escape_markdown_url(&source));
+                    }
+                    (None, Image::Pixiv { illust_id, .. }) => {
+                        let url = artwork_url(*illust_id);
+                        let _ = write!(output, "\n[{}]({})\n\n", url, url);
+                    }
+                    (None, Image::Uploaded { .. }) => {}
+                },
+            }
+        }
+    }
+
+    output
+}
+
+fn markdown_inlines(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => escape_markdown(text),
+            Inline::Ruby { base, reading } => format!(
+                "<ruby>{}<rt>{}</rt></ruby>",
+                escape_html(base),
+                escape_html(reading)
+            ),
+            Inline::Link { text, url } if is_web_url(url) => {
+                format!("[{}]({})", escape_markdown(text), escape_markdown_url(url))
+            }
+            Inline::Link { text, .. } => escape_markdown(text),
+            Inline::PageJump(page) => format!("[p. {}](#page-{})", page, page),
+        })
+        .collect()
+}
+
+/// Links come from the novel's text, only http(s) urls are rendered as links so that
+/// `javascript:` and `data:` urls never reach a reader.
+fn is_web_url(url: &str) -> bool {
+    let url = url.trim_start().to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// Percent-encodes the characters that would end a markdown link destination.
+fn escape_markdown_url(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Renders the document as an html fragment, see `to_html_with`.
+pub fn to_html(document: &Document) -> String {
+    to_html_with(document, |_| None)
+}
+
+/// Renders the document as an html fragment, one `<section id="page-N">` per page.
+/// Chapters are `<h2 id="chapter-N">` headings, numbered in the order of `Document::chapters`.
+/// `image_source` gives the url or path of each image. Unresolved pixiv images are rendered
+/// as a link to the artwork, unresolved uploaded images are left out.
+pub fn to_html_with<F>(document: &Document, image_source: F) -> String
+where
+    F: Fn(&Image) -> Option<String>,
+{
+    let mut output = String::new();
+    let mut chapter = 0;
+
+    for (index, page) in document.pages().iter().enumerate() {
+        let _ = writeln!(output, "<section id=\"page-{}\">", index + 1);
+        for block in page.blocks() {
+            match block {
+                Block::Chapter(title) => {
+                    chapter += 1;
+                    let _ = writeln!(
+                        output,
+                        "<h2 id=\"chapter-{}\">{}</h2>",
+                        chapter,
+                        html_inlines(title)
+                    );
+                }
+                Block::Paragraph(inlines) if inlines.is_empty() => {
+                    output.push_str("<p><br /></p>\n");
+                }
+                Block::Paragraph(inlines) => {
+                    let _ = writeln!(output, "<p>{}</p>", html_inlines(inlines));
+                }
+                Block::Image(image) => match (image_source(image), image) {
+                    (Some(source), _) => {
+                        let _ = writeln!(
+                            output,
+                            "<p><img src=\"{}\" alt=\"\" /></p>",
+                            escape_html(&source)
+                        );
+                    }
+                    (None, Image::Pixiv { illust_id, .. }) => {
+                        let url = artwork_url(*illust_id);
+                        let _ = writeln!(output, "<p><a href=\"{}\">{}</a></p>", url, url);
+                    }
+                    (None, Image::Uploaded { .. }) => {}
+                },
+            }
+        }
+        output.push_str("</section>\n");
+    }
+
+    output
+}
+
+fn html_inlines(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => escape_html(text),
+            Inline::Ruby { base, reading } => format!(
+                "<ruby>{}<rt>{}</rt></ruby>",
+                escape_html(base),
+                escape_html(reading)
+            ),
+            Inline::Link { text, url } if is_web_url(url) => {
+                format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(text))
+            }
+            Inline::Link { text, .. } => escape_html(text),
+            Inline::PageJump(page) => format!("<a href=\"#page-{}\">{}</a>", page, page),
+        })
+        .collect()
+}
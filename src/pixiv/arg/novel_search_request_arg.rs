@@ -0,0 +1,124 @@
+use crate::enums::{Filter, SearchSort, SearchTarget};
+
+use serde::{Deserialize, Serialize};
+
+/// NovelSearchRequestArg (Request Novel Search Arguments Builder).
+/// Builds the parameters used by `PixivRequestBuilder::request_novel_search`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NovelSearchRequestArg {
+    word: String,
+    search_target: SearchTarget,
+    sort: SearchSort,
+    merge_plain_keyword_results: bool,
+    include_translated_tag_results: bool,
+    // TODO: Figure out how to encapsulate the date properly.
+    start_date: Option<String>,
+    end_date: Option<String>,
+    offset: u32,
+    filter: Filter,
+}
+
+impl NovelSearchRequestArg {
+    pub fn new<T>(word: T) -> Self
+    where
+        T: Into<String>,
+    {
+        NovelSearchRequestArg {
+            word: word.into(),
+            search_target: SearchTarget::TagsPartial,
+            sort: SearchSort::DateDescending,
+            merge_plain_keyword_results: true,
+            include_translated_tag_results: true,
+            start_date: None,
+            end_date: None,
+            offset: 0,
+            filter: Filter::ForiOS,
+        }
+    }
+
+    pub fn set_search_target<T>(mut self, value: T) -> Self
+    where
+        T: Into<SearchTarget>,
+    {
+        self.search_target = value.into();
+        self
+    }
+
+    pub fn set_sort<T>(mut self, value: T) -> Self
+    where
+        T: Into<SearchSort>,
+    {
+        self.sort = value.into();
+        self
+    }
+
+    pub fn set_merge_plain_keyword_results(mut self, value: bool) -> Self {
+        self.merge_plain_keyword_results = value;
+        self
+    }
+
+    pub fn set_include_translated_tag_results(mut self, value: bool) -> Self {
+        self.include_translated_tag_results = value;
+        self
+    }
+
+    /// Date formatted as `YYYY-MM-DD`.
+    pub fn set_start_date<T>(mut self, value: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.start_date = Some(value.into());
+        self
+    }
+
+    /// Date formatted as `YYYY-MM-DD`.
+    pub fn set_end_date<T>(mut self, value: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.end_date = Some(value.into());
+        self
+    }
+
+    pub fn set_offset(mut self, value: u32) -> Self {
+        self.offset = value;
+        self
+    }
+
+    pub fn set_filter<T>(mut self, value: T) -> Self
+    where
+        T: Into<Filter>,
+    {
+        self.filter = value.into();
+        self
+    }
+
+    pub fn build(self) -> std::collections::HashMap<&'static str, String> {
+        let mut result = std::collections::HashMap::new();
+
+        result.insert("word", self.word);
+        result.insert("search_target", self.search_target.as_str().to_string());
+        result.insert("sort", self.sort.as_str().to_string());
+        result.insert(
+            "merge_plain_keyword_results",
+            self.merge_plain_keyword_results.to_string(),
+        );
+        result.insert(
+            "include_translated_tag_results",
+            self.include_translated_tag_results.to_string(),
+        );
+
+        if let Some(start_date) = self.start_date {
+            result.insert("start_date", start_date);
+        }
+
+        if let Some(end_date) = self.end_date {
+            result.insert("end_date", end_date);
+        }
+
+        result.insert("offset", self.offset.to_string());
+        result.insert("filter", self.filter.as_str().to_string());
+
+        result
+    }
+}
@@ -0,0 +1,58 @@
+use crate::enums::ContentType;
+use crate::enums::Filter;
+
+use serde::{Deserialize, Serialize};
+
+/// UserIllustrationsRequestArg (Request User Illustrations Arguments Builder).
+/// Builds the parameters used by `PixivRequestBuilder::request_user_illustrations`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserIllustrationsRequestArg {
+    user_id: u32,
+    content_type: ContentType,
+    offset: u32,
+    filter: Filter,
+}
+
+impl UserIllustrationsRequestArg {
+    pub fn new(user_id: u32) -> Self {
+        UserIllustrationsRequestArg {
+            user_id,
+            content_type: ContentType::Illustration,
+            offset: 0,
+            filter: Filter::ForiOS,
+        }
+    }
+
+    /// Only `ContentType::Illustration` and `ContentType::Manga` are accepted by the API.
+    pub fn set_content_type<T>(mut self, value: T) -> Self
+    where
+        T: Into<ContentType>,
+    {
+        self.content_type = value.into();
+        self
+    }
+
+    pub fn set_offset(mut self, value: u32) -> Self {
+        self.offset = value;
+        self
+    }
+
+    pub fn set_filter<T>(mut self, value: T) -> Self
+    where
+        T: Into<Filter>,
+    {
+        self.filter = value.into();
+        self
+    }
+
+    pub fn build(self) -> std::collections::HashMap<&'static str, String> {
+        let mut result = std::collections::HashMap::new();
+
+        result.insert("user_id", self.user_id.to_string());
+        result.insert("type", self.content_type.as_str().to_string());
+        result.insert("offset", self.offset.to_string());
+        result.insert("filter", self.filter.as_str().to_string());
+
+        result
+    }
+}
@@ -0,0 +1,21 @@
+use crate::enums::Visibility;
+
+pub struct UserNovelBookmarksRequestArg {
+    pub user_id: u32,
+    pub restrict: Visibility,
+    /// Only return bookmarks older than this bookmark id. Used for paging.
+    pub max_bookmark_id: Option<u64>,
+    /// Only return bookmarks registered with this bookmark tag.
+    pub tag: Option<String>,
+}
+
+impl UserNovelBookmarksRequestArg {
+    pub fn new(user_id: u32) -> Self {
+        UserNovelBookmarksRequestArg {
+            user_id,
+            restrict: Visibility::Public,
+            max_bookmark_id: None,
+            tag: None,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::enums::{Duration, Filter, SearchSort};
+
+use serde::{Deserialize, Serialize};
+
+/// UserSearchRequestArg (Request User Search Arguments Builder).
+/// Builds the parameters used by `PixivRequestBuilder::request_user_search`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserSearchRequestArg {
+    word: String,
+    sort: SearchSort,
+    duration: Option<Duration>,
+    offset: u32,
+    filter: Filter,
+}
+
+impl UserSearchRequestArg {
+    pub fn new<T>(word: T) -> Self
+    where
+        T: Into<String>,
+    {
+        UserSearchRequestArg {
+            word: word.into(),
+            sort: SearchSort::DateDescending,
+            duration: None,
+            offset: 0,
+            filter: Filter::ForiOS,
+        }
+    }
+
+    pub fn set_sort<T>(mut self, value: T) -> Self
+    where
+        T: Into<SearchSort>,
+    {
+        self.sort = value.into();
+        self
+    }
+
+    pub fn set_duration<T>(mut self, value: T) -> Self
+    where
+        T: Into<Duration>,
+    {
+        self.duration = Some(value.into());
+        self
+    }
+
+    pub fn set_offset(mut self, value: u32) -> Self {
+        self.offset = value;
+        self
+    }
+
+    pub fn set_filter<T>(mut self, value: T) -> Self
+    where
+        T: Into<Filter>,
+    {
+        self.filter = value.into();
+        self
+    }
+
+    pub fn build(self) -> std::collections::HashMap<&'static str, String> {
+        let mut result = std::collections::HashMap::new();
+
+        result.insert("word", self.word);
+        result.insert("sort", self.sort.as_str().to_string());
+
+        if let Some(duration) = self.duration {
+            result.insert("duration", duration.as_str().to_string());
+        }
+
+        result.insert("offset", self.offset.to_string());
+        result.insert("filter", self.filter.as_str().to_string());
+
+        result
+    }
+}
@@ -1,14 +1,23 @@
 use crate::constants::{
     AUTH_URL, CLIENT_ID, CLIENT_SECRET, HASH_SECRET, USER_AGENT, X_CLIENT_HASH, X_CLIENT_TIME,
 };
-use crate::errors::AuthError;
+use crate::download::downloader::Downloader;
+use crate::download::report::DownloadReport;
+use crate::enums::{ImageSize, UgoiraSize};
+use crate::errors::{ApiError, AuthError, DownloadError};
 use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::helper_structs::ugoira_metadata::UgoiraMetadata;
 use crate::pixiv::request::PixivRequest;
+#[cfg(feature = "storage")]
+use crate::storage::catalog::Catalog;
 
 use http::{header, status::StatusCode};
 use md5;
 use reqwest::blocking::{Client, ClientBuilder, Response};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+#[cfg(feature = "storage")]
+use std::sync::{Arc, Mutex, PoisonError};
 
 /// Used to authenticate to the PixivClient servers and construct PixivClient requests through methods creating `PixivRequestBuilder`.
 #[derive(Debug, Clone)]
@@ -16,6 +25,8 @@ pub struct PixivClient {
     pub client: Client,
     pub access_token: String,
     pub refresh_token: String,
+    #[cfg(feature = "storage")]
+    catalog: Option<Arc<Mutex<Catalog>>>,
 }
 
 impl PixivClient {
@@ -34,6 +45,8 @@ impl PixivClient {
             client: client,
             access_token: String::default(),
             refresh_token: String::default(),
+            #[cfg(feature = "storage")]
+            catalog: None,
         })
     }
     /// This is required to use all the other functions this library provides. Requires a valid username and password.
@@ -149,6 +162,18 @@ impl PixivClient {
         &mut self.refresh_token
     }
 
+    /// Records the illustrations and users of every response parsed by `execute_json` in
+    /// `catalog`. Failing to record a response is logged and does not fail the request.
+    #[cfg(feature = "storage")]
+    pub fn set_catalog(&mut self, catalog: Arc<Mutex<Catalog>>) {
+        self.catalog = Some(catalog);
+    }
+
+    #[cfg(feature = "storage")]
+    pub fn catalog(&self) -> Option<&Arc<Mutex<Catalog>>> {
+        self.catalog.as_ref()
+    }
+
     /// Get current UTC time as a `String`.
     fn get_current_time(&self) -> String {
         chrono::offset::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
@@ -194,12 +219,58 @@ impl PixivClient {
             .send()
     }
 
-    /// Download a given illustration to path
-    pub fn download_illustration<'a, 'b, 'c>(
-        &'a self,
-        illustration: &'c Illustration,
-        path: &'b std::path::Path,
-    ) {
-        illustration.download(&self.client, &path);
+    /// Executes a given `PixivRequest` and parses the response as `T`, recording what it
+    /// contains in the catalog if one is set.
+    pub fn execute_json<T: DeserializeOwned>(&self, request: PixivRequest) -> Result<T, ApiError> {
+        let body = self
+            .execute_with_auth(request)?
+            .error_for_status()?
+            .bytes()?;
+        let value: Value = serde_json::from_slice(&body)?;
+        #[cfg(feature = "storage")]
+        if let Some(catalog) = &self.catalog {
+            let mut catalog = catalog.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Err(e) = catalog.record_response(&value) {
+                log::warn!(
+                    "Failed to record the response in the catalog. Reason: {}",
+                    e
+                );
+            }
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Download the original images of every page of a given illustration to path
+    pub fn download_illustration(
+        &self,
+        illustration: &Illustration,
+        path: &std::path::Path,
+    ) -> DownloadReport {
+        illustration.download(&self.client, path)
+    }
+
+    /// Download every page of a given illustration at the given size to path
+    pub fn download_illustration_pages(
+        &self,
+        illustration: &Illustration,
+        path: &std::path::Path,
+        size: ImageSize,
+    ) -> DownloadReport {
+        illustration.download_pages(&self.client, path, size)
+    }
+
+    /// Download the frame zip of an ugoira to path, returning the path of the written file.
+    pub fn download_ugoira(
+        &self,
+        metadata: &UgoiraMetadata,
+        size: UgoiraSize,
+        path: &std::path::Path,
+    ) -> Result<std::path::PathBuf, DownloadError> {
+        metadata.download(&self.client, size, path)
+    }
+
+    /// Returns a `Downloader` sharing the connection pool of this client.
+    pub fn downloader(&self) -> Downloader {
+        Downloader::new(self.client.clone())
     }
 }
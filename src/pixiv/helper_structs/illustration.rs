@@ -1,5 +1,6 @@
-use crate::enums::ContentType;
-
+use crate::download::downloader::Downloader;
+use crate::download::report::DownloadReport;
+use crate::enums::{ContentType, ImageSize};
 use crate::pixiv::helper_structs::image_url::ImageUrl;
 use crate::pixiv::helper_structs::meta_page::MetaPage;
 use crate::pixiv::helper_structs::series::Series;
@@ -7,6 +8,7 @@ use crate::pixiv::helper_structs::single_page_meta::SingleMetaPage;
 use crate::pixiv::helper_structs::tag::Tag;
 use crate::pixiv::result::illustration_proxy::IllustrationProxy;
 use crate::pixiv::user::User;
+use crate::utils::artwork_url;
 
 use serde::{Deserialize, Serialize};
 
@@ -51,47 +53,69 @@ impl From<IllustrationProxy> for Illustration {
 }
 
 impl Illustration {
-    pub fn download(&self, client: &reqwest::blocking::Client, path: &std::path::Path) {
-        let urls: Vec<reqwest::Url> = self
-            .image_urls
-            .clone()
-            .into_iter()
-            .map(|url| reqwest::Url::parse(&url))
-            .filter_map(Result::ok)
-            .collect();
-
-        for target in urls {
-            let response = client
-                .request(http::Method::GET, target)
-                .header(
-                    reqwest::header::REFERER,
-                    format!(
-                        "https://www.pixiv.net/member_illust.php?mode=medium&illust_id={}",
-                        self.id
-                    ),
-                )
-                .send();
-            let response = response.unwrap();
-            let mut dest = {
-                println!("response_url:{}", response.url());
-                let fname = response
-                    .url()
-                    .path_segments()
-                    .and_then(|segments| segments.last())
-                    .and_then(|name| if name.is_empty() { None } else { Some(name) })
-                    .unwrap_or("tmp.bin");
-                println!("file to download_illustration: '{}'", fname);
-                let fname = path.join(fname);
-                println!("will be located under: '{:?}'", fname);
-                std::fs::File::create(fname).unwrap()
+    /// Returns the url of the page of the illustration on pixiv.net.
+    pub fn artwork_url(&self) -> String {
+        artwork_url(self.id)
+    }
+
+    /// Returns the url of every page at the given size, in page order.
+    /// Pages that have no url at this size are `None`.
+    pub fn page_urls(&self, size: ImageSize) -> Vec<Option<&String>> {
+        if self.meta_pages.is_empty() {
+            let url = match size {
+                ImageSize::Original => self
+                    .meta_single_page
+                    .as_ref()
+                    .and_then(|page| page.original_image_url()),
+                _ => self.image_urls.get(size),
             };
-
-            let bytes = response.bytes().unwrap();
-            let mut content = bytes.as_ref();
-            std::io::copy(&mut content, &mut dest).unwrap();
+            vec![url]
+        } else {
+            self.meta_pages
+                .iter()
+                .map(|page| page.image_urls().get(size))
+                .collect()
         }
     }
 
+    /// Returns the file name of a page, `<id>_p<page>.<ext>`, keeping the extension of `url`.
+    pub fn page_file_name(&self, page: usize, url: &str) -> String {
+        let ext = url
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, ext)| ext)
+            .filter(|ext| !ext.is_empty())
+            .unwrap_or("jpg");
+        format!("{}_p{}.{}", self.id, page, ext)
+    }
+
+    /// Downloads the original image of every page into the directory `path`.
+    pub fn download(
+        &self,
+        client: &reqwest::blocking::Client,
+        path: &std::path::Path,
+    ) -> DownloadReport {
+        self.download_pages(client, path, ImageSize::default())
+    }
+
+    /// Downloads every page at the given size into the directory `path`, naming the files by
+    /// page index (see `page_file_name`). Pages without an url at this size are skipped.
+    pub fn download_pages(
+        &self,
+        client: &reqwest::blocking::Client,
+        path: &std::path::Path,
+        size: ImageSize,
+    ) -> DownloadReport {
+        let targets = self
+            .page_urls(size)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(page, url)| url.map(|url| (page, url)))
+            .map(|(page, url)| (url, path.join(self.page_file_name(page, url))));
+        Downloader::new(client.clone()).download_all(targets)
+    }
+
     pub fn caption(&self) -> &String {
         &self.caption
     }
@@ -1,3 +1,5 @@
+use crate::enums::ImageSize;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -6,6 +8,19 @@ pub struct ImageUrl {
     pub medium: Option<String>,
     pub small: Option<String>,
     pub square_medium: Option<String>,
+    /// Only present for the pages of multi-page works, see `Illustration::page_urls`.
+    pub original: Option<String>,
+}
+
+impl ImageUrl {
+    pub fn get(&self, size: ImageSize) -> Option<&String> {
+        match size {
+            ImageSize::SquareMedium => self.square_medium.as_ref(),
+            ImageSize::Medium => self.medium.as_ref(),
+            ImageSize::Large => self.large.as_ref(),
+            ImageSize::Original => self.original.as_ref(),
+        }
+    }
 }
 
 impl IntoIterator for ImageUrl {
@@ -35,6 +50,7 @@ impl Iterator for ImageUrlIterator {
                 1 => self.url.medium.take(),
                 2 => self.url.large.take(),
                 3 => self.url.square_medium.take(),
+                4 => self.url.original.take(),
                 _ => return None,
             };
             self.index += 1;
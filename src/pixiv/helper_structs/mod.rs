@@ -3,6 +3,14 @@ pub mod illustration;
 pub mod illustration_tag;
 pub mod image_url;
 pub mod meta_page;
+pub mod novel;
+pub mod novel_series_detail;
+pub mod profile_publicity;
 pub mod series;
 pub mod single_page_meta;
 pub mod tag;
+pub mod ugoira_frame;
+pub mod ugoira_metadata;
+pub mod user_preview;
+pub mod user_profile;
+pub mod workspace;
@@ -0,0 +1,149 @@
+use crate::pixiv::helper_structs::image_url::ImageUrl;
+use crate::pixiv::helper_structs::series::Series;
+use crate::pixiv::helper_structs::tag::Tag;
+use crate::pixiv::result::novel_proxy::NovelProxy;
+use crate::pixiv::user::User;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Struct representations of a PixivClient novel.
+/// The text itself is not part of it, use `PixivRequestBuilder::request_novel_text` to fetch it.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Novel {
+    caption: String,
+    create_date: String,
+    id: u32,
+    image_urls: ImageUrl,
+    is_bookmarked: bool,
+    is_muted: bool,
+    is_mypixiv_only: bool,
+    is_original: bool,
+    is_x_restricted: bool,
+    novel_ai_type: Option<u32>,
+    page_count: u32,
+    restrict: u32,
+    // PixivClient sends an empty object instead of null when the novel is not part of a series.
+    #[serde(deserialize_with = "empty_object_as_none")]
+    series: Option<Series>,
+    tags: Vec<Tag>,
+    text_length: u32,
+    title: String,
+    total_bookmarks: u32,
+    total_comments: Option<u32>,
+    total_view: u32,
+    user: User,
+    visible: bool,
+    x_restrict: u32,
+}
+
+fn empty_object_as_none<'de, D>(deserializer: D) -> Result<Option<Series>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::Object(map)) if map.is_empty() => Ok(None),
+        Some(value) => serde_json::from_value(value)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Convert `NovelProxy` to `Novel`
+impl From<NovelProxy> for Novel {
+    fn from(proxy: NovelProxy) -> Self {
+        proxy.novel
+    }
+}
+
+impl Novel {
+    pub fn caption(&self) -> &String {
+        &self.caption
+    }
+
+    pub fn create_date(&self) -> &String {
+        &self.create_date
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Cover image urls.
+    pub fn image_urls(&self) -> &ImageUrl {
+        &self.image_urls
+    }
+
+    pub fn is_bookmarked(&self) -> bool {
+        self.is_bookmarked
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.is_muted
+    }
+
+    pub fn is_mypixiv_only(&self) -> bool {
+        self.is_mypixiv_only
+    }
+
+    pub fn is_original(&self) -> bool {
+        self.is_original
+    }
+
+    pub fn is_x_restricted(&self) -> bool {
+        self.is_x_restricted
+    }
+
+    pub fn novel_ai_type(&self) -> Option<u32> {
+        self.novel_ai_type
+    }
+
+    pub fn page_count(&self) -> u32 {
+        self.page_count
+    }
+
+    pub fn restrict(&self) -> u32 {
+        self.restrict
+    }
+
+    pub fn series(&self) -> Option<&Series> {
+        self.series.as_ref()
+    }
+
+    pub fn tags(&self) -> &Vec<Tag> {
+        &self.tags
+    }
+
+    /// Number of characters of the text.
+    pub fn text_length(&self) -> u32 {
+        self.text_length
+    }
+
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn total_bookmarks(&self) -> u32 {
+        self.total_bookmarks
+    }
+
+    pub fn total_comments(&self) -> Option<u32> {
+        self.total_comments
+    }
+
+    pub fn total_view(&self) -> u32 {
+        self.total_view
+    }
+
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn x_restrict(&self) -> u32 {
+        self.x_restrict
+    }
+}
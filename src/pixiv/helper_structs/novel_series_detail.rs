@@ -0,0 +1,66 @@
+use crate::pixiv::user::User;
+
+use serde::{Deserialize, Serialize};
+
+/// Description of a novel series, without its novels.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct NovelSeriesDetail {
+    id: u32,
+    title: String,
+    caption: String,
+    is_original: bool,
+    is_concluded: bool,
+    content_count: u32,
+    total_character_count: u32,
+    user: User,
+    display_text: String,
+    novel_ai_type: Option<u32>,
+    watchlist_added: bool,
+}
+
+impl NovelSeriesDetail {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn caption(&self) -> &String {
+        &self.caption
+    }
+
+    pub fn is_original(&self) -> bool {
+        self.is_original
+    }
+
+    pub fn is_concluded(&self) -> bool {
+        self.is_concluded
+    }
+
+    /// Number of novels in the series.
+    pub fn content_count(&self) -> u32 {
+        self.content_count
+    }
+
+    pub fn total_character_count(&self) -> u32 {
+        self.total_character_count
+    }
+
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn display_text(&self) -> &String {
+        &self.display_text
+    }
+
+    pub fn novel_ai_type(&self) -> Option<u32> {
+        self.novel_ai_type
+    }
+
+    pub fn watchlist_added(&self) -> bool {
+        self.watchlist_added
+    }
+}
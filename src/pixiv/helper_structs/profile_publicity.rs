@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// Which parts of a user's profile are visible to others.
+/// Each field is either `"public"`, `"mypixiv"` or `"private"`.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ProfilePublicity {
+    gender: String,
+    region: String,
+    birth_day: String,
+    birth_year: String,
+    job: String,
+    pawoo: bool,
+}
+
+impl ProfilePublicity {
+    pub fn gender(&self) -> &String {
+        &self.gender
+    }
+
+    pub fn region(&self) -> &String {
+        &self.region
+    }
+
+    pub fn birth_day(&self) -> &String {
+        &self.birth_day
+    }
+
+    pub fn birth_year(&self) -> &String {
+        &self.birth_year
+    }
+
+    pub fn job(&self) -> &String {
+        &self.job
+    }
+
+    pub fn pawoo(&self) -> bool {
+        self.pawoo
+    }
+}
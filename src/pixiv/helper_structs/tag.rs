@@ -1,9 +1,30 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Tag {
     name: String,
+    // The app api sends a single string, older responses a list of strings.
+    #[serde(default, deserialize_with = "string_or_list")]
     translated_name: Option<Vec<String>>,
+    added_by_uploaded_user: Option<bool>,
+}
+
+fn string_or_list<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        String(String),
+        List(Vec<String>),
+    }
+
+    Ok(match Option::<StringOrList>::deserialize(deserializer)? {
+        Some(StringOrList::String(name)) => Some(vec![name]),
+        Some(StringOrList::List(names)) => Some(names),
+        None => None,
+    })
 }
 
 impl Tag {
@@ -14,4 +35,9 @@ impl Tag {
     pub fn translated_name(&self) -> &Option<Vec<String>> {
         &self.translated_name
     }
+
+    /// Whether the author registered the tag. Only present on novels.
+    pub fn added_by_uploaded_user(&self) -> Option<bool> {
+        self.added_by_uploaded_user
+    }
 }
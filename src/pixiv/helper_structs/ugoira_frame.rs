@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// A frame of an ugoira: the name of the image inside the zip and how long it is shown.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UgoiraFrame {
+    file: String,
+    delay: u32,
+}
+
+impl UgoiraFrame {
+    pub fn file(&self) -> &String {
+        &self.file
+    }
+
+    /// Delay in milliseconds before the next frame.
+    pub fn delay(&self) -> u32 {
+        self.delay
+    }
+}
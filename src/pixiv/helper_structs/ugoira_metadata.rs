@@ -0,0 +1,82 @@
+use crate::download::downloader::Downloader;
+use crate::enums::UgoiraSize;
+use crate::errors::DownloadError;
+use crate::pixiv::helper_structs::ugoira_frame::UgoiraFrame;
+
+use serde::{Deserialize, Serialize};
+
+/// Urls of the zips holding the frames of an ugoira.
+/// PixivClient only returns the medium one, the others are derived from it.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UgoiraZipUrls {
+    medium: String,
+}
+
+impl UgoiraZipUrls {
+    pub fn medium(&self) -> &String {
+        &self.medium
+    }
+
+    /// Url of the zip with the frames at their original resolution.
+    pub fn original(&self) -> String {
+        self.medium.replace("600x600", "1920x1080")
+    }
+
+    pub fn get(&self, size: UgoiraSize) -> String {
+        match size {
+            UgoiraSize::Medium => self.medium.clone(),
+            UgoiraSize::Original => self.original(),
+        }
+    }
+}
+
+/// Struct representations of the frames of an ugoira.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UgoiraMetadata {
+    zip_urls: UgoiraZipUrls,
+    frames: Vec<UgoiraFrame>,
+}
+
+impl UgoiraMetadata {
+    pub fn zip_urls(&self) -> &UgoiraZipUrls {
+        &self.zip_urls
+    }
+
+    pub fn frames(&self) -> &Vec<UgoiraFrame> {
+        &self.frames
+    }
+
+    /// Total duration of the animation in milliseconds.
+    pub fn duration(&self) -> u32 {
+        self.frames.iter().map(|frame| frame.delay()).sum()
+    }
+
+    /// Fetches the zip holding the frames.
+    pub fn fetch_zip(
+        &self,
+        client: &reqwest::blocking::Client,
+        size: UgoiraSize,
+    ) -> Result<Vec<u8>, reqwest::Error> {
+        Downloader::new(client.clone()).fetch(&self.zip_urls.get(size))
+    }
+
+    /// Downloads the zip holding the frames into the directory `path`, keeping the file name
+    /// used by pixiv. Returns the path of the written file.
+    pub fn download(
+        &self,
+        client: &reqwest::blocking::Client,
+        size: UgoiraSize,
+        path: &std::path::Path,
+    ) -> Result<std::path::PathBuf, DownloadError> {
+        let url = self.zip_urls.get(size);
+        let fname = url
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("ugoira.zip");
+        let fname = path.join(fname);
+
+        Downloader::new(client.clone()).download(&url, &fname)?;
+        Ok(fname)
+    }
+}
@@ -0,0 +1,32 @@
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::helper_structs::novel::Novel;
+use crate::pixiv::user::User;
+
+use serde::{Deserialize, Serialize};
+
+/// A user together with a few of their latest works, as shown in user listings.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserPreview {
+    user: User,
+    illusts: Vec<Illustration>,
+    novels: Vec<Novel>,
+    is_muted: bool,
+}
+
+impl UserPreview {
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn illusts(&self) -> &Vec<Illustration> {
+        &self.illusts
+    }
+
+    pub fn novels(&self) -> &Vec<Novel> {
+        &self.novels
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.is_muted
+    }
+}
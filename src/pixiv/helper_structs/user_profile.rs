@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+/// Profile section of a user detail, i.e. everything shown on the user's profile page.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct UserProfile {
+    webpage: Option<String>,
+    gender: String,
+    birth: String,
+    birth_day: String,
+    birth_year: u32,
+    region: String,
+    address_id: u32,
+    country_code: String,
+    job: String,
+    job_id: u32,
+    total_follow_users: u32,
+    total_mypixiv_users: u32,
+    total_illusts: u32,
+    total_manga: u32,
+    total_novels: u32,
+    total_illust_bookmarks_public: u32,
+    total_illust_series: u32,
+    total_novel_series: u32,
+    background_image_url: Option<String>,
+    twitter_account: Option<String>,
+    twitter_url: Option<String>,
+    pawoo_url: Option<String>,
+    is_premium: bool,
+    is_using_custom_profile_image: bool,
+}
+
+impl UserProfile {
+    pub fn webpage(&self) -> Option<&String> {
+        self.webpage.as_ref()
+    }
+
+    pub fn gender(&self) -> &String {
+        &self.gender
+    }
+
+    pub fn birth(&self) -> &String {
+        &self.birth
+    }
+
+    pub fn birth_day(&self) -> &String {
+        &self.birth_day
+    }
+
+    pub fn birth_year(&self) -> u32 {
+        self.birth_year
+    }
+
+    pub fn region(&self) -> &String {
+        &self.region
+    }
+
+    pub fn address_id(&self) -> u32 {
+        self.address_id
+    }
+
+    pub fn country_code(&self) -> &String {
+        &self.country_code
+    }
+
+    pub fn job(&self) -> &String {
+        &self.job
+    }
+
+    pub fn job_id(&self) -> u32 {
+        self.job_id
+    }
+
+    pub fn total_follow_users(&self) -> u32 {
+        self.total_follow_users
+    }
+
+    pub fn total_mypixiv_users(&self) -> u32 {
+        self.total_mypixiv_users
+    }
+
+    pub fn total_illusts(&self) -> u32 {
+        self.total_illusts
+    }
+
+    pub fn total_manga(&self) -> u32 {
+        self.total_manga
+    }
+
+    pub fn total_novels(&self) -> u32 {
+        self.total_novels
+    }
+
+    pub fn total_illust_bookmarks_public(&self) -> u32 {
+        self.total_illust_bookmarks_public
+    }
+
+    pub fn total_illust_series(&self) -> u32 {
+        self.total_illust_series
+    }
+
+    pub fn total_novel_series(&self) -> u32 {
+        self.total_novel_series
+    }
+
+    pub fn background_image_url(&self) -> Option<&String> {
+        self.background_image_url.as_ref()
+    }
+
+    pub fn twitter_account(&self) -> Option<&String> {
+        self.twitter_account.as_ref()
+    }
+
+    pub fn twitter_url(&self) -> Option<&String> {
+        self.twitter_url.as_ref()
+    }
+
+    pub fn pawoo_url(&self) -> Option<&String> {
+        self.pawoo_url.as_ref()
+    }
+
+    pub fn is_premium(&self) -> bool {
+        self.is_premium
+    }
+
+    pub fn is_using_custom_profile_image(&self) -> bool {
+        self.is_using_custom_profile_image
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// The user's working environment, as filled in on their profile.
+/// Unset entries are returned as empty strings.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Workspace {
+    pc: String,
+    monitor: String,
+    tool: String,
+    scanner: String,
+    tablet: String,
+    mouse: String,
+    printer: String,
+    desktop: String,
+    music: String,
+    desk: String,
+    chair: String,
+    comment: String,
+    workspace_image_url: Option<String>,
+}
+
+impl Workspace {
+    pub fn pc(&self) -> &String {
+        &self.pc
+    }
+
+    pub fn monitor(&self) -> &String {
+        &self.monitor
+    }
+
+    pub fn tool(&self) -> &String {
+        &self.tool
+    }
+
+    pub fn scanner(&self) -> &String {
+        &self.scanner
+    }
+
+    pub fn tablet(&self) -> &String {
+        &self.tablet
+    }
+
+    pub fn mouse(&self) -> &String {
+        &self.mouse
+    }
+
+    pub fn printer(&self) -> &String {
+        &self.printer
+    }
+
+    pub fn desktop(&self) -> &String {
+        &self.desktop
+    }
+
+    pub fn music(&self) -> &String {
+        &self.music
+    }
+
+    pub fn desk(&self) -> &String {
+        &self.desk
+    }
+
+    pub fn chair(&self) -> &String {
+        &self.chair
+    }
+
+    pub fn comment(&self) -> &String {
+        &self.comment
+    }
+
+    pub fn workspace_image_url(&self) -> Option<&String> {
+        self.workspace_image_url.as_ref()
+    }
+}
@@ -1,10 +1,16 @@
-use crate::constants::{BASE_URL, ILLUST_ID, OFFSET, RESTRICT, TAGS, USER_ID};
+use crate::constants::{BASE_URL, ILLUST_ID, NOVEL_ID, OFFSET, RESTRICT, TAGS, USER_ID};
 use crate::enums::{Filter, RankingType, Visibility};
 use crate::pixiv::arg::illustration_ranking_request_arg::IllustrationRankingRequestArg;
 use crate::pixiv::arg::illustration_search_request_arg::IllustrationSearchRequestArg;
+use crate::pixiv::arg::novel_ranking_request_arg::NovelRankingRequestArg;
+use crate::pixiv::arg::novel_search_request_arg::NovelSearchRequestArg;
 use crate::pixiv::arg::recommended_illustration_request_arg::RecommendedIllustrationRequestArg;
 use crate::pixiv::arg::user_bookmark_tags_illustration_request_arg::UserBookmarkTagsIllustrationRequestArg;
+use crate::pixiv::arg::user_bookmarks_illustration_request_arg::UserBookmarksIllustrationRequestArg;
 use crate::pixiv::arg::user_following_request_arg::UserFollowingRequestArgs;
+use crate::pixiv::arg::user_illustrations_request_arg::UserIllustrationsRequestArg;
+use crate::pixiv::arg::user_novel_bookmarks_request_arg::UserNovelBookmarksRequestArg;
+use crate::pixiv::arg::user_search_request_arg::UserSearchRequestArg;
 use crate::pixiv::request::PixivRequest;
 use crate::utils::comma_delimited;
 
@@ -26,6 +32,17 @@ impl PixivRequestBuilder {
         PixivRequest::new(Method::GET, api_uri)
     }
 
+    /// Used to build a request fetching the page behind a `next_url` returned by a previous response.
+    /// The url already carries every parameter of the original request.
+    /// Returns `None` if `next_url` is not a valid url.
+    pub fn request_next_page<T>(next_url: T) -> Option<PixivRequest>
+    where
+        T: AsRef<str>,
+    {
+        let uri = Uri::try_from(next_url.as_ref()).ok()?;
+        Some(PixivRequest::new(Method::GET, uri))
+    }
+
     /////////////////////////////////////////////////////////////////////
     /////
     /////                        VERSION 1 API
@@ -150,6 +167,7 @@ impl PixivRequestBuilder {
             .finish()
     }
 
+    /// Legacy public-api follow. Prefer `request_adding_follow`.
     pub fn following_add(user_id: usize) -> PixivRequest {
         const API_URL: &'static str =
             "https://public-api.secure.pixiv.net/v1/me/favorite-users.json";
@@ -160,6 +178,7 @@ impl PixivRequestBuilder {
             .finish()
     }
 
+    /// Legacy public-api unfollow. Prefer `request_delete_follow`.
     pub fn following_remove<B, I>(user_ids: I) -> PixivRequest
     where
         B: Borrow<usize>,
@@ -174,6 +193,8 @@ impl PixivRequestBuilder {
             .finish()
     }
 
+    /// Legacy public-api listing of a user's works.
+    /// Prefer `request_user_illustrations`, which supports content type filtering and paging.
     pub fn user_works(user_id: usize) -> PixivRequest {
         let url = format!(
             "https://public-api.secure.pixiv.net/v1/users/{}/works.json",
@@ -336,6 +357,73 @@ impl PixivRequestBuilder {
             .finish()
     }
 
+    /// Used to build a request to fetch the profile of a user given their id.
+    pub fn request_user_detail(user_id: usize) -> PixivRequest {
+        let uri = format!("{}/v1/user/detail", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(USER_ID, user_id.to_string())
+            .add_param_from_str("filter", Filter::ForiOS.as_str())
+            .finish()
+    }
+
+    /// Used to build a request to list the users pixiv recommends to follow.
+    /// The response can be parsed as `UserPreviews`.
+    pub fn request_recommended_users(offset: usize) -> PixivRequest {
+        let uri = format!("{}/v1/user/recommended", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(OFFSET, offset.to_string())
+            .add_param_from_str("filter", Filter::ForiOS.as_str())
+            .finish()
+    }
+
+    /// Used to build a request to search users by name.
+    /// The response can be parsed as `UserPreviews`.
+    pub fn request_user_search<T>(argument: T) -> PixivRequest
+    where
+        T: Into<UserSearchRequestArg>,
+    {
+        let argument = argument.into();
+        let uri = format!("{}/v1/search/user", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        argument
+            .build()
+            .iter()
+            .fold(PixivRequest::new(Method::GET, uri), |acc, (key, val)| {
+                acc.add_param(key, String::from(val))
+            })
+            .finish()
+    }
+
+    /// Used to build a request to list the works of a user, filtered by content type.
+    /// Use `UserIllustrationsRequestArg::set_offset` to fetch the following pages.
+    pub fn request_user_illustrations<T>(argument: T) -> PixivRequest
+    where
+        T: Into<UserIllustrationsRequestArg>,
+    {
+        let argument = argument.into();
+        let uri = format!("{}/v1/user/illusts", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        argument
+            .build()
+            .iter()
+            .fold(PixivRequest::new(Method::GET, uri), |acc, (key, val)| {
+                acc.add_param(key, String::from(val))
+            })
+            .finish()
+    }
+
+    /// Used to build a request to fetch the frames of an ugoira given its id.
+    /// The response can be parsed as `UgoiraMetadataProxy`.
+    pub fn request_ugoira_metadata(illust_id: usize) -> PixivRequest {
+        let uri = format!("{}/v1/ugoira/metadata", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(ILLUST_ID, illust_id.to_string())
+            .finish()
+    }
+
     /// TODO: Documentation
     pub fn request_trending_tags() -> PixivRequest {
         let uri = format!("{}/v1/trending-tags/illust", BASE_URL);
@@ -382,15 +470,22 @@ impl PixivRequestBuilder {
             .finish()
     }
 
-    /// TODO: Documentation
-    pub fn request_adding_bookmark(illust_id: usize, visibility: Visibility) -> PixivRequest {
+    /// Used to build a request to bookmark an illustration with the given bookmark tags.
+    pub fn request_adding_bookmark<B, I>(
+        illust_id: usize,
+        visibility: Visibility,
+        tags: I,
+    ) -> PixivRequest
+    where
+        B: AsRef<str>,
+        I: IntoIterator<Item = B>,
+    {
         let uri = format!("{}/v2/illust/bookmark/add", BASE_URL);
         let uri = Uri::try_from(uri.as_str()).unwrap();
-        PixivRequest::new(Method::POST, uri)
-            .add_form(ILLUST_ID, illust_id.to_string().as_str())
-            .add_form_from_str(RESTRICT, visibility.as_str())
-            .add_form_from_str(TAGS, "Fate/GO")
-            .finish()
+        let request = PixivRequest::new(Method::POST, uri)
+            .add_form(ILLUST_ID, illust_id.to_string())
+            .add_form_from_str(RESTRICT, visibility.as_str());
+        add_bookmark_tags(request, tags).finish()
     }
 
     /// TODO: Documentation
@@ -416,8 +511,26 @@ impl PixivRequestBuilder {
             .finish()
     }
 
-    /// TODO: Documentation
-    /// TODO: Test
+    /// Used to build a request to list the illustrations bookmarked by a user, newest first.
+    /// The response can be parsed as `UserIllustrations`.
+    pub fn request_user_bookmarks_illustration(
+        args: UserBookmarksIllustrationRequestArg,
+    ) -> PixivRequest {
+        let uri = format!("{}/v1/user/bookmarks/illust", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(USER_ID, args.user_id.to_string())
+            .add_param(RESTRICT, args.restrict.as_str())
+            .maybe_add_param(
+                "max_bookmark_id",
+                args.max_bookmark_id.map(|x| x.to_string()),
+            )
+            .maybe_add_param("tag", args.tag)
+            .finish()
+    }
+
+    /// Used to build a request to list the users followed by a user.
+    /// The response can be parsed as `UserPreviews`.
     pub fn request_user_following(args: UserFollowingRequestArgs) -> PixivRequest {
         let uri = format!("{}/v1/user/following", BASE_URL);
         let uri = Uri::try_from(uri.as_str()).unwrap();
@@ -427,4 +540,188 @@ impl PixivRequestBuilder {
             .add_param(OFFSET, args.offset.to_string())
             .finish()
     }
+
+    /// Used to build a request to list the followers of a user.
+    /// The response can be parsed as `UserPreviews`.
+    pub fn request_user_follower(user_id: usize, offset: usize) -> PixivRequest {
+        let uri = format!("{}/v1/user/follower", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(USER_ID, user_id.to_string())
+            .add_param(OFFSET, offset.to_string())
+            .add_param_from_str("filter", Filter::ForiOS.as_str())
+            .finish()
+    }
+
+    /// Used to build a request to list the mypixiv friends of a user.
+    /// The response can be parsed as `UserPreviews`.
+    pub fn request_user_mypixiv(user_id: usize, offset: usize) -> PixivRequest {
+        let uri = format!("{}/v1/user/mypixiv", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(USER_ID, user_id.to_string())
+            .add_param(OFFSET, offset.to_string())
+            .finish()
+    }
+
+    /// Used to build a request to follow a user, either publicly or privately.
+    pub fn request_adding_follow(user_id: usize, visibility: Visibility) -> PixivRequest {
+        let uri = format!("{}/v1/user/follow/add", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::POST, uri)
+            .add_form(USER_ID, user_id.to_string())
+            .add_form_from_str(RESTRICT, visibility.as_str())
+            .finish()
+    }
+
+    /// Used to build a request to unfollow a user.
+    pub fn request_delete_follow(user_id: usize) -> PixivRequest {
+        let uri = format!("{}/v1/user/follow/delete", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::POST, uri)
+            .add_form(USER_ID, user_id.to_string())
+            .finish()
+    }
+
+    /// Used to build a request to fetch a novel given its id.
+    /// The response can be parsed as `NovelProxy`.
+    pub fn request_novel(novel_id: usize) -> PixivRequest {
+        let uri = format!("{}/v2/novel/detail", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(NOVEL_ID, novel_id.to_string())
+            .finish()
+    }
+
+    /// Used to build a request to fetch the text of a novel given its id.
+    /// The response is an html page, use `NovelText::from_webview` on its body.
+    pub fn request_novel_text(novel_id: usize) -> PixivRequest {
+        let uri = format!("{}/webview/v2/novel", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param("id", novel_id.to_string())
+            .add_param_from_str("viewer_version", "20221031_ai")
+            .finish()
+    }
+
+    /// Used to build a request to fetch a novel series and its novels.
+    /// `last_order` is the position of the last novel already fetched, used for paging.
+    pub fn request_novel_series(series_id: usize, last_order: Option<u32>) -> PixivRequest {
+        let uri = format!("{}/v2/novel/series", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param("series_id", series_id.to_string())
+            .maybe_add_param("last_order", last_order.map(|x| x.to_string()))
+            .finish()
+    }
+
+    /// Used to build a request to search novels.
+    /// The response can be parsed as `NovelSearchProxy`.
+    pub fn request_novel_search<T>(argument: T) -> PixivRequest
+    where
+        T: Into<NovelSearchRequestArg>,
+    {
+        let argument = argument.into();
+        let uri = format!("{}/v1/search/novel", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        argument
+            .build()
+            .iter()
+            .fold(PixivRequest::new(Method::GET, uri), |acc, (key, val)| {
+                acc.add_param(key, String::from(val))
+            })
+            .finish()
+    }
+
+    /// Used to build a request to list the novels of a user.
+    /// The response can be parsed as `UserNovels`.
+    pub fn request_user_novels(user_id: usize, offset: usize) -> PixivRequest {
+        let uri = format!("{}/v1/user/novels", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(USER_ID, user_id.to_string())
+            .add_param(OFFSET, offset.to_string())
+            .add_param_from_str("filter", Filter::ForiOS.as_str())
+            .finish()
+    }
+
+    /// Used to build a request to list the novels bookmarked by a user.
+    /// The response can be parsed as `NovelList`.
+    pub fn request_user_novel_bookmarks(args: UserNovelBookmarksRequestArg) -> PixivRequest {
+        let uri = format!("{}/v1/user/bookmarks/novel", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::GET, uri)
+            .add_param(USER_ID, args.user_id.to_string())
+            .add_param(RESTRICT, args.restrict.as_str())
+            .maybe_add_param(
+                "max_bookmark_id",
+                args.max_bookmark_id.map(|x| x.to_string()),
+            )
+            .maybe_add_param("tag", args.tag)
+            .finish()
+    }
+
+    /// Used to build a request to bookmark a novel with the given bookmark tags.
+    pub fn request_adding_novel_bookmark<B, I>(
+        novel_id: usize,
+        visibility: Visibility,
+        tags: I,
+    ) -> PixivRequest
+    where
+        B: AsRef<str>,
+        I: IntoIterator<Item = B>,
+    {
+        let uri = format!("{}/v2/novel/bookmark/add", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        let request = PixivRequest::new(Method::POST, uri)
+            .add_form(NOVEL_ID, novel_id.to_string())
+            .add_form_from_str(RESTRICT, visibility.as_str());
+        add_bookmark_tags(request, tags).finish()
+    }
+
+    /// Used to build a request to remove a novel from the bookmarks.
+    pub fn request_delete_novel_bookmark(novel_id: usize) -> PixivRequest {
+        let uri = format!("{}/v1/novel/bookmark/delete", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        PixivRequest::new(Method::POST, uri)
+            .add_form(NOVEL_ID, novel_id.to_string())
+            .finish()
+    }
+
+    /// Used to build a request to fetch the novel ranking.
+    /// The response can be parsed as `NovelList`.
+    pub fn request_novel_ranking<T>(argument: T) -> PixivRequest
+    where
+        T: Into<NovelRankingRequestArg>,
+    {
+        let argument = argument.into();
+        let uri = format!("{}/v1/novel/ranking", BASE_URL);
+        let uri = Uri::try_from(uri.as_str()).unwrap();
+        argument
+            .build()
+            .iter()
+            .fold(PixivRequest::new(Method::GET, uri), |acc, (key, val)| {
+                acc.add_param(key, String::from(val))
+            })
+            .finish()
+    }
+}
+
+/// Adds the bookmark tags of an illustration or novel bookmark, if there are any.
+/// Several tags are sent space separated under a single key.
+fn add_bookmark_tags<B, I>(request: PixivRequest, tags: I) -> PixivRequest
+where
+    B: AsRef<str>,
+    I: IntoIterator<Item = B>,
+{
+    let tags = tags
+        .into_iter()
+        .map(|tag| tag.as_ref().to_string())
+        .collect::<Vec<String>>()
+        .join(" ");
+    if tags.is_empty() {
+        request
+    } else {
+        request.add_form(TAGS, tags)
+    }
 }
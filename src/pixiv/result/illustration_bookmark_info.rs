@@ -1,3 +1,5 @@
+use crate::enums::Visibility;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -6,9 +8,47 @@ pub struct IllustBookmarkInfoTag {
     name: String,
 }
 
+impl IllustBookmarkInfoTag {
+    /// Whether the bookmark carries this tag, rather than it only being suggested.
+    pub fn is_registered(&self) -> bool {
+        self.is_registered
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IllustBookmarkInfo {
     is_bookmarked: bool,
     restrict: String,
     tags: Vec<IllustBookmarkInfoTag>,
 }
+
+impl IllustBookmarkInfo {
+    pub fn is_bookmarked(&self) -> bool {
+        self.is_bookmarked
+    }
+
+    pub fn restrict(&self) -> &String {
+        &self.restrict
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        (self.restrict != "private").into()
+    }
+
+    pub fn tags(&self) -> &Vec<IllustBookmarkInfoTag> {
+        &self.tags
+    }
+
+    /// Names of the tags the bookmark was registered with.
+    pub fn registered_tags(&self) -> Vec<&String> {
+        self.tags
+            .iter()
+            .filter(|tag| tag.is_registered)
+            .map(|tag| &tag.name)
+            .collect()
+    }
+}
@@ -6,3 +6,9 @@ use serde::{Deserialize, Serialize};
 pub struct IllustBookmarkInfoProxy {
     bookmark_detail: IllustBookmarkInfo,
 }
+
+impl IllustBookmarkInfoProxy {
+    pub fn into_inner(self) -> IllustBookmarkInfo {
+        self.bookmark_detail
+    }
+}
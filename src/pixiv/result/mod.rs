@@ -4,6 +4,17 @@ pub mod illustration_comment;
 pub mod illustration_proxy;
 pub mod illustration_ranking;
 pub mod illustration_search_proxy;
+pub mod novel_list;
+pub mod novel_proxy;
+pub mod novel_search_proxy;
+pub mod novel_series;
+pub mod novel_text;
+pub mod paginated;
 pub mod recommended_illustration;
 pub mod related_illustration_search_proxy;
 pub mod trending_illustrations;
+pub mod ugoira_metadata_proxy;
+pub mod user_detail;
+pub mod user_illustrations;
+pub mod user_novels;
+pub mod user_previews;
@@ -0,0 +1,39 @@
+use crate::pixiv::helper_structs::novel::Novel;
+use crate::pixiv::result::paginated::Paginated;
+
+use serde::{Deserialize, Serialize};
+
+/// NovelList
+/// Returned by the novel ranking and novel bookmark requests.
+/// `next_url` is `None` once the last page has been reached.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NovelList {
+    novels: Vec<Novel>,
+    next_url: Option<String>,
+}
+
+impl NovelList {
+    pub fn novels(&self) -> &Vec<Novel> {
+        &self.novels
+    }
+
+    pub fn into_inner(self) -> Vec<Novel> {
+        self.novels
+    }
+}
+
+impl Paginated for NovelList {
+    fn next_url(&self) -> Option<&String> {
+        self.next_url.as_ref()
+    }
+}
+
+impl IntoIterator for NovelList {
+    type Item = Novel;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    /// Consume the struct, yielding an iterator.
+    fn into_iter(self) -> Self::IntoIter {
+        self.novels.into_iter()
+    }
+}
@@ -0,0 +1,16 @@
+use crate::pixiv::helper_structs::novel::Novel;
+
+use serde::{Deserialize, Serialize};
+
+/// PixivClient hides the actual novel object behind the value "novel".
+/// This struct exists purely to bypass this indirection...
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NovelProxy {
+    pub novel: Novel,
+}
+
+impl NovelProxy {
+    pub fn into_inner(self) -> Novel {
+        self.novel
+    }
+}
@@ -0,0 +1,29 @@
+use crate::pixiv::helper_structs::novel::Novel;
+use crate::pixiv::result::paginated::Paginated;
+
+use serde::{Deserialize, Serialize};
+
+/// NovelSearchProxy
+/// Returned by `PixivRequestBuilder::request_novel_search`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NovelSearchProxy {
+    novels: Vec<Novel>,
+    next_url: Option<String>,
+    search_span_limit: u32,
+}
+
+impl NovelSearchProxy {
+    pub fn search_span_limit(&self) -> u32 {
+        self.search_span_limit
+    }
+
+    pub fn into_inner(self) -> Vec<Novel> {
+        self.novels
+    }
+}
+
+impl Paginated for NovelSearchProxy {
+    fn next_url(&self) -> Option<&String> {
+        self.next_url.as_ref()
+    }
+}
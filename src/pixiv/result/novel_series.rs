@@ -0,0 +1,45 @@
+use crate::pixiv::helper_structs::novel::Novel;
+use crate::pixiv::helper_structs::novel_series_detail::NovelSeriesDetail;
+use crate::pixiv::result::paginated::Paginated;
+
+use serde::{Deserialize, Serialize};
+
+/// NovelSeries
+/// Returned by `PixivRequestBuilder::request_novel_series`.
+/// `novels` holds one page of the series, in reading order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NovelSeries {
+    novel_series_detail: NovelSeriesDetail,
+    novel_series_first_novel: Option<Novel>,
+    novel_series_latest_novel: Option<Novel>,
+    novels: Vec<Novel>,
+    next_url: Option<String>,
+}
+
+impl NovelSeries {
+    pub fn detail(&self) -> &NovelSeriesDetail {
+        &self.novel_series_detail
+    }
+
+    pub fn first_novel(&self) -> Option<&Novel> {
+        self.novel_series_first_novel.as_ref()
+    }
+
+    pub fn latest_novel(&self) -> Option<&Novel> {
+        self.novel_series_latest_novel.as_ref()
+    }
+
+    pub fn novels(&self) -> &Vec<Novel> {
+        &self.novels
+    }
+
+    pub fn into_inner(self) -> Vec<Novel> {
+        self.novels
+    }
+}
+
+impl Paginated for NovelSeries {
+    fn next_url(&self) -> Option<&String> {
+        self.next_url.as_ref()
+    }
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+
+/// A neighbouring novel in a series.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NovelNavigationEntry {
+    id: u32,
+    title: String,
+}
+
+impl NovelNavigationEntry {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NovelNavigation {
+    next_novel: Option<NovelNavigationEntry>,
+    prev_novel: Option<NovelNavigationEntry>,
+}
+
+impl NovelNavigation {
+    pub fn next_novel(&self) -> Option<&NovelNavigationEntry> {
+        self.next_novel.as_ref()
+    }
+
+    pub fn prev_novel(&self) -> Option<&NovelNavigationEntry> {
+        self.prev_novel.as_ref()
+    }
+}
+
+/// Urls of an image embedded in a novel, from the smallest to the largest.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NovelImageUrls {
+    #[serde(rename = "128x128")]
+    pub px_128x128: Option<String>,
+    #[serde(rename = "240mw")]
+    pub px_240mw: Option<String>,
+    #[serde(rename = "480mw")]
+    pub px_480mw: Option<String>,
+    #[serde(rename = "1200x1200")]
+    pub px_1200x1200: Option<String>,
+    pub small: Option<String>,
+    pub medium: Option<String>,
+    pub original: Option<String>,
+}
+
+impl NovelImageUrls {
+    /// The url of the largest size available.
+    pub fn largest(&self) -> Option<&String> {
+        self.original
+            .as_ref()
+            .or(self.px_1200x1200.as_ref())
+            .or(self.medium.as_ref())
+            .or(self.px_480mw.as_ref())
+            .or(self.px_240mw.as_ref())
+            .or(self.small.as_ref())
+            .or(self.px_128x128.as_ref())
+    }
+}
+
+/// An image uploaded with the novel, referenced by `[uploadedimage:ID]`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NovelUploadedImage {
+    novel_image_id: String,
+    #[serde(default)]
+    urls: NovelImageUrls,
+}
+
+impl NovelUploadedImage {
+    pub fn novel_image_id(&self) -> &String {
+        &self.novel_image_id
+    }
+
+    pub fn urls(&self) -> &NovelImageUrls {
+        &self.urls
+    }
+}
+
+/// The illustration behind a `[pixivimage:ID-PAGE]` tag.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NovelEmbeddedIllustDetail {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    images: NovelImageUrls,
+}
+
+impl NovelEmbeddedIllustDetail {
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn images(&self) -> &NovelImageUrls {
+        &self.images
+    }
+}
+
+/// An illustration referenced by `[pixivimage:ID-PAGE]`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NovelEmbeddedIllust {
+    #[serde(default)]
+    visible: bool,
+    /// `None` once the illustration was deleted or made private.
+    illust: Option<NovelEmbeddedIllustDetail>,
+}
+
+impl NovelEmbeddedIllust {
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn illust(&self) -> Option<&NovelEmbeddedIllustDetail> {
+        self.illust.as_ref()
+    }
+}
+
+/// The webview sends empty maps as `[]`.
+fn map_or_empty_array<'de, D, V>(deserializer: D) -> Result<HashMap<String, V>, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MapOrArray<V> {
+        Map(HashMap<String, V>),
+        Array(Vec<serde::de::IgnoredAny>),
+    }
+
+    match MapOrArray::deserialize(deserializer)? {
+        MapOrArray::Map(map) => Ok(map),
+        MapOrArray::Array(items) if items.is_empty() => Ok(HashMap::new()),
+        MapOrArray::Array(_) => Err(serde::de::Error::custom("expected a map")),
+    }
+}
+
+/// NovelText
+/// The text of a novel, embedded in the page returned by `PixivRequestBuilder::request_novel_text`.
+/// Use `NovelText::from_webview` to extract it.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NovelText {
+    id: String,
+    title: String,
+    series_id: Option<String>,
+    series_title: Option<String>,
+    user_id: String,
+    cover_url: String,
+    tags: Vec<String>,
+    caption: String,
+    cdate: String,
+    text: String,
+    #[serde(default, deserialize_with = "map_or_empty_array")]
+    illusts: HashMap<String, NovelEmbeddedIllust>,
+    #[serde(default, deserialize_with = "map_or_empty_array")]
+    images: HashMap<String, NovelUploadedImage>,
+    series_navigation: Option<NovelNavigation>,
+}
+
+impl NovelText {
+    /// Extracts the novel from the html page served by the novel webview.
+    pub fn from_webview(html: &str) -> Result<NovelText, serde_json::Error> {
+        const MARKER: &str = "novel: {";
+
+        let start = match html.find(MARKER) {
+            Some(index) => index + MARKER.len() - 1,
+            None => {
+                return Err(serde::de::Error::custom(
+                    "novel payload not found in webview",
+                ))
+            }
+        };
+
+        // The payload is followed by more javascript, so only the first value is parsed.
+        match serde_json::Deserializer::from_str(&html[start..])
+            .into_iter::<NovelText>()
+            .next()
+        {
+            Some(result) => result,
+            None => Err(serde::de::Error::custom("novel payload is empty")),
+        }
+    }
+
+    pub fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn series_id(&self) -> Option<&String> {
+        self.series_id.as_ref()
+    }
+
+    pub fn series_title(&self) -> Option<&String> {
+        self.series_title.as_ref()
+    }
+
+    pub fn user_id(&self) -> &String {
+        &self.user_id
+    }
+
+    pub fn cover_url(&self) -> &String {
+        &self.cover_url
+    }
+
+    pub fn tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    pub fn caption(&self) -> &String {
+        &self.caption
+    }
+
+    pub fn cdate(&self) -> &String {
+        &self.cdate
+    }
+
+    /// The raw text, including pixiv's novel markup.
+    pub fn text(&self) -> &String {
+        &self.text
+    }
+
+    /// Illustrations referenced by `[pixivimage:...]` tags, keyed by the tag's `ID` or `ID-PAGE`.
+    pub fn illusts(&self) -> &HashMap<String, NovelEmbeddedIllust> {
+        &self.illusts
+    }
+
+    /// The illustration referenced by `[pixivimage:illust_id]` or `[pixivimage:illust_id-page]`.
+    pub fn illust(&self, illust_id: u32, page: Option<u32>) -> Option<&NovelEmbeddedIllust> {
+        match page {
+            Some(page) => self.illusts.get(&format!("{}-{}", illust_id, page)),
+            None => self.illusts.get(&illust_id.to_string()),
+        }
+    }
+
+    /// Images referenced by `[uploadedimage:...]` tags, keyed by image id.
+    pub fn images(&self) -> &HashMap<String, NovelUploadedImage> {
+        &self.images
+    }
+
+    /// The image referenced by `[uploadedimage:image_id]`.
+    pub fn image(&self, image_id: u32) -> Option<&NovelUploadedImage> {
+        self.images.get(&image_id.to_string())
+    }
+
+    pub fn series_navigation(&self) -> Option<&NovelNavigation> {
+        self.series_navigation.as_ref()
+    }
+
+    pub fn into_inner(self) -> String {
+        self.text
+    }
+}
@@ -0,0 +1,16 @@
+use crate::pixiv::request::PixivRequest;
+use crate::pixiv::request_builder::PixivRequestBuilder;
+
+/// Implemented by results that are split into pages by the API.
+/// Each page carries the url of the following one in `next_url`.
+pub trait Paginated {
+    /// Url of the next page, or `None` once the last page has been reached.
+    fn next_url(&self) -> Option<&String>;
+
+    /// Builds the request fetching the next page, if there is one.
+    /// A `next_url` that is not a valid url ends the pagination.
+    fn next_request(&self) -> Option<PixivRequest> {
+        self.next_url()
+            .and_then(PixivRequestBuilder::request_next_page)
+    }
+}
@@ -0,0 +1,16 @@
+use crate::pixiv::helper_structs::ugoira_metadata::UgoiraMetadata;
+
+use serde::{Deserialize, Serialize};
+
+/// PixivClient hides the actual metadata object behind the value "ugoira_metadata".
+/// This struct exists purely to bypass this indirection...
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UgoiraMetadataProxy {
+    pub ugoira_metadata: UgoiraMetadata,
+}
+
+impl UgoiraMetadataProxy {
+    pub fn into_inner(self) -> UgoiraMetadata {
+        self.ugoira_metadata
+    }
+}
@@ -0,0 +1,38 @@
+use crate::pixiv::helper_structs::profile_publicity::ProfilePublicity;
+use crate::pixiv::helper_structs::user_profile::UserProfile;
+use crate::pixiv::helper_structs::workspace::Workspace;
+use crate::pixiv::user::User;
+
+use serde::{Deserialize, Serialize};
+
+/// UserDetail
+/// Returned by `PixivRequestBuilder::request_user_detail`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserDetail {
+    user: User,
+    profile: UserProfile,
+    profile_publicity: ProfilePublicity,
+    workspace: Workspace,
+}
+
+impl UserDetail {
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn profile(&self) -> &UserProfile {
+        &self.profile
+    }
+
+    pub fn profile_publicity(&self) -> &ProfilePublicity {
+        &self.profile_publicity
+    }
+
+    pub fn workspace(&self) -> &Workspace {
+        &self.workspace
+    }
+
+    pub fn into_inner(self) -> User {
+        self.user
+    }
+}
@@ -0,0 +1,40 @@
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::result::paginated::Paginated;
+
+use serde::{Deserialize, Serialize};
+
+/// UserIllustrations
+/// Returned by `PixivRequestBuilder::request_user_illustrations` and
+/// `PixivRequestBuilder::request_user_bookmarks_illustration`.
+/// `next_url` is `None` once the last page has been reached.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserIllustrations {
+    illusts: Vec<Illustration>,
+    next_url: Option<String>,
+}
+
+impl UserIllustrations {
+    pub fn illusts(&self) -> &Vec<Illustration> {
+        &self.illusts
+    }
+
+    pub fn into_inner(self) -> Vec<Illustration> {
+        self.illusts
+    }
+}
+
+impl Paginated for UserIllustrations {
+    fn next_url(&self) -> Option<&String> {
+        self.next_url.as_ref()
+    }
+}
+
+impl IntoIterator for UserIllustrations {
+    type Item = Illustration;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    /// Consume the struct, yielding an iterator.
+    fn into_iter(self) -> Self::IntoIter {
+        self.illusts.into_iter()
+    }
+}
@@ -0,0 +1,34 @@
+use crate::pixiv::helper_structs::novel::Novel;
+use crate::pixiv::result::paginated::Paginated;
+use crate::pixiv::user::User;
+
+use serde::{Deserialize, Serialize};
+
+/// UserNovels
+/// Returned by `PixivRequestBuilder::request_user_novels`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserNovels {
+    user: User,
+    novels: Vec<Novel>,
+    next_url: Option<String>,
+}
+
+impl UserNovels {
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn novels(&self) -> &Vec<Novel> {
+        &self.novels
+    }
+
+    pub fn into_inner(self) -> Vec<Novel> {
+        self.novels
+    }
+}
+
+impl Paginated for UserNovels {
+    fn next_url(&self) -> Option<&String> {
+        self.next_url.as_ref()
+    }
+}
@@ -0,0 +1,40 @@
+use crate::pixiv::helper_structs::user_preview::UserPreview;
+use crate::pixiv::result::paginated::Paginated;
+
+use serde::{Deserialize, Serialize};
+
+/// UserPreviews
+/// Returned by the user listing requests: following, follower, mypixiv, recommended users
+/// and user search.
+/// `next_url` is `None` once the last page has been reached.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UserPreviews {
+    user_previews: Vec<UserPreview>,
+    next_url: Option<String>,
+}
+
+impl UserPreviews {
+    pub fn user_previews(&self) -> &Vec<UserPreview> {
+        &self.user_previews
+    }
+
+    pub fn into_inner(self) -> Vec<UserPreview> {
+        self.user_previews
+    }
+}
+
+impl Paginated for UserPreviews {
+    fn next_url(&self) -> Option<&String> {
+        self.next_url.as_ref()
+    }
+}
+
+impl IntoIterator for UserPreviews {
+    type Item = UserPreview;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    /// Consume the struct, yielding an iterator.
+    fn into_iter(self) -> Self::IntoIter {
+        self.user_previews.into_iter()
+    }
+}
@@ -6,7 +6,9 @@ use serde::{Deserialize, Serialize};
 #[derive(Deserialize, Serialize, Debug)]
 pub struct User {
     account: String,
+    comment: Option<String>,
     id: u32,
+    is_access_blocking_user: Option<bool>,
     is_followed: Option<bool>,
     name: String,
     profile_image_urls: ImageUrl,
@@ -17,10 +19,19 @@ impl User {
         &self.account
     }
 
+    /// The user's self introduction. Only present in user detail responses.
+    pub fn comment(&self) -> Option<&String> {
+        self.comment.as_ref()
+    }
+
     pub fn id(&self) -> u32 {
         self.id
     }
 
+    pub fn is_access_blocking_user(&self) -> Option<bool> {
+        self.is_access_blocking_user
+    }
+
     pub fn is_followed(&self) -> Option<bool> {
         self.is_followed
     }
@@ -0,0 +1,584 @@
+use crate::enums::Visibility;
+use crate::errors::StorageError;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::user::User;
+use crate::storage::query::{BookmarkState, HistoryEntry, IllustrationQuery};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row, Transaction};
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY,
+    account TEXT NOT NULL,
+    name TEXT NOT NULL,
+    is_followed INTEGER,
+    raw TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS series (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS illustrations (
+    id INTEGER PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id),
+    series_id INTEGER REFERENCES series(id),
+    title TEXT NOT NULL,
+    type TEXT NOT NULL,
+    create_date TEXT NOT NULL,
+    created_at INTEGER,
+    page_count INTEGER NOT NULL,
+    sanity_level INTEGER NOT NULL,
+    x_restrict INTEGER NOT NULL,
+    total_view INTEGER NOT NULL,
+    total_bookmarks INTEGER NOT NULL,
+    total_comments INTEGER,
+    raw TEXT NOT NULL,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS illustrations_user ON illustrations(user_id);
+CREATE INDEX IF NOT EXISTS illustrations_created ON illustrations(created_at);
+CREATE INDEX IF NOT EXISTS illustrations_bookmarks ON illustrations(total_bookmarks);
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    translated_name TEXT
+);
+CREATE TABLE IF NOT EXISTS illustration_tags (
+    illust_id INTEGER NOT NULL REFERENCES illustrations(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id),
+    position INTEGER NOT NULL,
+    PRIMARY KEY (illust_id, tag_id)
+);
+CREATE INDEX IF NOT EXISTS illustration_tags_tag ON illustration_tags(tag_id);
+CREATE TABLE IF NOT EXISTS illustration_history (
+    illust_id INTEGER NOT NULL REFERENCES illustrations(id) ON DELETE CASCADE,
+    seen_at INTEGER NOT NULL,
+    total_view INTEGER NOT NULL,
+    total_bookmarks INTEGER NOT NULL,
+    total_comments INTEGER,
+    is_bookmarked INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS illustration_history_illust ON illustration_history(illust_id);
+CREATE TABLE IF NOT EXISTS bookmarks (
+    illust_id INTEGER PRIMARY KEY,
+    is_bookmarked INTEGER NOT NULL,
+    visibility TEXT,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS bookmark_history (
+    illust_id INTEGER NOT NULL,
+    is_bookmarked INTEGER NOT NULL,
+    visibility TEXT,
+    changed_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS bookmark_history_illust ON bookmark_history(illust_id);
+";
+
+/// A SQLite database of the illustrations, users, tags and series seen so far.
+///
+/// Records are upserted: `first_seen` is kept from the first time a row was written and
+/// `last_seen` moves forward. Every change in an illustration's view, bookmark and comment
+/// counts is appended to its history, and every change of bookmark state to the bookmark
+/// history. The full API response is kept next to the normalized columns so that queries
+/// can hand back complete `Illustration`s.
+#[derive(Debug)]
+pub struct Catalog {
+    connection: Connection,
+}
+
+impl Catalog {
+    /// Opens the catalog at `path`, creating the database and its tables if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// A catalog that lives only as long as the returned value.
+    pub fn in_memory() -> Result<Self, StorageError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(connection: Connection) -> Result<Self, StorageError> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Catalog { connection })
+    }
+
+    /// The underlying connection, for queries the helpers do not cover.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Upserts an illustration together with its user, series and tags.
+    pub fn record_illustration(&mut self, illust: &Illustration) -> Result<(), StorageError> {
+        let tx = self.connection.transaction()?;
+        insert_illustration(&tx, illust, now())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Upserts many illustrations in one transaction, returning how many were written.
+    pub fn record_illustrations<'a, I>(&mut self, illusts: I) -> Result<usize, StorageError>
+    where
+        I: IntoIterator<Item = &'a Illustration>,
+    {
+        let tx = self.connection.transaction()?;
+        let seen_at = now();
+        let mut count = 0;
+        for illust in illusts {
+            insert_illustration(&tx, illust, seen_at)?;
+            count += 1;
+        }
+        tx.commit()?;
+        Ok(count)
+    }
+
+    /// Upserts a user, e.g. one returned by a user search or the following list.
+    pub fn record_user(&mut self, user: &User) -> Result<(), StorageError> {
+        let tx = self.connection.transaction()?;
+        insert_user(&tx, user, now())?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Upserts every illustration and user found in a raw API response, returning how many
+    /// were written.
+    ///
+    /// Illustrations are looked up under `illust`, `illusts` and `ranking_illusts` and users
+    /// under `user`, at any depth. Objects that don't parse as one are skipped.
+    pub fn record_response(&mut self, response: &Value) -> Result<usize, StorageError> {
+        let mut illusts = Vec::new();
+        let mut users = Vec::new();
+        collect_seen(response, &mut illusts, &mut users);
+
+        let tx = self.connection.transaction()?;
+        let seen_at = now();
+        for user in &users {
+            insert_user(&tx, user, seen_at)?;
+        }
+        for illust in &illusts {
+            insert_illustration(&tx, illust, seen_at)?;
+        }
+        tx.commit()?;
+        Ok(users.len() + illusts.len())
+    }
+
+    /// Records the bookmark state of an illustration.
+    ///
+    /// Returns true if the state changed, in which case it is appended to the bookmark
+    /// history. A `None` visibility keeps the one already known.
+    pub fn record_bookmark(
+        &mut self,
+        illust_id: u32,
+        bookmarked: bool,
+        visibility: Option<Visibility>,
+    ) -> Result<bool, StorageError> {
+        let tx = self.connection.transaction()?;
+        let changed = update_bookmark(&tx, illust_id, bookmarked, visibility, now())?;
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    /// Records bookmark additions and removals in one transaction, e.g. those found by a
+    /// bookmark sync. The added illustrations are upserted too.
+    pub fn record_bookmark_sync<'a, A, R>(
+        &mut self,
+        added: A,
+        removed: R,
+    ) -> Result<(), StorageError>
+    where
+        A: IntoIterator<Item = (Visibility, &'a Illustration)>,
+        R: IntoIterator<Item = (Visibility, u32)>,
+    {
+        let tx = self.connection.transaction()?;
+        let seen_at = now();
+        for (visibility, illust) in added {
+            insert_illustration(&tx, illust, seen_at)?;
+            update_bookmark(&tx, illust.id(), true, Some(visibility), seen_at)?;
+        }
+        for (visibility, illust_id) in removed {
+            update_bookmark(&tx, illust_id, false, Some(visibility), seen_at)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn illustration(&self, id: u32) -> Result<Option<Illustration>, StorageError> {
+        let raw: Option<String> = self
+            .connection
+            .query_row("SELECT raw FROM illustrations WHERE id = ?", [id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(raw.map(|raw| serde_json::from_str(&raw)).transpose()?)
+    }
+
+    pub fn user(&self, id: u32) -> Result<Option<User>, StorageError> {
+        let raw: Option<String> = self
+            .connection
+            .query_row("SELECT raw FROM users WHERE id = ?", [id], |row| row.get(0))
+            .optional()?;
+        Ok(raw.map(|raw| serde_json::from_str(&raw)).transpose()?)
+    }
+
+    pub fn contains_illustration(&self, id: u32) -> Result<bool, StorageError> {
+        Ok(self
+            .connection
+            .query_row("SELECT 1 FROM illustrations WHERE id = ?", [id], |_| Ok(()))
+            .optional()?
+            .is_some())
+    }
+
+    /// When the illustration was first recorded.
+    pub fn first_seen(&self, id: u32) -> Result<Option<DateTime<Utc>>, StorageError> {
+        self.seen(id, "first_seen")
+    }
+
+    /// When the illustration was last recorded.
+    pub fn last_seen(&self, id: u32) -> Result<Option<DateTime<Utc>>, StorageError> {
+        self.seen(id, "last_seen")
+    }
+
+    fn seen(&self, id: u32, column: &str) -> Result<Option<DateTime<Utc>>, StorageError> {
+        Ok(self
+            .connection
+            .query_row(
+                &format!("SELECT {} FROM illustrations WHERE id = ?", column),
+                [id],
+                |row| timestamp(row, 0),
+            )
+            .optional()?)
+    }
+
+    /// Illustrations matching every filter of `query`, newest first.
+    pub fn query(&self, query: &IllustrationQuery) -> Result<Vec<Illustration>, StorageError> {
+        let (tail, params) = query.to_sql();
+        let sql = format!(
+            "SELECT i.raw FROM illustrations i LEFT JOIN bookmarks b ON b.illust_id = i.id{}",
+            tail
+        );
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(params), |row| row.get::<_, String>(0))?;
+        let mut illusts = Vec::new();
+        for raw in rows {
+            illusts.push(serde_json::from_str(&raw?)?);
+        }
+        Ok(illusts)
+    }
+
+    /// Illustrations tagged with `tag`, or whose tag translates to it.
+    pub fn by_tag(&self, tag: &str) -> Result<Vec<Illustration>, StorageError> {
+        self.query(&IllustrationQuery::new().add_tag(tag))
+    }
+
+    pub fn by_user(&self, user_id: u32) -> Result<Vec<Illustration>, StorageError> {
+        self.query(&IllustrationQuery::new().set_user_id(user_id))
+    }
+
+    /// Illustrations created in `[from, to)`.
+    pub fn created_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Illustration>, StorageError> {
+        self.query(
+            &IllustrationQuery::new()
+                .set_created_after(from)
+                .set_created_before(to),
+        )
+    }
+
+    pub fn with_min_bookmarks(&self, min: u32) -> Result<Vec<Illustration>, StorageError> {
+        self.query(&IllustrationQuery::new().set_min_bookmarks(min))
+    }
+
+    /// Every recorded change in the illustration's counts, oldest first.
+    pub fn history(&self, illust_id: u32) -> Result<Vec<HistoryEntry>, StorageError> {
+        let mut statement = self.connection.prepare(
+            "SELECT seen_at, total_view, total_bookmarks, total_comments, is_bookmarked
+             FROM illustration_history WHERE illust_id = ? ORDER BY seen_at, rowid",
+        )?;
+        let rows = statement.query_map([illust_id], |row| {
+            Ok(HistoryEntry {
+                seen_at: timestamp(row, 0)?,
+                total_view: row.get(1)?,
+                total_bookmarks: row.get(2)?,
+                total_comments: row.get(3)?,
+                is_bookmarked: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn bookmark(&self, illust_id: u32) -> Result<Option<BookmarkState>, StorageError> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT illust_id, is_bookmarked, visibility, updated_at
+                 FROM bookmarks WHERE illust_id = ?",
+                [illust_id],
+                bookmark_state,
+            )
+            .optional()?)
+    }
+
+    /// Every change of the illustration's bookmark state, oldest first.
+    pub fn bookmark_history(&self, illust_id: u32) -> Result<Vec<BookmarkState>, StorageError> {
+        let mut statement = self.connection.prepare(
+            "SELECT illust_id, is_bookmarked, visibility, changed_at
+             FROM bookmark_history WHERE illust_id = ? ORDER BY changed_at, rowid",
+        )?;
+        let rows = statement.query_map([illust_id], bookmark_state)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Ids of the illustrations currently bookmarked, with `visibility` if given.
+    pub fn bookmarked_ids(&self, visibility: Option<Visibility>) -> Result<Vec<u32>, StorageError> {
+        let mut statement = self.connection.prepare(
+            "SELECT illust_id FROM bookmarks
+             WHERE is_bookmarked = 1 AND (?1 IS NULL OR visibility = ?1)
+             ORDER BY illust_id DESC",
+        )?;
+        let rows = statement.query_map([visibility.map(|v| v.as_str())], |row| row.get(0))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+fn collect_seen(value: &Value, illusts: &mut Vec<Illustration>, users: &mut Vec<User>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_seen(item, illusts, users);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                match (key.as_str(), value) {
+                    ("illust", _) => illusts.extend(Illustration::deserialize(value).ok()),
+                    ("illusts" | "ranking_illusts", Value::Array(items)) => illusts.extend(
+                        items
+                            .iter()
+                            .filter_map(|item| Illustration::deserialize(item).ok()),
+                    ),
+                    ("user", _) => users.extend(User::deserialize(value).ok()),
+                    _ => collect_seen(value, illusts, users),
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+fn timestamp(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let seconds: i64 = row.get(index)?;
+    DateTime::from_timestamp(seconds, 0)
+        .ok_or(rusqlite::Error::IntegralValueOutOfRange(index, seconds))
+}
+
+fn bookmark_state(row: &Row) -> rusqlite::Result<BookmarkState> {
+    let visibility: Option<String> = row.get(2)?;
+    Ok(BookmarkState {
+        illust_id: row.get(0)?,
+        is_bookmarked: row.get(1)?,
+        visibility: visibility.and_then(|v| match v.as_str() {
+            "public" => Some(Visibility::Public),
+            "private" => Some(Visibility::Private),
+            _ => None,
+        }),
+        updated_at: timestamp(row, 3)?,
+    })
+}
+
+fn insert_user(tx: &Transaction, user: &User, seen_at: i64) -> Result<(), StorageError> {
+    tx.execute(
+        "INSERT INTO users (id, account, name, is_followed, raw, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)
+         ON CONFLICT(id) DO UPDATE SET
+             account = excluded.account,
+             name = excluded.name,
+             is_followed = COALESCE(excluded.is_followed, users.is_followed),
+             raw = excluded.raw,
+             last_seen = excluded.last_seen",
+        params![
+            user.id(),
+            user.account(),
+            user.name(),
+            user.is_followed(),
+            serde_json::to_string(user)?,
+            seen_at
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_illustration(
+    tx: &Transaction,
+    illust: &Illustration,
+    seen_at: i64,
+) -> Result<(), StorageError> {
+    insert_user(tx, illust.user(), seen_at)?;
+    if let Some(series) = illust.series() {
+        tx.execute(
+            "INSERT INTO series (id, title, first_seen, last_seen) VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT(id) DO UPDATE SET title = excluded.title, last_seen = excluded.last_seen",
+            params![series.id(), series.title(), seen_at],
+        )?;
+    }
+
+    let created_at = DateTime::parse_from_rfc3339(illust.create_date())
+        .ok()
+        .map(|date| date.timestamp());
+    tx.execute(
+        "INSERT INTO illustrations (id, user_id, series_id, title, type, create_date, created_at,
+             page_count, sanity_level, x_restrict, total_view, total_bookmarks, total_comments,
+             raw, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?15)
+         ON CONFLICT(id) DO UPDATE SET
+             user_id = excluded.user_id,
+             series_id = excluded.series_id,
+             title = excluded.title,
+             type = excluded.type,
+             create_date = excluded.create_date,
+             created_at = excluded.created_at,
+             page_count = excluded.page_count,
+             sanity_level = excluded.sanity_level,
+             x_restrict = excluded.x_restrict,
+             total_view = excluded.total_view,
+             total_bookmarks = excluded.total_bookmarks,
+             total_comments = COALESCE(excluded.total_comments, illustrations.total_comments),
+             raw = excluded.raw,
+             last_seen = excluded.last_seen",
+        params![
+            illust.id(),
+            illust.user().id(),
+            illust.series().map(|series| series.id()),
+            illust.title(),
+            illust.content_type().as_str(),
+            illust.create_date(),
+            created_at,
+            illust.page_count(),
+            illust.sanity_level(),
+            illust.x_restrict(),
+            illust.total_view(),
+            illust.total_bookmarks(),
+            illust.total_comments(),
+            serde_json::to_string(illust)?,
+            seen_at
+        ],
+    )?;
+
+    tx.execute(
+        "DELETE FROM illustration_tags WHERE illust_id = ?",
+        [illust.id()],
+    )?;
+    for (position, tag) in illust.tags().iter().enumerate() {
+        let translated = tag
+            .translated_name()
+            .as_ref()
+            .and_then(|names| names.first());
+        let tag_id: i64 = tx.query_row(
+            "INSERT INTO tags (name, translated_name) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET
+                 translated_name = COALESCE(excluded.translated_name, tags.translated_name)
+             RETURNING id",
+            params![tag.name(), translated],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO illustration_tags (illust_id, tag_id, position)
+             VALUES (?1, ?2, ?3)",
+            params![illust.id(), tag_id, position as i64],
+        )?;
+    }
+
+    // Only append to the history when a count moved since the last entry.
+    let last: Option<(u32, u32, Option<u32>, bool)> = tx
+        .query_row(
+            "SELECT total_view, total_bookmarks, total_comments, is_bookmarked
+             FROM illustration_history WHERE illust_id = ? ORDER BY seen_at DESC, rowid DESC",
+            [illust.id()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()?;
+    let current = (
+        illust.total_view(),
+        illust.total_bookmarks(),
+        illust.total_comments(),
+        illust.is_bookmarked(),
+    );
+    if last != Some(current) {
+        tx.execute(
+            "INSERT INTO illustration_history
+                 (illust_id, seen_at, total_view, total_bookmarks, total_comments, is_bookmarked)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                illust.id(),
+                seen_at,
+                current.0,
+                current.1,
+                current.2,
+                current.3
+            ],
+        )?;
+    }
+
+    update_bookmark(tx, illust.id(), illust.is_bookmarked(), None, seen_at)?;
+    Ok(())
+}
+
+fn update_bookmark(
+    tx: &Transaction,
+    illust_id: u32,
+    bookmarked: bool,
+    visibility: Option<Visibility>,
+    changed_at: i64,
+) -> Result<bool, StorageError> {
+    let previous: Option<(bool, Option<String>)> = tx
+        .query_row(
+            "SELECT is_bookmarked, visibility FROM bookmarks WHERE illust_id = ?",
+            [illust_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let visibility = visibility
+        .map(|v| v.as_str().to_owned())
+        .or_else(|| previous.as_ref().and_then(|(_, v)| v.clone()));
+    let changed = match &previous {
+        Some((was_bookmarked, was_visibility)) => {
+            *was_bookmarked != bookmarked || *was_visibility != visibility
+        }
+        // An illustration that was never bookmarked needs no entry.
+        None => bookmarked,
+    };
+    if !changed {
+        return Ok(false);
+    }
+
+    tx.execute(
+        "INSERT INTO bookmarks (illust_id, is_bookmarked, visibility, updated_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(illust_id) DO UPDATE SET
+             is_bookmarked = excluded.is_bookmarked,
+             visibility = excluded.visibility,
+             updated_at = excluded.updated_at",
+        params![illust_id, bookmarked, visibility, changed_at],
+    )?;
+    tx.execute(
+        "INSERT INTO bookmark_history (illust_id, is_bookmarked, visibility, changed_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![illust_id, bookmarked, visibility, changed_at],
+    )?;
+    Ok(true)
+}
@@ -0,0 +1,177 @@
+use crate::enums::Visibility;
+
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+
+/// Filters for `Catalog::query`. Every filter that is set must match.
+#[derive(Debug, Clone, Default)]
+pub struct IllustrationQuery {
+    tags: Vec<String>,
+    user_id: Option<u32>,
+    series_id: Option<u32>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    min_bookmarks: Option<u32>,
+    bookmarked: Option<bool>,
+    limit: Option<u32>,
+}
+
+impl IllustrationQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only works tagged with `tag`, matched against the tag or its translation.
+    pub fn add_tag<T: Into<String>>(mut self, tag: T) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn set_user_id(mut self, user_id: u32) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    pub fn set_series_id(mut self, series_id: u32) -> Self {
+        self.series_id = Some(series_id);
+        self
+    }
+
+    /// Only works created at or after `date`.
+    pub fn set_created_after(mut self, date: DateTime<Utc>) -> Self {
+        self.created_after = Some(date);
+        self
+    }
+
+    /// Only works created strictly before `date`.
+    pub fn set_created_before(mut self, date: DateTime<Utc>) -> Self {
+        self.created_before = Some(date);
+        self
+    }
+
+    pub fn set_min_bookmarks(mut self, min: u32) -> Self {
+        self.min_bookmarks = Some(min);
+        self
+    }
+
+    /// Only works the logged in user has (or has not) bookmarked.
+    pub fn set_bookmarked(mut self, bookmarked: bool) -> Self {
+        self.bookmarked = Some(bookmarked);
+        self
+    }
+
+    pub fn set_limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Builds the `WHERE ... ORDER BY ... LIMIT` tail of the select and its parameters.
+    pub(crate) fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        for tag in &self.tags {
+            clauses.push(
+                "EXISTS (SELECT 1 FROM illustration_tags it JOIN tags t ON t.id = it.tag_id \
+                 WHERE it.illust_id = i.id AND (t.name = ? OR t.translated_name = ?))",
+            );
+            params.push(Value::Text(tag.clone()));
+            params.push(Value::Text(tag.clone()));
+        }
+        if let Some(user_id) = self.user_id {
+            clauses.push("i.user_id = ?");
+            params.push(Value::Integer(user_id.into()));
+        }
+        if let Some(series_id) = self.series_id {
+            clauses.push("i.series_id = ?");
+            params.push(Value::Integer(series_id.into()));
+        }
+        if let Some(date) = self.created_after {
+            clauses.push("i.created_at >= ?");
+            params.push(Value::Integer(date.timestamp()));
+        }
+        if let Some(date) = self.created_before {
+            clauses.push("i.created_at < ?");
+            params.push(Value::Integer(date.timestamp()));
+        }
+        if let Some(min) = self.min_bookmarks {
+            clauses.push("i.total_bookmarks >= ?");
+            params.push(Value::Integer(min.into()));
+        }
+        if let Some(bookmarked) = self.bookmarked {
+            clauses.push("COALESCE(b.is_bookmarked, 0) = ?");
+            params.push(Value::Integer(bookmarked.into()));
+        }
+
+        let mut sql = String::new();
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
+        }
+        sql.push_str(" ORDER BY i.created_at DESC, i.id DESC");
+        if let Some(limit) = self.limit {
+            sql.push_str(" LIMIT ?");
+            params.push(Value::Integer(limit.into()));
+        }
+        (sql, params)
+    }
+}
+
+/// The statistics of an illustration at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub(crate) seen_at: DateTime<Utc>,
+    pub(crate) total_view: u32,
+    pub(crate) total_bookmarks: u32,
+    pub(crate) total_comments: Option<u32>,
+    pub(crate) is_bookmarked: bool,
+}
+
+impl HistoryEntry {
+    pub fn seen_at(&self) -> DateTime<Utc> {
+        self.seen_at
+    }
+
+    pub fn total_view(&self) -> u32 {
+        self.total_view
+    }
+
+    pub fn total_bookmarks(&self) -> u32 {
+        self.total_bookmarks
+    }
+
+    pub fn total_comments(&self) -> Option<u32> {
+        self.total_comments
+    }
+
+    pub fn is_bookmarked(&self) -> bool {
+        self.is_bookmarked
+    }
+}
+
+/// Whether an illustration is bookmarked, and since when.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookmarkState {
+    pub(crate) illust_id: u32,
+    pub(crate) is_bookmarked: bool,
+    pub(crate) visibility: Option<Visibility>,
+    pub(crate) updated_at: DateTime<Utc>,
+}
+
+impl BookmarkState {
+    pub fn illust_id(&self) -> u32 {
+        self.illust_id
+    }
+
+    pub fn is_bookmarked(&self) -> bool {
+        self.is_bookmarked
+    }
+
+    /// `None` when the state was taken from an illustration, which does not say.
+    pub fn visibility(&self) -> Option<Visibility> {
+        self.visibility
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+}
@@ -0,0 +1,464 @@
+use crate::enums::{BackupFormat, Visibility};
+use crate::errors::SyncError;
+use crate::pixiv::arg::user_bookmarks_illustration_request_arg::UserBookmarksIllustrationRequestArg;
+use crate::pixiv::client::PixivClient;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::request::PixivRequest;
+use crate::pixiv::request_builder::PixivRequestBuilder;
+use crate::pixiv::result::illustration_bookmark_info_proxy::IllustBookmarkInfoProxy;
+use crate::pixiv::result::paginated::Paginated;
+use crate::pixiv::result::user_illustrations::UserIllustrations;
+use crate::sync::throttle::Throttle;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+const CSV_HEADER: [&str; 9] = [
+    "illust_id",
+    "restrict",
+    "tags",
+    "title",
+    "user_id",
+    "user_name",
+    "create_date",
+    "total_bookmarks",
+    "url",
+];
+
+/// One bookmark: the illustration, its visibility and its bookmark tags.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BookmarkRecord {
+    illust_id: u32,
+    restrict: Visibility,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    illustration: Option<Illustration>,
+}
+
+impl BookmarkRecord {
+    pub fn new(illust_id: u32, restrict: Visibility, tags: Vec<String>) -> Self {
+        BookmarkRecord {
+            illust_id,
+            restrict,
+            tags,
+            illustration: None,
+        }
+    }
+
+    pub fn set_illustration(self, illustration: Illustration) -> Self {
+        BookmarkRecord {
+            illustration: Some(illustration),
+            ..self
+        }
+    }
+
+    pub fn illust_id(&self) -> u32 {
+        self.illust_id
+    }
+
+    pub fn restrict(&self) -> Visibility {
+        self.restrict
+    }
+
+    pub fn tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    /// The illustration as it was when exported. Not kept by CSV backups.
+    pub fn illustration(&self) -> Option<&Illustration> {
+        self.illustration.as_ref()
+    }
+}
+
+/// Every bookmark of an account, newest first.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BookmarkBackup {
+    #[serde(default)]
+    user_id: Option<u32>,
+    #[serde(default)]
+    exported_at: Option<String>,
+    bookmarks: Vec<BookmarkRecord>,
+}
+
+impl BookmarkBackup {
+    pub fn new(user_id: u32) -> Self {
+        BookmarkBackup {
+            user_id: Some(user_id),
+            exported_at: Some(
+                chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            ),
+            bookmarks: Vec::new(),
+        }
+    }
+
+    /// The exported account. Not kept by CSV backups.
+    pub fn user_id(&self) -> Option<u32> {
+        self.user_id
+    }
+
+    /// When the export was made, as an RFC 3339 date. Not kept by CSV backups.
+    pub fn exported_at(&self) -> Option<&String> {
+        self.exported_at.as_ref()
+    }
+
+    pub fn bookmarks(&self) -> &Vec<BookmarkRecord> {
+        &self.bookmarks
+    }
+
+    pub fn push(&mut self, record: BookmarkRecord) {
+        self.bookmarks.push(record);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bookmarks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty()
+    }
+
+    pub fn to_string(&self, format: BackupFormat) -> Result<String, SyncError> {
+        match format {
+            BackupFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            BackupFormat::Csv => Ok(self.to_csv()),
+        }
+    }
+
+    pub fn parse(text: &str, format: BackupFormat) -> Result<Self, SyncError> {
+        match format {
+            BackupFormat::Json => Ok(serde_json::from_str(text)?),
+            BackupFormat::Csv => Self::from_csv(text),
+        }
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, path: P, format: BackupFormat) -> Result<(), SyncError> {
+        Ok(fs::write(path, self.to_string(format)?)?)
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P, format: BackupFormat) -> Result<Self, SyncError> {
+        Self::parse(&fs::read_to_string(path)?, format)
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        push_csv_row(&mut csv, CSV_HEADER.iter().map(|field| field.to_string()));
+        for record in &self.bookmarks {
+            let illust = record.illustration.as_ref();
+            push_csv_row(
+                &mut csv,
+                [
+                    record.illust_id.to_string(),
+                    record.restrict.as_str().to_string(),
+                    // Bookmark tags can't contain spaces.
+                    record.tags.join(" "),
+                    illust.map(|i| i.title().clone()).unwrap_or_default(),
+                    illust
+                        .map(|i| i.user().id().to_string())
+                        .unwrap_or_default(),
+                    illust.map(|i| i.user().name().clone()).unwrap_or_default(),
+                    illust.map(|i| i.create_date().clone()).unwrap_or_default(),
+                    illust
+                        .map(|i| i.total_bookmarks().to_string())
+                        .unwrap_or_default(),
+                    illust.map(|i| i.artwork_url()).unwrap_or_default(),
+                ],
+            );
+        }
+        csv
+    }
+
+    fn from_csv(text: &str) -> Result<Self, SyncError> {
+        let mut rows = parse_csv(text)?.into_iter();
+        let header = rows
+            .next()
+            .ok_or_else(|| SyncError::InvalidBackup("the CSV is empty".to_string()))?;
+        let column = |name: &str| header.iter().position(|field| field == name);
+        let missing = |name: &str| SyncError::InvalidBackup(format!("no {:?} column", name));
+        let id_column = column("illust_id").ok_or_else(|| missing("illust_id"))?;
+        let restrict_column = column("restrict").ok_or_else(|| missing("restrict"))?;
+        let tags_column = column("tags");
+
+        let mut backup = BookmarkBackup::default();
+        for (line, row) in rows.enumerate() {
+            let field = |index: usize| row.get(index).map(String::as_str).unwrap_or_default();
+            let invalid =
+                |what: &str| SyncError::InvalidBackup(format!("row {}: {}", line + 2, what));
+            let illust_id = field(id_column)
+                .parse()
+                .map_err(|_| invalid("invalid illust_id"))?;
+            let restrict = match field(restrict_column) {
+                "public" => Visibility::Public,
+                "private" => Visibility::Private,
+                _ => return Err(invalid("invalid restrict")),
+            };
+            let tags = tags_column
+                .map(|index| field(index).split_whitespace().map(String::from).collect())
+                .unwrap_or_default();
+            backup.push(BookmarkRecord::new(illust_id, restrict, tags));
+        }
+        Ok(backup)
+    }
+}
+
+fn push_csv_row<I: IntoIterator<Item = String>>(csv: &mut String, fields: I) {
+    for (index, field) in fields.into_iter().enumerate() {
+        if index > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(&field);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+/// Splits RFC 4180 CSV into rows of fields, skipping blank lines.
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, SyncError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|field| !field.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(SyncError::InvalidBackup(
+            "unterminated quoted field".to_string(),
+        ));
+    }
+    row.push(field);
+    if row.iter().any(|field| !field.is_empty()) {
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Exports the bookmarks of a user into a `BookmarkBackup`.
+#[derive(Debug)]
+pub struct BookmarkExporter {
+    user_id: u32,
+    visibilities: Vec<Visibility>,
+    fetch_tags: bool,
+    delay: Duration,
+}
+
+impl BookmarkExporter {
+    /// Exports the public and private bookmarks of `user_id`, with their tags, sending at
+    /// most one request per second.
+    pub fn new(user_id: u32) -> Self {
+        BookmarkExporter {
+            user_id,
+            visibilities: vec![Visibility::Public, Visibility::Private],
+            fetch_tags: true,
+            delay: Duration::from_secs(1),
+        }
+    }
+
+    pub fn set_visibilities(self, visibilities: Vec<Visibility>) -> Self {
+        BookmarkExporter {
+            visibilities,
+            ..self
+        }
+    }
+
+    /// The bookmark tags take one more request per bookmark. Without them the export
+    /// only needs one request per page of bookmarks.
+    pub fn set_fetch_tags(self, fetch_tags: bool) -> Self {
+        BookmarkExporter { fetch_tags, ..self }
+    }
+
+    /// The minimum time between two requests.
+    pub fn set_delay(self, delay: Duration) -> Self {
+        BookmarkExporter { delay, ..self }
+    }
+
+    pub fn export(&self, client: &PixivClient) -> Result<BookmarkBackup, SyncError> {
+        self.export_with(|request| Ok(client.execute_json(request)?))
+    }
+
+    /// Like `export`, sending the requests through `fetch`.
+    pub fn export_with<F>(&self, mut fetch: F) -> Result<BookmarkBackup, SyncError>
+    where
+        F: FnMut(PixivRequest) -> Result<Value, SyncError>,
+    {
+        let mut throttle = Throttle::new(self.delay);
+        let mut fetch = |request| {
+            throttle.wait();
+            fetch(request)
+        };
+        let mut backup = BookmarkBackup::new(self.user_id);
+        for &visibility in &self.visibilities {
+            let mut args = UserBookmarksIllustrationRequestArg::new(self.user_id);
+            args.restrict = visibility;
+            let mut request = Some(PixivRequestBuilder::request_user_bookmarks_illustration(
+                args,
+            ));
+            while let Some(current) = request {
+                let page: UserIllustrations = serde_json::from_value(fetch(current)?)?;
+                request = page.next_request();
+                for illustration in page {
+                    let tags = if self.fetch_tags {
+                        let request = PixivRequestBuilder::request_illustration_bookmark_info(
+                            illustration.id() as usize,
+                        );
+                        let detail: IllustBookmarkInfoProxy =
+                            serde_json::from_value(fetch(request)?)?;
+                        detail
+                            .into_inner()
+                            .registered_tags()
+                            .into_iter()
+                            .cloned()
+                            .collect()
+                    } else {
+                        Vec::new()
+                    };
+                    backup.push(
+                        BookmarkRecord::new(illustration.id(), visibility, tags)
+                            .set_illustration(illustration),
+                    );
+                }
+            }
+        }
+        Ok(backup)
+    }
+}
+
+/// What a restore did, or would do in a dry run.
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    dry_run: bool,
+    restored: Vec<u32>,
+    failed: Vec<(u32, SyncError)>,
+}
+
+impl RestoreReport {
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Ids of the illustrations bookmarked, or that would be in a dry run, in request order.
+    pub fn restored(&self) -> &Vec<u32> {
+        &self.restored
+    }
+
+    pub fn failed(&self) -> &Vec<(u32, SyncError)> {
+        &self.failed
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Adds the bookmarks of a `BookmarkBackup` to the logged in account.
+///
+/// Bookmarks are added oldest first so that they keep their order in the new account.
+#[derive(Debug)]
+pub struct BookmarkRestore {
+    dry_run: bool,
+    delay: Duration,
+}
+
+impl Default for BookmarkRestore {
+    fn default() -> Self {
+        BookmarkRestore {
+            dry_run: false,
+            delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl BookmarkRestore {
+    /// Restores for real, sending at most one request per second.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only report what would be restored, without sending anything.
+    pub fn set_dry_run(self, dry_run: bool) -> Self {
+        BookmarkRestore { dry_run, ..self }
+    }
+
+    /// The minimum time between two requests.
+    pub fn set_delay(self, delay: Duration) -> Self {
+        BookmarkRestore { delay, ..self }
+    }
+
+    /// The requests a restore sends, in order.
+    pub fn requests(&self, backup: &BookmarkBackup) -> Vec<(u32, PixivRequest)> {
+        backup
+            .bookmarks
+            .iter()
+            .rev()
+            .map(|record| {
+                let request = PixivRequestBuilder::request_adding_bookmark(
+                    record.illust_id as usize,
+                    record.restrict,
+                    &record.tags,
+                );
+                (record.illust_id, request)
+            })
+            .collect()
+    }
+
+    pub fn restore(&self, client: &PixivClient, backup: &BookmarkBackup) -> RestoreReport {
+        self.restore_with(backup, |request| {
+            client.execute_with_auth(request)?.error_for_status()?;
+            Ok(())
+        })
+    }
+
+    /// Like `restore`, sending the requests through `send`. A failed bookmark does not stop
+    /// the others.
+    pub fn restore_with<F>(&self, backup: &BookmarkBackup, mut send: F) -> RestoreReport
+    where
+        F: FnMut(PixivRequest) -> Result<(), SyncError>,
+    {
+        let mut report = RestoreReport {
+            dry_run: self.dry_run,
+            ..RestoreReport::default()
+        };
+        let mut throttle = Throttle::new(self.delay);
+        for (illust_id, request) in self.requests(backup) {
+            if self.dry_run {
+                report.restored.push(illust_id);
+                continue;
+            }
+            throttle.wait();
+            match send(request) {
+                Ok(()) => report.restored.push(illust_id),
+                Err(e) => report.failed.push((illust_id, e)),
+            }
+        }
+        report
+    }
+}
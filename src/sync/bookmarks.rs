@@ -0,0 +1,329 @@
+use crate::download::downloader::Downloader;
+use crate::download::queue::DownloadQueue;
+use crate::download::report::DownloadReport;
+use crate::download::template::DownloadLayout;
+use crate::enums::{ImageSize, Visibility};
+use crate::errors::SyncError;
+use crate::pixiv::arg::user_bookmarks_illustration_request_arg::UserBookmarksIllustrationRequestArg;
+use crate::pixiv::client::PixivClient;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::request::PixivRequest;
+use crate::pixiv::request_builder::PixivRequestBuilder;
+use crate::pixiv::result::paginated::Paginated;
+use crate::pixiv::result::user_illustrations::UserIllustrations;
+use crate::sync::state;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// How many synced bookmarks in a row, in their synced order, end an incremental walk.
+const IN_ORDER_MATCHES: usize = 3;
+
+/// The bookmarks known after the last sync, newest first.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct BookmarkSyncState {
+    #[serde(default)]
+    public: Vec<u32>,
+    #[serde(default)]
+    private: Vec<u32>,
+    #[serde(default)]
+    synced_at: Option<String>,
+    #[serde(default)]
+    pending: Vec<(String, PathBuf)>,
+}
+
+impl BookmarkSyncState {
+    /// Ids of the illustrations bookmarked with `visibility`, newest bookmark first.
+    pub fn ids(&self, visibility: Visibility) -> &Vec<u32> {
+        match visibility {
+            Visibility::Public => &self.public,
+            Visibility::Private => &self.private,
+        }
+    }
+
+    fn ids_mut(&mut self, visibility: Visibility) -> &mut Vec<u32> {
+        match visibility {
+            Visibility::Public => &mut self.public,
+            Visibility::Private => &mut self.private,
+        }
+    }
+
+    /// When the last sync finished, as an RFC 3339 date.
+    pub fn synced_at(&self) -> Option<&String> {
+        self.synced_at.as_ref()
+    }
+
+    /// The `(url, path)` of the files that failed to download, retried by the next sync.
+    pub fn pending(&self) -> &Vec<(String, PathBuf)> {
+        &self.pending
+    }
+}
+
+/// What changed since the previous sync.
+#[derive(Debug, Default)]
+pub struct BookmarkSyncReport {
+    added: Vec<(Visibility, Illustration)>,
+    removed: Vec<(Visibility, u32)>,
+    pages: usize,
+    downloads: Option<DownloadReport>,
+}
+
+impl BookmarkSyncReport {
+    /// The newly bookmarked illustrations, newest first.
+    pub fn added(&self) -> &Vec<(Visibility, Illustration)> {
+        &self.added
+    }
+
+    /// Ids of the illustrations that are no longer bookmarked.
+    pub fn removed(&self) -> &Vec<(Visibility, u32)> {
+        &self.removed
+    }
+
+    /// How many pages of bookmarks were fetched.
+    pub fn pages(&self) -> usize {
+        self.pages
+    }
+
+    /// The downloads of the added illustrations, if `BookmarkSync::set_download` was used.
+    pub fn downloads(&self) -> Option<&DownloadReport> {
+        self.downloads.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Downloads every page of the added illustrations through `queue`.
+    pub fn download(
+        &self,
+        mut queue: DownloadQueue,
+        layout: &mut DownloadLayout,
+        size: ImageSize,
+    ) -> DownloadReport {
+        for (_, illustration) in &self.added {
+            queue.push_illustration_with_layout(illustration, layout, size);
+        }
+        queue.run(|_| {})
+    }
+}
+
+/// Mirrors the bookmarks of a user incrementally.
+///
+/// Bookmarks are walked newest first and the walk stops once a few illustrations in a row
+/// match the synced list in order, so a run only fetches the pages added since the previous
+/// one. A synced work met out of order was bookmarked again and is moved to the top.
+/// Bookmarks removed above the stopping point are reported as removals; use `set_full`
+/// from time to time to walk everything and catch removals further down.
+#[derive(Debug)]
+pub struct BookmarkSync {
+    user_id: u32,
+    visibilities: Vec<Visibility>,
+    full: bool,
+    state: BookmarkSyncState,
+    state_path: Option<PathBuf>,
+    download: Option<(DownloadLayout, ImageSize)>,
+}
+
+impl BookmarkSync {
+    /// Syncs the public and private bookmarks of `user_id`, keeping the state in memory.
+    pub fn new(user_id: u32) -> Self {
+        BookmarkSync {
+            user_id,
+            visibilities: vec![Visibility::Public, Visibility::Private],
+            full: false,
+            state: BookmarkSyncState::default(),
+            state_path: None,
+            download: None,
+        }
+    }
+
+    /// Loads the state of the previous runs from `path`, and saves it there after each sync.
+    pub fn open<P: AsRef<Path>>(user_id: u32, path: P) -> Result<Self, SyncError> {
+        let path = path.as_ref();
+        Ok(BookmarkSync {
+            state: state::load(path)?,
+            state_path: Some(path.to_path_buf()),
+            ..Self::new(user_id)
+        })
+    }
+
+    /// Which bookmark lists to sync. Private bookmarks are only visible to their owner.
+    pub fn set_visibilities(self, visibilities: Vec<Visibility>) -> Self {
+        BookmarkSync {
+            visibilities,
+            ..self
+        }
+    }
+
+    /// Walk every bookmark instead of stopping where the synced list starts.
+    pub fn set_full(self, full: bool) -> Self {
+        BookmarkSync { full, ..self }
+    }
+
+    /// Download the added illustrations where `layout` puts them.
+    pub fn set_download(self, layout: DownloadLayout, size: ImageSize) -> Self {
+        BookmarkSync {
+            download: Some((layout, size)),
+            ..self
+        }
+    }
+
+    pub fn user_id(&self) -> u32 {
+        self.user_id
+    }
+
+    pub fn state(&self) -> &BookmarkSyncState {
+        &self.state
+    }
+
+    /// Fetches the bookmarks added since the last sync, then downloads them if asked to.
+    ///
+    /// The state is saved once the downloads are done. Files that failed to download are kept
+    /// in it and downloaded again by the next sync.
+    pub fn sync(&mut self, client: &PixivClient) -> Result<BookmarkSyncReport, SyncError> {
+        self.sync_downloading_with(
+            |request| Ok(client.execute_json(request)?),
+            client.downloader(),
+        )
+    }
+
+    /// Like `sync`, fetching the pages through `fetch` and the files through `downloader`.
+    pub fn sync_downloading_with<F>(
+        &mut self,
+        fetch: F,
+        downloader: Downloader,
+    ) -> Result<BookmarkSyncReport, SyncError>
+    where
+        F: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+    {
+        let (mut state, mut report) = self.walk(fetch)?;
+        if let Some((layout, size)) = &mut self.download {
+            let mut queue = DownloadQueue::new(downloader);
+            queue.push_all(std::mem::take(&mut state.pending));
+            for (_, illustration) in &report.added {
+                queue.push_illustration_with_layout(illustration, layout, *size);
+            }
+            let downloads = queue.run(|_| {});
+            state.pending = downloads
+                .failed()
+                .map(|file| (file.url().clone(), file.path().clone()))
+                .collect();
+            report.downloads = Some(downloads);
+        }
+        self.commit(state)?;
+        Ok(report)
+    }
+
+    /// Like `sync`, fetching the pages through `fetch` and without downloading anything.
+    ///
+    /// The state is only updated, and saved, once every list was walked successfully.
+    pub fn sync_with<F>(&mut self, fetch: F) -> Result<BookmarkSyncReport, SyncError>
+    where
+        F: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+    {
+        let (state, report) = self.walk(fetch)?;
+        self.commit(state)?;
+        Ok(report)
+    }
+
+    /// Walks every list, returning the new state without saving it.
+    fn walk<F>(&self, mut fetch: F) -> Result<(BookmarkSyncState, BookmarkSyncReport), SyncError>
+    where
+        F: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+    {
+        let mut state = self.state.clone();
+        let mut report = BookmarkSyncReport::default();
+        for &visibility in &self.visibilities {
+            let mut args = UserBookmarksIllustrationRequestArg::new(self.user_id);
+            args.restrict = visibility;
+            let request = PixivRequestBuilder::request_user_bookmarks_illustration(args);
+            sync_list(
+                state.ids_mut(visibility),
+                visibility,
+                request,
+                self.full,
+                &mut fetch,
+                &mut report,
+            )?;
+        }
+
+        state.synced_at =
+            Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+        Ok((state, report))
+    }
+
+    fn commit(&mut self, state: BookmarkSyncState) -> Result<(), SyncError> {
+        if let Some(path) = &self.state_path {
+            state::save(&state, path)?;
+        }
+        self.state = state;
+        Ok(())
+    }
+}
+
+fn sync_list<F>(
+    ids: &mut Vec<u32>,
+    visibility: Visibility,
+    mut request: PixivRequest,
+    full: bool,
+    fetch: &mut F,
+    report: &mut BookmarkSyncReport,
+) -> Result<(), SyncError>
+where
+    F: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+{
+    let positions: HashMap<u32, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+    let mut walked = Vec::new();
+    // The synced bookmarks walked in a row in their synced order, as (first position, length).
+    let mut run: Option<(usize, usize)> = None;
+    let mut split = ids.len();
+    'pages: loop {
+        let page = fetch(request)?;
+        report.pages += 1;
+        let next = page.next_request();
+        for illustration in page {
+            let id = illustration.id();
+            walked.push(id);
+            match positions.get(&id) {
+                Some(&position) => {
+                    run = match run {
+                        Some((first, length)) if first + length == position => {
+                            Some((first, length + 1))
+                        }
+                        _ => Some((position, 1)),
+                    };
+                }
+                None => {
+                    run = None;
+                    report.added.push((visibility, illustration));
+                }
+            }
+            if let Some((first, length)) = run {
+                if !full && length == IN_ORDER_MATCHES {
+                    walked.truncate(walked.len() - length);
+                    split = first;
+                    break 'pages;
+                }
+            }
+        }
+        match next {
+            Some(next) => request = next,
+            None => break,
+        }
+    }
+
+    // Everything from the stopping point down was not walked and is kept as it was, except
+    // for the works that moved above it.
+    let mut untouched = ids.split_off(split);
+    let walked_set: HashSet<u32> = walked.iter().copied().collect();
+    untouched.retain(|id| !walked_set.contains(id));
+    report.removed.extend(
+        ids.iter()
+            .filter(|id| !walked_set.contains(id))
+            .map(|&id| (visibility, id)),
+    );
+    walked.extend(untouched);
+    *ids = walked;
+    Ok(())
+}
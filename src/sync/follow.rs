@@ -0,0 +1,242 @@
+use crate::enums::Visibility;
+use crate::errors::SyncError;
+use crate::pixiv::client::PixivClient;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::request::PixivRequest;
+use crate::pixiv::request_builder::PixivRequestBuilder;
+use crate::pixiv::result::paginated::Paginated;
+use crate::pixiv::result::user_illustrations::UserIllustrations;
+use crate::sync::state;
+use crate::sync::watch::{self, WatchControl};
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+/// The highest illustration id seen in each following feed.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct FollowWatchState {
+    #[serde(default)]
+    public: Option<u32>,
+    #[serde(default)]
+    private: Option<u32>,
+}
+
+impl FollowWatchState {
+    pub fn last_seen(&self, visibility: Visibility) -> Option<u32> {
+        match visibility {
+            Visibility::Public => self.public,
+            Visibility::Private => self.private,
+        }
+    }
+
+    fn last_seen_mut(&mut self, visibility: Visibility) -> &mut Option<u32> {
+        match visibility {
+            Visibility::Public => &mut self.public,
+            Visibility::Private => &mut self.private,
+        }
+    }
+}
+
+/// Sent by `FollowWatcher::watch`.
+#[derive(Debug)]
+pub enum FollowEvent {
+    /// A work posted since the previous poll by an artist followed with `visibility`.
+    NewWork {
+        visibility: Visibility,
+        illustration: Box<Illustration>,
+    },
+    /// A poll failed. The watch goes on and the next poll picks up the missed works.
+    PollFailed(SyncError),
+}
+
+/// Polls the works of the followed artists and reports the new ones.
+///
+/// New works are those with an id above the highest one seen by the previous poll. The
+/// first poll only records where the feed stands, unless `set_emit_initial` is used.
+#[derive(Debug)]
+pub struct FollowWatcher {
+    visibilities: Vec<Visibility>,
+    interval: Duration,
+    max_pages: usize,
+    emit_initial: bool,
+    state: FollowWatchState,
+    state_path: Option<PathBuf>,
+    control: WatchControl,
+}
+
+impl Default for FollowWatcher {
+    fn default() -> Self {
+        FollowWatcher {
+            visibilities: vec![Visibility::Public, Visibility::Private],
+            interval: Duration::from_secs(300),
+            max_pages: 5,
+            emit_initial: false,
+            state: FollowWatchState::default(),
+            state_path: None,
+            control: WatchControl::new(),
+        }
+    }
+}
+
+impl FollowWatcher {
+    /// Watches both feeds every five minutes, keeping the state in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the state left by a previous run from `path`, and saves it there after each poll.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SyncError> {
+        let path = path.as_ref();
+        Ok(FollowWatcher {
+            state: state::load(path)?,
+            state_path: Some(path.to_path_buf()),
+            ..Self::default()
+        })
+    }
+
+    pub fn set_visibilities(self, visibilities: Vec<Visibility>) -> Self {
+        FollowWatcher {
+            visibilities,
+            ..self
+        }
+    }
+
+    /// The time between the end of a poll and the start of the next one.
+    pub fn set_interval(self, interval: Duration) -> Self {
+        FollowWatcher { interval, ..self }
+    }
+
+    /// How many pages of the feed a poll walks at most, so that a watcher that was down
+    /// for long does not walk the whole feed.
+    pub fn set_max_pages(self, max_pages: usize) -> Self {
+        FollowWatcher {
+            max_pages: max_pages.max(1),
+            ..self
+        }
+    }
+
+    /// Report the works found by the very first poll instead of only recording them.
+    pub fn set_emit_initial(self, emit_initial: bool) -> Self {
+        FollowWatcher {
+            emit_initial,
+            ..self
+        }
+    }
+
+    pub fn state(&self) -> &FollowWatchState {
+        &self.state
+    }
+
+    /// Handle to stop `watch`.
+    pub fn control(&self) -> WatchControl {
+        self.control.clone()
+    }
+
+    /// Polls the feeds once, returning the new works oldest first.
+    pub fn poll(
+        &mut self,
+        client: &PixivClient,
+    ) -> Result<Vec<(Visibility, Illustration)>, SyncError> {
+        self.poll_with(|request| fetch(client, request))
+    }
+
+    /// Like `poll`, fetching the pages through `fetch`.
+    ///
+    /// The state is only updated, and saved, once every feed was fetched successfully.
+    pub fn poll_with<F>(
+        &mut self,
+        mut fetch: F,
+    ) -> Result<Vec<(Visibility, Illustration)>, SyncError>
+    where
+        F: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+    {
+        let mut state = self.state.clone();
+        let mut new_works = Vec::new();
+        for &visibility in &self.visibilities {
+            let last_seen = state.last_seen(visibility);
+            let mut request = Some(PixivRequestBuilder::request_illustration_following(
+                visibility,
+            ));
+            // A first poll that only records where the feed stands needs its newest work.
+            let max_pages = if last_seen.is_none() && !self.emit_initial {
+                1
+            } else {
+                self.max_pages
+            };
+            let mut works = Vec::new();
+            let mut pages = 0;
+            'pages: while let Some(current) = request.filter(|_| pages < max_pages) {
+                let page = fetch(current)?;
+                pages += 1;
+                request = page.next_request();
+                for illustration in page {
+                    if last_seen.is_some_and(|last_seen| illustration.id() <= last_seen) {
+                        break 'pages;
+                    }
+                    works.push(illustration);
+                }
+            }
+
+            if let Some(highest) = works.iter().map(|illustration| illustration.id()).max() {
+                *state.last_seen_mut(visibility) = Some(highest);
+            }
+            if last_seen.is_some() || self.emit_initial {
+                // The feed is newest first, works are reported in the order they were posted.
+                works.sort_by_key(|illustration| illustration.id());
+                new_works.extend(works.into_iter().map(|work| (visibility, work)));
+            }
+        }
+
+        if let Some(path) = &self.state_path {
+            state::save(&state, path)?;
+        }
+        self.state = state;
+        Ok(new_works)
+    }
+
+    /// Polls every interval until stopped through `control`, passing the events to `on_event`.
+    pub fn watch<F>(&mut self, client: &PixivClient, on_event: F)
+    where
+        F: FnMut(FollowEvent),
+    {
+        self.watch_with(|request| fetch(client, request), on_event)
+    }
+
+    /// Same as `watch`, sending the events through `sender`. The watch stops once the
+    /// receiving end hangs up.
+    pub fn watch_with_channel(&mut self, client: &PixivClient, sender: Sender<FollowEvent>) {
+        let control = self.control();
+        self.watch(client, |event| {
+            if sender.send(event).is_err() {
+                control.stop();
+            }
+        })
+    }
+
+    /// Like `watch`, fetching the pages through `fetch`.
+    pub fn watch_with<G, F>(&mut self, mut fetch: G, mut on_event: F)
+    where
+        G: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+        F: FnMut(FollowEvent),
+    {
+        let control = self.control();
+        let interval = self.interval;
+        watch::run(&control, interval, || match self.poll_with(&mut fetch) {
+            Ok(works) => {
+                for (visibility, illustration) in works {
+                    on_event(FollowEvent::NewWork {
+                        visibility,
+                        illustration: Box::new(illustration),
+                    });
+                }
+            }
+            Err(e) => on_event(FollowEvent::PollFailed(e)),
+        });
+    }
+}
+
+fn fetch(client: &PixivClient, request: PixivRequest) -> Result<UserIllustrations, SyncError> {
+    Ok(client.execute_json(request)?)
+}
@@ -0,0 +1,8 @@
+pub mod backup;
+pub mod bookmarks;
+pub mod follow;
+pub mod sink;
+pub mod state;
+pub mod throttle;
+pub mod watch;
+pub mod watchlist;
@@ -0,0 +1,108 @@
+use crate::errors::SyncError;
+use crate::sync::watchlist::WatchHit;
+
+use reqwest::blocking::Client;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Stdout, Write};
+use std::path::Path;
+
+/// Receives the works matched by a `Watchlist`.
+///
+/// Closures taking a `&WatchHit` are sinks too.
+pub trait WatchSink {
+    fn send(&mut self, hit: &WatchHit) -> Result<(), SyncError>;
+}
+
+impl<F> WatchSink for F
+where
+    F: FnMut(&WatchHit) -> Result<(), SyncError>,
+{
+    fn send(&mut self, hit: &WatchHit) -> Result<(), SyncError> {
+        self(hit)
+    }
+}
+
+/// Writes every hit as one line of JSON, see `WatchHit::to_json`.
+#[derive(Debug)]
+pub struct JsonLinesSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl JsonLinesSink<Stdout> {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+}
+
+impl JsonLinesSink<File> {
+    /// Appends to the file at `path`, creating it if needed.
+    pub fn append<P: AsRef<Path>>(path: P) -> Result<Self, SyncError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(sink_error)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl<W: Write> WatchSink for JsonLinesSink<W> {
+    fn send(&mut self, hit: &WatchHit) -> Result<(), SyncError> {
+        let mut line = serde_json::to_vec(&hit.to_json()).map_err(sink_error)?;
+        line.push(b'\n');
+        // One write per line, so that lines of several writers appending to a file don't mix.
+        self.writer.write_all(&line).map_err(sink_error)?;
+        self.writer.flush().map_err(sink_error)
+    }
+}
+
+/// Posts every hit as JSON to an HTTP endpoint, see `WatchHit::to_json`.
+#[derive(Debug, Clone)]
+pub struct WebhookSink {
+    client: Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new<T: ToString>(url: T) -> Self {
+        Self::with_client(Client::new(), url)
+    }
+
+    pub fn with_client<T: ToString>(client: Client, url: T) -> Self {
+        WebhookSink {
+            client,
+            url: url.to_string(),
+        }
+    }
+
+    pub fn url(&self) -> &String {
+        &self.url
+    }
+}
+
+impl WatchSink for WebhookSink {
+    fn send(&mut self, hit: &WatchHit) -> Result<(), SyncError> {
+        self.client
+            .post(&self.url)
+            .json(&hit.to_json())
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(sink_error)?;
+        Ok(())
+    }
+}
+
+fn sink_error<E: Error + Send + Sync + 'static>(e: E) -> SyncError {
+    SyncError::Sink(Box::new(e))
+}
@@ -0,0 +1,45 @@
+use crate::errors::SyncError;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Reads a JSON state file, or the default state if there is none yet.
+pub(crate) fn load<T, P>(path: P) -> Result<T, SyncError>
+where
+    T: DeserializeOwned + Default,
+    P: AsRef<Path>,
+{
+    match fs::read(path) {
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes a JSON state file. The file is replaced atomically, so a crash never loses the
+/// previous state.
+pub(crate) fn save<T, P>(state: &T, path: P) -> Result<(), SyncError>
+where
+    T: Serialize,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_vec_pretty(state)?;
+
+    let mut temporary = path.as_os_str().to_os_string();
+    temporary.push(".tmp");
+    let temporary = Path::new(&temporary);
+    let written = fs::write(temporary, json)
+        .and_then(|_| fs::File::open(temporary)?.sync_all())
+        .and_then(|_| fs::rename(temporary, path));
+    if written.is_err() {
+        let _ = fs::remove_file(temporary);
+    }
+    Ok(written?)
+}
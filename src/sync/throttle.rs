@@ -0,0 +1,25 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Spaces out requests so that consecutive ones are at least `delay` apart.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    delay: Duration,
+    last: Option<Instant>,
+}
+
+impl Throttle {
+    pub(crate) fn new(delay: Duration) -> Self {
+        Throttle { delay, last: None }
+    }
+
+    /// Sleeps until the next request may be sent.
+    pub(crate) fn wait(&mut self) {
+        if let Some(last) = self.last {
+            if let Some(remaining) = self.delay.checked_sub(last.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+        self.last = Some(Instant::now());
+    }
+}
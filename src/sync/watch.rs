@@ -0,0 +1,45 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Handle to stop a running watcher from another thread, or from its own callback.
+#[derive(Debug, Clone, Default)]
+pub struct WatchControl {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl WatchControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ends the watch once the current poll is done. A watcher waiting for its next poll
+    /// returns right away.
+    pub fn stop(&self) {
+        let (lock, condvar) = &*self.state;
+        *lock.lock().unwrap() = true;
+        condvar.notify_all();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        *self.state.0.lock().unwrap()
+    }
+
+    /// Waits for `duration` or until stopped, returns whether the watch should go on.
+    fn sleep(&self, duration: Duration) -> bool {
+        let (lock, condvar) = &*self.state;
+        let (stopped, _) = condvar
+            .wait_timeout_while(lock.lock().unwrap(), duration, |stopped| !*stopped)
+            .unwrap();
+        !*stopped
+    }
+}
+
+/// Calls `poll` every `interval` until `control` is stopped.
+pub(crate) fn run<F: FnMut()>(control: &WatchControl, interval: Duration, mut poll: F) {
+    while !control.is_stopped() {
+        poll();
+        if !control.sleep(interval) {
+            break;
+        }
+    }
+}
@@ -0,0 +1,564 @@
+use crate::enums::{ContentType, SearchTarget};
+use crate::errors::SyncError;
+use crate::pixiv::arg::illustration_search_request_arg::IllustrationSearchRequestArg;
+use crate::pixiv::arg::user_illustrations_request_arg::UserIllustrationsRequestArg;
+use crate::pixiv::client::PixivClient;
+use crate::pixiv::helper_structs::illustration::Illustration;
+use crate::pixiv::request::PixivRequest;
+use crate::pixiv::request_builder::PixivRequestBuilder;
+use crate::pixiv::result::paginated::Paginated;
+use crate::pixiv::result::user_illustrations::UserIllustrations;
+use crate::sync::sink::WatchSink;
+use crate::sync::state;
+use crate::sync::throttle::Throttle;
+use crate::sync::watch::{self, WatchControl};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where a `Watchlist` looks for new works.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchSource {
+    /// The works of a user, who does not need to be followed.
+    User {
+        user_id: u32,
+        content_type: ContentType,
+    },
+    /// The newest results of an illustration search.
+    Search { word: String, target: SearchTarget },
+}
+
+impl WatchSource {
+    /// The illustrations of a user.
+    pub fn user(user_id: u32) -> Self {
+        WatchSource::User {
+            user_id,
+            content_type: ContentType::Illustration,
+        }
+    }
+
+    /// The manga of a user.
+    pub fn user_manga(user_id: u32) -> Self {
+        WatchSource::User {
+            user_id,
+            content_type: ContentType::Manga,
+        }
+    }
+
+    /// Works with a tag containing `word`.
+    pub fn search<T: Into<String>>(word: T) -> Self {
+        Self::search_with(word, SearchTarget::TagsPartial)
+    }
+
+    pub fn search_with<T: Into<String>>(word: T, target: SearchTarget) -> Self {
+        WatchSource::Search {
+            word: word.into(),
+            target,
+        }
+    }
+
+    /// Identifies the source in the watchlist state and in the sinks.
+    pub fn key(&self) -> String {
+        match self {
+            WatchSource::User {
+                user_id,
+                content_type,
+            } => format!("user:{}:{}", content_type.as_str(), user_id),
+            WatchSource::Search { word, target } => {
+                format!("search:{}:{}", target.as_str(), word)
+            }
+        }
+    }
+
+    /// The request fetching the newest works of the source.
+    pub fn request(&self) -> PixivRequest {
+        match self {
+            WatchSource::User {
+                user_id,
+                content_type,
+            } => PixivRequestBuilder::request_user_illustrations(
+                UserIllustrationsRequestArg::new(*user_id).set_content_type(*content_type),
+            ),
+            WatchSource::Search { word, target } => {
+                PixivRequestBuilder::request_illustration_search(
+                    IllustrationSearchRequestArg::new(word.as_str()).set_search_target(*target),
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for WatchSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+/// Which works of a source are reported. Tags are compared case insensitively, against
+/// both their name and their translation.
+#[derive(Debug, Clone, Default)]
+pub struct WatchFilter {
+    include_tags: Vec<String>,
+    exclude_tags: Vec<String>,
+    max_sanity_level: Option<u32>,
+}
+
+impl WatchFilter {
+    /// Accepts every work.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only works with every included tag are reported.
+    pub fn add_include_tag<T: Into<String>>(mut self, tag: T) -> Self {
+        self.include_tags.push(tag.into().to_lowercase());
+        self
+    }
+
+    /// Works with any excluded tag are left out.
+    pub fn add_exclude_tag<T: Into<String>>(mut self, tag: T) -> Self {
+        self.exclude_tags.push(tag.into().to_lowercase());
+        self
+    }
+
+    /// Works above this `sanity_level` are left out.
+    pub fn set_max_sanity_level(mut self, value: u32) -> Self {
+        self.max_sanity_level = Some(value);
+        self
+    }
+
+    pub fn matches(&self, illustration: &Illustration) -> bool {
+        if self
+            .max_sanity_level
+            .is_some_and(|max| illustration.sanity_level() > max)
+        {
+            return false;
+        }
+        let tags: HashSet<String> = illustration
+            .tags()
+            .iter()
+            .flat_map(|tag| {
+                let translated = tag.translated_name().iter().flatten();
+                std::iter::once(tag.name()).chain(translated)
+            })
+            .map(|name| name.to_lowercase())
+            .collect();
+        self.include_tags.iter().all(|tag| tags.contains(tag))
+            && !self.exclude_tags.iter().any(|tag| tags.contains(tag))
+    }
+}
+
+/// A new work that passed the filter of its source.
+#[derive(Debug)]
+pub struct WatchHit {
+    source: WatchSource,
+    illustration: Illustration,
+}
+
+impl WatchHit {
+    pub fn source(&self) -> &WatchSource {
+        &self.source
+    }
+
+    pub fn illustration(&self) -> &Illustration {
+        &self.illustration
+    }
+
+    /// `{"source": <key>, "url": <artwork url>, "illustration": <illustration>}`, as sent
+    /// to the sinks.
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "source": self.source.key(),
+            "url": self.illustration.artwork_url(),
+            "illustration": self.illustration,
+        })
+    }
+}
+
+/// Something that went wrong during a poll. The other sources are not affected.
+#[derive(Debug)]
+pub enum WatchFailure {
+    /// The source could not be fetched. It is polled again from the same point next time.
+    Source(WatchSource, SyncError),
+    /// A sink did not take a hit. The next polls send it again to that sink only, up to
+    /// `set_max_retries` times.
+    Sink(SyncError),
+    /// A hit that some sink still did not take after the last retry. It is not sent again.
+    Dropped(Box<WatchHit>),
+    /// The state could not be saved.
+    State(SyncError),
+}
+
+/// The outcome of one poll of a `Watchlist`.
+#[derive(Debug, Default)]
+pub struct WatchlistReport {
+    hits: Vec<WatchHit>,
+    failures: Vec<WatchFailure>,
+}
+
+impl WatchlistReport {
+    /// The new matching works, oldest first within each source, whether or not every sink
+    /// took them. A work found by several sources is only reported for the first one.
+    pub fn hits(&self) -> &Vec<WatchHit> {
+        &self.hits
+    }
+
+    pub fn failures(&self) -> &Vec<WatchFailure> {
+        &self.failures
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn into_hits(self) -> Vec<WatchHit> {
+        self.hits
+    }
+}
+
+/// The highest illustration id seen for each source, keyed by `WatchSource::key`, and the
+/// hits that some sinks did not take yet.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct WatchlistState {
+    #[serde(default)]
+    last_seen: BTreeMap<String, u32>,
+    #[serde(default)]
+    pending: Vec<PendingHit>,
+}
+
+impl WatchlistState {
+    pub fn last_seen(&self, source: &WatchSource) -> Option<u32> {
+        self.last_seen.get(&source.key()).copied()
+    }
+
+    /// How many hits wait to be sent again to some sinks.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+/// A hit to send again to the sinks, by index, that did not take it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct PendingHit {
+    source: String,
+    illustration: Value,
+    sinks: Vec<usize>,
+    retries: usize,
+}
+
+/// Polls a list of users and searches and dispatches the new works that pass their
+/// filters to the sinks.
+///
+/// New works are those with an id above the highest one seen by the previous poll of
+/// the same source. The first poll of a source only records where it stands, unless
+/// `set_emit_initial` is used. A hit that a sink did not take is sent again to that sink
+/// alone by the next polls, so the other sinks don't get it twice.
+pub struct Watchlist {
+    entries: Vec<(WatchSource, WatchFilter)>,
+    sinks: Vec<Box<dyn WatchSink + Send>>,
+    interval: Duration,
+    delay: Duration,
+    max_pages: usize,
+    max_retries: usize,
+    emit_initial: bool,
+    state: WatchlistState,
+    state_path: Option<PathBuf>,
+    control: WatchControl,
+}
+
+impl fmt::Debug for Watchlist {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watchlist")
+            .field("entries", &self.entries)
+            .field("sinks", &self.sinks.len())
+            .field("interval", &self.interval)
+            .field("delay", &self.delay)
+            .field("max_pages", &self.max_pages)
+            .field("max_retries", &self.max_retries)
+            .field("emit_initial", &self.emit_initial)
+            .field("state", &self.state)
+            .field("state_path", &self.state_path)
+            .finish()
+    }
+}
+
+impl Default for Watchlist {
+    fn default() -> Self {
+        Watchlist {
+            entries: Vec::new(),
+            sinks: Vec::new(),
+            interval: Duration::from_secs(300),
+            delay: Duration::from_secs(1),
+            max_pages: 3,
+            max_retries: 3,
+            emit_initial: false,
+            state: WatchlistState::default(),
+            state_path: None,
+            control: WatchControl::new(),
+        }
+    }
+}
+
+impl Watchlist {
+    /// An empty watchlist polled every five minutes, keeping the state in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the state left by a previous run from `path`, and saves it there after each poll.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SyncError> {
+        let path = path.as_ref();
+        Ok(Watchlist {
+            state: state::load(path)?,
+            state_path: Some(path.to_path_buf()),
+            ..Self::default()
+        })
+    }
+
+    pub fn add(mut self, source: WatchSource, filter: WatchFilter) -> Self {
+        self.entries.push((source, filter));
+        self
+    }
+
+    /// Every hit goes to every sink, in the order they were added.
+    pub fn add_sink<S: WatchSink + Send + 'static>(mut self, sink: S) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// The time between the end of a poll and the start of the next one.
+    pub fn set_interval(self, interval: Duration) -> Self {
+        Watchlist { interval, ..self }
+    }
+
+    /// The minimum time between two requests of a poll.
+    pub fn set_delay(self, delay: Duration) -> Self {
+        Watchlist { delay, ..self }
+    }
+
+    /// How many pages of a source a poll walks at most.
+    pub fn set_max_pages(self, max_pages: usize) -> Self {
+        Watchlist {
+            max_pages: max_pages.max(1),
+            ..self
+        }
+    }
+
+    /// How many later polls send a hit again to a sink that did not take it, before it is
+    /// reported as `WatchFailure::Dropped`.
+    pub fn set_max_retries(self, max_retries: usize) -> Self {
+        Watchlist {
+            max_retries: max_retries.max(1),
+            ..self
+        }
+    }
+
+    /// Report the works found by the very first poll of a source instead of only
+    /// recording them.
+    pub fn set_emit_initial(self, emit_initial: bool) -> Self {
+        Watchlist {
+            emit_initial,
+            ..self
+        }
+    }
+
+    pub fn entries(&self) -> &Vec<(WatchSource, WatchFilter)> {
+        &self.entries
+    }
+
+    pub fn state(&self) -> &WatchlistState {
+        &self.state
+    }
+
+    /// Handle to stop `watch`.
+    pub fn control(&self) -> WatchControl {
+        self.control.clone()
+    }
+
+    /// Sends the pending hits again and polls every source once, sending the new hits to
+    /// the sinks.
+    pub fn poll(&mut self, client: &PixivClient) -> WatchlistReport {
+        self.poll_with(|request| fetch(client, request))
+    }
+
+    /// Like `poll`, fetching the pages through `fetch`.
+    pub fn poll_with<F>(&mut self, mut fetch: F) -> WatchlistReport
+    where
+        F: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+    {
+        let mut throttle = Throttle::new(self.delay);
+        let mut report = WatchlistReport::default();
+        self.retry_pending(&mut report);
+        let mut reported = HashSet::new();
+        for (source, filter) in &self.entries {
+            let last_seen = self.state.last_seen(source);
+            // A first poll that only records where the source stands needs its newest work.
+            let max_pages = if last_seen.is_none() && !self.emit_initial {
+                1
+            } else {
+                self.max_pages
+            };
+            let works = match fetch_new(
+                source.request(),
+                last_seen,
+                max_pages,
+                &mut throttle,
+                &mut fetch,
+            ) {
+                Ok(works) => works,
+                Err(e) => {
+                    report
+                        .failures
+                        .push(WatchFailure::Source(source.clone(), e));
+                    continue;
+                }
+            };
+
+            if let Some(highest) = works.iter().map(|work| work.id()).max() {
+                let entry = self.state.last_seen.entry(source.key()).or_insert(highest);
+                *entry = highest.max(*entry);
+            }
+            if last_seen.is_some() || self.emit_initial {
+                for illustration in works.into_iter().rev() {
+                    if !filter.matches(&illustration) || !reported.insert(illustration.id()) {
+                        continue;
+                    }
+                    let hit = WatchHit {
+                        source: source.clone(),
+                        illustration,
+                    };
+                    let all = 0..self.sinks.len();
+                    let failed = send(&mut self.sinks, &hit, all, &mut report.failures);
+                    if !failed.is_empty() {
+                        self.state.pending.push(PendingHit {
+                            source: source.key(),
+                            illustration: serde_json::json!(hit.illustration),
+                            sinks: failed,
+                            retries: 0,
+                        });
+                    }
+                    report.hits.push(hit);
+                }
+            }
+        }
+
+        if let Some(path) = &self.state_path {
+            if let Err(e) = state::save(&self.state, path) {
+                report.failures.push(WatchFailure::State(e));
+            }
+        }
+        report
+    }
+
+    /// Sends the pending hits to the sinks that did not take them, oldest first. Hits of
+    /// sources that were removed from the watchlist are forgotten.
+    fn retry_pending(&mut self, report: &mut WatchlistReport) {
+        for mut pending in std::mem::take(&mut self.state.pending) {
+            let source = self
+                .entries
+                .iter()
+                .map(|(source, _)| source)
+                .find(|source| source.key() == pending.source);
+            let illustration = serde_json::from_value(pending.illustration.clone()).ok();
+            let (Some(source), Some(illustration)) = (source, illustration) else {
+                continue;
+            };
+            let hit = WatchHit {
+                source: source.clone(),
+                illustration,
+            };
+            let sinks = std::mem::take(&mut pending.sinks);
+            pending.sinks = send(&mut self.sinks, &hit, sinks, &mut report.failures);
+            pending.retries += 1;
+            if pending.sinks.is_empty() {
+                continue;
+            }
+            if pending.retries < self.max_retries {
+                self.state.pending.push(pending);
+            } else {
+                report.failures.push(WatchFailure::Dropped(Box::new(hit)));
+            }
+        }
+    }
+
+    /// Polls every interval until stopped through `control`, passing each report to
+    /// `on_poll` once its hits were dispatched.
+    pub fn watch<F>(&mut self, client: &PixivClient, on_poll: F)
+    where
+        F: FnMut(&WatchlistReport),
+    {
+        self.watch_with(|request| fetch(client, request), on_poll)
+    }
+
+    /// Like `watch`, fetching the pages through `fetch`.
+    pub fn watch_with<G, F>(&mut self, mut fetch: G, mut on_poll: F)
+    where
+        G: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+        F: FnMut(&WatchlistReport),
+    {
+        let control = self.control();
+        let interval = self.interval;
+        watch::run(&control, interval, || on_poll(&self.poll_with(&mut fetch)));
+    }
+}
+
+/// Sends `hit` to the sinks at `indices`, returning those that failed. Indices past the
+/// last sink are skipped.
+fn send<I>(
+    sinks: &mut [Box<dyn WatchSink + Send>],
+    hit: &WatchHit,
+    indices: I,
+    failures: &mut Vec<WatchFailure>,
+) -> Vec<usize>
+where
+    I: IntoIterator<Item = usize>,
+{
+    let mut failed = Vec::new();
+    for i in indices {
+        let Some(sink) = sinks.get_mut(i) else {
+            continue;
+        };
+        if let Err(e) = sink.send(hit) {
+            failures.push(WatchFailure::Sink(e));
+            failed.push(i);
+        }
+    }
+    failed
+}
+
+/// Fetches the works above `last_seen`, newest first.
+fn fetch_new<F>(
+    request: PixivRequest,
+    last_seen: Option<u32>,
+    max_pages: usize,
+    throttle: &mut Throttle,
+    fetch: &mut F,
+) -> Result<Vec<Illustration>, SyncError>
+where
+    F: FnMut(PixivRequest) -> Result<UserIllustrations, SyncError>,
+{
+    let mut works = Vec::new();
+    let mut request = Some(request);
+    let mut pages = 0;
+    while let Some(current) = request.filter(|_| pages < max_pages) {
+        throttle.wait();
+        let page = fetch(current)?;
+        pages += 1;
+        request = page.next_request();
+        for illustration in page {
+            if last_seen.is_some_and(|last_seen| illustration.id() <= last_seen) {
+                return Ok(works);
+            }
+            works.push(illustration);
+        }
+    }
+    Ok(works)
+}
+
+fn fetch(client: &PixivClient, request: PixivRequest) -> Result<UserIllustrations, SyncError> {
+    Ok(client.execute_json(request)?)
+}
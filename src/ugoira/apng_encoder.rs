@@ -0,0 +1,28 @@
+use crate::errors::ConversionError;
+use crate::ugoira::frames::Frame;
+
+use std::io::Write;
+
+/// Encodes the frames as an animated PNG. `loop_count` of 0 loops forever.
+/// Every frame must have the size of the first one.
+pub fn encode<W>(frames: &[Frame], loop_count: u16, writer: W) -> Result<(), ConversionError>
+where
+    W: Write,
+{
+    let first = frames.first().ok_or(ConversionError::NoFrames)?;
+
+    let mut encoder = png::Encoder::new(writer, first.width(), first.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, loop_count as u32)?;
+
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        // Delays are a fraction of a second, milliseconds fit as long as they fit in a u16.
+        let delay = u16::try_from(frame.delay()).unwrap_or(u16::MAX);
+        writer.set_frame_delay(delay, 1000)?;
+        writer.write_image_data(frame.image().as_raw())?;
+    }
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use crate::enums::AnimationFormat;
+use crate::errors::ConversionError;
+use crate::pixiv::helper_structs::ugoira_metadata::UgoiraMetadata;
+use crate::ugoira::frames::read_frames;
+use crate::ugoira::{apng_encoder, gif_encoder, webp_encoder};
+
+use std::io::{Read, Seek, Write};
+
+/// ConversionOptions (Ugoira Conversion Options Builder).
+/// Builds the options used by `convert`.
+#[derive(Debug, Clone)]
+pub struct ConversionOptions {
+    format: AnimationFormat,
+    size: Option<(u32, u32)>,
+    loop_count: u16,
+    palette_quality: i32,
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        ConversionOptions {
+            format: AnimationFormat::Gif,
+            size: None,
+            loop_count: 0,
+            palette_quality: 10,
+        }
+    }
+}
+
+impl ConversionOptions {
+    pub fn new(format: AnimationFormat) -> Self {
+        ConversionOptions {
+            format,
+            ..Default::default()
+        }
+    }
+
+    pub fn format(&self) -> AnimationFormat {
+        self.format
+    }
+
+    pub fn set_format(mut self, value: AnimationFormat) -> Self {
+        self.format = value;
+        self
+    }
+
+    /// Scales the frames to fit in `width`x`height`, keeping their aspect ratio.
+    pub fn set_size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Number of times the animation is played, 0 (the default) loops forever.
+    pub fn set_loop_count(mut self, value: u16) -> Self {
+        self.loop_count = value;
+        self
+    }
+
+    /// Quality of the palettes computed for GIF, from 1 (best, slowest) to 30 (worst, fastest).
+    /// Ignored by the other formats, which are lossless.
+    pub fn set_palette_quality(mut self, value: i32) -> Self {
+        self.palette_quality = value.clamp(1, 30);
+        self
+    }
+}
+
+/// Converts the frame zip of an ugoira into an animation, using the delays of `metadata`.
+/// Returns the writer once the animation has been written.
+pub fn convert<R, W>(
+    zip: R,
+    metadata: &UgoiraMetadata,
+    options: &ConversionOptions,
+    mut writer: W,
+) -> Result<W, ConversionError>
+where
+    R: Read + Seek,
+    W: Write,
+{
+    let frames = read_frames(zip, metadata, options.size)?;
+
+    match options.format {
+        AnimationFormat::Gif => gif_encoder::encode(
+            &frames,
+            options.loop_count,
+            options.palette_quality,
+            &mut writer,
+        )?,
+        AnimationFormat::Apng => apng_encoder::encode(&frames, options.loop_count, &mut writer)?,
+        AnimationFormat::WebP => webp_encoder::encode(&frames, options.loop_count, &mut writer)?,
+    }
+
+    Ok(writer)
+}
@@ -0,0 +1,87 @@
+use crate::errors::ConversionError;
+use crate::pixiv::helper_structs::ugoira_metadata::UgoiraMetadata;
+
+use image::imageops::FilterType;
+use image::RgbaImage;
+use std::io::{Read, Seek};
+
+/// A decoded frame of an ugoira.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    image: RgbaImage,
+    delay: u32,
+}
+
+impl Frame {
+    pub fn new(image: RgbaImage, delay: u32) -> Self {
+        Frame { image, delay }
+    }
+
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Delay in milliseconds before the next frame.
+    pub fn delay(&self) -> u32 {
+        self.delay
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+}
+
+/// Decodes the frames listed in `metadata` from the zip fetched with `UgoiraMetadata::fetch_zip`.
+/// When `size` is set, frames are scaled to fit in it, keeping their aspect ratio.
+pub fn read_frames<R>(
+    zip: R,
+    metadata: &UgoiraMetadata,
+    size: Option<(u32, u32)>,
+) -> Result<Vec<Frame>, ConversionError>
+where
+    R: Read + Seek,
+{
+    let mut archive = zip::ZipArchive::new(zip)?;
+    let mut frames = Vec::with_capacity(metadata.frames().len());
+
+    for frame in metadata.frames() {
+        let mut data = Vec::new();
+        match archive.by_name(frame.file()) {
+            Ok(mut file) => file.read_to_end(&mut data)?,
+            Err(zip::result::ZipError::FileNotFound) => {
+                return Err(ConversionError::MissingFrame(frame.file().clone()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut image = image::load_from_memory(&data)?.to_rgba8();
+        if let Some((width, height)) = size {
+            let (width, height) = fit(image.width(), image.height(), width, height);
+            if (width, height) != image.dimensions() {
+                image = image::imageops::resize(&image, width, height, FilterType::Lanczos3);
+            }
+        }
+        frames.push(Frame::new(image, frame.delay()));
+    }
+
+    if frames.is_empty() {
+        return Err(ConversionError::NoFrames);
+    }
+    Ok(frames)
+}
+
+/// Largest size with the aspect ratio of `width`x`height` fitting in `max_width`x`max_height`.
+fn fit(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    let ratio = f64::min(
+        max_width as f64 / width as f64,
+        max_height as f64 / height as f64,
+    );
+    (
+        ((width as f64 * ratio).round() as u32).max(1),
+        ((height as f64 * ratio).round() as u32).max(1),
+    )
+}
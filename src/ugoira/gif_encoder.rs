@@ -0,0 +1,46 @@
+use crate::errors::ConversionError;
+use crate::ugoira::frames::Frame;
+
+use std::io::Write;
+
+/// Encodes the frames as an animated GIF, each frame with its own palette.
+/// `loop_count` of 0 loops forever. `speed` goes from 1 (best palette) to 30 (fastest).
+pub fn encode<W>(
+    frames: &[Frame],
+    loop_count: u16,
+    speed: i32,
+    writer: W,
+) -> Result<(), ConversionError>
+where
+    W: Write,
+{
+    let first = frames.first().ok_or(ConversionError::NoFrames)?;
+    let (width, height) = dimensions(first)?;
+
+    let mut encoder = gif::Encoder::new(writer, width, height, &[])?;
+    encoder.set_repeat(match loop_count {
+        0 => gif::Repeat::Infinite,
+        n => gif::Repeat::Finite(n),
+    })?;
+
+    for frame in frames {
+        let (width, height) = dimensions(frame)?;
+        let mut pixels = frame.image().as_raw().clone();
+        let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, speed);
+        // Delays are in hundredths of a second, browsers slow down anything under 2.
+        gif_frame.delay = u16::try_from(frame.delay().div_ceil(10).max(2)).unwrap_or(u16::MAX);
+        encoder.write_frame(&gif_frame)?;
+    }
+
+    encoder.into_inner()?;
+    Ok(())
+}
+
+fn dimensions(frame: &Frame) -> Result<(u16, u16), ConversionError> {
+    match (u16::try_from(frame.width()), u16::try_from(frame.height())) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => Err(ConversionError::Encoding(
+            String::from("frame is too large for a GIF").into(),
+        )),
+    }
+}
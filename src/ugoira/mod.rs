@@ -0,0 +1,5 @@
+pub mod apng_encoder;
+pub mod convert;
+pub mod frames;
+pub mod gif_encoder;
+pub mod webp_encoder;
@@ -0,0 +1,88 @@
+use crate::errors::ConversionError;
+use crate::ugoira::frames::Frame;
+
+use std::io::Write;
+
+/// Encodes the frames as a lossless animated WebP. `loop_count` of 0 loops forever.
+///
+/// `image-webp` only writes still images, so each frame is encoded on its own and its `VP8L`
+/// bitstream is wrapped in the `ANMF` chunks of an animated container.
+pub fn encode<W>(frames: &[Frame], loop_count: u16, mut writer: W) -> Result<(), ConversionError>
+where
+    W: Write,
+{
+    let first = frames.first().ok_or(ConversionError::NoFrames)?;
+    let (canvas_width, canvas_height) = (first.width(), first.height());
+
+    let mut body = Vec::new();
+
+    let mut vp8x = vec![0x02 | 0x10, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(canvas_width - 1)?);
+    vp8x.extend_from_slice(&u24(canvas_height - 1)?);
+    write_chunk(&mut body, b"VP8X", &vp8x);
+
+    let mut anim = vec![0, 0, 0, 0];
+    anim.extend_from_slice(&loop_count.to_le_bytes());
+    write_chunk(&mut body, b"ANIM", &anim);
+
+    for frame in frames {
+        let mut still = Vec::new();
+        image_webp::WebPEncoder::new(&mut still).encode(
+            frame.image().as_raw(),
+            frame.width(),
+            frame.height(),
+            image_webp::ColorType::Rgba8,
+        )?;
+
+        // Frame placement, size and duration, followed by the image chunks.
+        let mut anmf = Vec::new();
+        anmf.extend_from_slice(&u24(0)?);
+        anmf.extend_from_slice(&u24(0)?);
+        anmf.extend_from_slice(&u24(frame.width() - 1)?);
+        anmf.extend_from_slice(&u24(frame.height() - 1)?);
+        anmf.extend_from_slice(&u24(frame.delay().min(0xFF_FFFF))?);
+        // Frames are opaque and cover the whole canvas: no blending, no disposal.
+        anmf.push(0x02);
+        anmf.extend_from_slice(image_chunks(&still)?);
+        write_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(body.len() as u32 + 4).to_le_bytes())?;
+    writer.write_all(b"WEBP")?;
+    writer.write_all(&body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Strips the `RIFF` header of a still WebP, keeping the image chunks.
+fn image_chunks(webp: &[u8]) -> Result<&[u8], ConversionError> {
+    match webp.get(12..) {
+        Some(chunks) if webp.starts_with(b"RIFF") => Ok(chunks),
+        _ => Err(ConversionError::Encoding(
+            String::from("invalid WebP frame").into(),
+        )),
+    }
+}
+
+fn write_chunk(output: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(fourcc);
+    output.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    output.extend_from_slice(data);
+    // Chunks are padded to an even size.
+    if data.len() % 2 == 1 {
+        output.push(0);
+    }
+}
+
+fn u24(value: u32) -> Result<[u8; 3], ConversionError> {
+    match value {
+        0..=0xFF_FFFF => {
+            let bytes = value.to_le_bytes();
+            Ok([bytes[0], bytes[1], bytes[2]])
+        }
+        _ => Err(ConversionError::Encoding(
+            String::from("frame is too large for a WebP").into(),
+        )),
+    }
+}
@@ -18,6 +18,58 @@ pub fn comma_delimited<T: Display + ?Sized, B: Borrow<T>, I: IntoIterator<Item =
     ret
 }
 
+/// Link to the pixiv page of an illustration.
+pub fn artwork_url(illust_id: u32) -> String {
+    format!("https://www.pixiv.net/artworks/{}", illust_id)
+}
+
+/// Converts the HTML of a pixiv caption into plain text: `<br />` becomes a line break, other
+/// tags are dropped (links keep their text) and entities are decoded.
+pub fn caption_to_text(caption: &str) -> String {
+    let mut text = String::with_capacity(caption.len());
+    let mut rest = caption;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        match rest[start..].find('>') {
+            Some(end) => {
+                let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+                if tag.starts_with("br") {
+                    text.push('\n');
+                }
+                rest = &rest[start + end + 1..];
+            }
+            None => {
+                text.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    text.push_str(rest);
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#039;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Escapes the characters with a special meaning in html and xml.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 //const DEFAULT_CAPACITY: usize = 4096;
 const SMOL_CAPCITY: usize = 64;
 
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{manga, temp_dir};
+use pixieve_rs::download::archive::{ArchiveKey, DownloadArchive};
+use pixieve_rs::download::downloader::Downloader;
+use pixieve_rs::download::queue::DownloadQueue;
+use pixieve_rs::enums::ImageSize;
+
+use std::sync::Arc;
+
+#[test]
+fn test_archive_key_format() {
+    let key = ArchiveKey::new(75523989, 1, ImageSize::Original);
+
+    assert_eq!(key.to_string(), "75523989_p1 original");
+    assert_eq!("75523989_p1 original".parse::<ArchiveKey>().unwrap(), key);
+    assert!("75523989 original".parse::<ArchiveKey>().is_err());
+    assert!("75523989_p1 huge".parse::<ArchiveKey>().is_err());
+}
+
+#[test]
+fn test_archive_persists() {
+    let dir = temp_dir("archive");
+    let path = dir.join("archive.txt");
+    let key = ArchiveKey::new(66024340, 0, ImageSize::Large);
+
+    let archive = DownloadArchive::open(&path).unwrap();
+    assert!(archive.insert(key).unwrap());
+    assert!(!archive.insert(key).unwrap());
+    drop(archive);
+
+    let archive = DownloadArchive::open(&path).unwrap();
+    assert!(archive.contains(&key));
+    assert_eq!(archive.len(), 1);
+}
+
+#[test]
+fn test_archive_export_import() {
+    let archive = DownloadArchive::in_memory();
+    archive
+        .extend(vec![
+            ArchiveKey::new(2, 0, ImageSize::Original),
+            ArchiveKey::new(1, 3, ImageSize::Medium),
+        ])
+        .unwrap();
+
+    let exported = archive.export(Vec::new()).unwrap();
+    assert_eq!(
+        String::from_utf8(exported.clone()).unwrap(),
+        "1_p3 medium\n2_p0 original\n"
+    );
+
+    let copy = DownloadArchive::in_memory();
+    let input = format!("# exported\n\n{}", String::from_utf8(exported).unwrap());
+    assert_eq!(copy.import(input.as_bytes()).unwrap(), 2);
+    assert_eq!(copy.keys(), archive.keys());
+}
+
+#[test]
+fn test_archive_import_directory() {
+    let dir = temp_dir("archive-directory");
+    std::fs::create_dir_all(dir.join("Artist")).unwrap();
+    for name in [
+        "Artist/75523989_p0.png",
+        "Artist/75523989_p1_master1200.jpg",
+        "66024340_p0.jpg.part",
+        "notes.txt",
+    ] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+
+    let archive = DownloadArchive::in_memory();
+    assert_eq!(
+        archive.import_directory(&dir, ImageSize::Original).unwrap(),
+        2
+    );
+    assert!(archive.contains(&ArchiveKey::new(75523989, 1, ImageSize::Original)));
+}
+
+#[test]
+fn test_queue_skips_archived_pages() {
+    let manga = manga();
+    let archive = Arc::new(DownloadArchive::in_memory());
+    archive
+        .insert(ArchiveKey::new(75523989, 0, ImageSize::Original))
+        .unwrap();
+
+    let mut queue =
+        DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new())).set_archive(archive);
+
+    assert_eq!(
+        queue.push_illustration(&manga, std::path::Path::new("."), ImageSize::Original),
+        vec![0]
+    );
+    assert_eq!(
+        queue.push_illustration(&manga, std::path::Path::new("."), ImageSize::Large),
+        vec![1, 2]
+    );
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use common::{illustration_json, manga};
+use pixieve_rs::enums::{BackupFormat, Visibility};
+use pixieve_rs::errors::SyncError;
+use pixieve_rs::pixiv::request::PixivRequest;
+use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
+use pixieve_rs::sync::backup::{BookmarkBackup, BookmarkExporter, BookmarkRecord, BookmarkRestore};
+use std::time::Duration;
+
+fn backup() -> BookmarkBackup {
+    let mut backup = BookmarkBackup::new(6996493);
+    backup.push(
+        BookmarkRecord::new(
+            75523989,
+            Visibility::Private,
+            vec!["Fate/GO".to_string(), "漫画".to_string()],
+        )
+        .set_illustration(manga()),
+    );
+    backup.push(BookmarkRecord::new(
+        66024340,
+        Visibility::Public,
+        Vec::new(),
+    ));
+    backup
+}
+
+#[test]
+fn test_adding_bookmark_request_sends_tags() {
+    let request = PixivRequestBuilder::request_adding_bookmark(1, Visibility::Private, ["a", "b"]);
+    assert_eq!(request.form.get("restrict").unwrap(), "private");
+    assert_eq!(request.form.get("tags[]").unwrap(), "a b");
+
+    let request = PixivRequestBuilder::request_adding_bookmark(1, Visibility::Public, [""; 0]);
+    assert!(!request.form.contains_key("tags[]"));
+}
+
+#[test]
+fn test_json_backup_round_trips() {
+    let json = backup().to_string(BackupFormat::Json).unwrap();
+    let parsed = BookmarkBackup::parse(&json, BackupFormat::Json).unwrap();
+
+    assert_eq!(parsed.user_id(), Some(6996493));
+    assert!(parsed.exported_at().is_some());
+    assert_eq!(parsed.len(), 2);
+    let record = &parsed.bookmarks()[0];
+    assert_eq!(record.restrict(), Visibility::Private);
+    assert_eq!(
+        record.tags(),
+        &vec!["Fate/GO".to_string(), "漫画".to_string()]
+    );
+    assert_eq!(record.illustration().unwrap().title(), "Manga/Test: 1");
+    assert!(parsed.bookmarks()[1].illustration().is_none());
+}
+
+#[test]
+fn test_csv_backup_round_trips() {
+    let csv = backup().to_string(BackupFormat::Csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("illust_id,restrict,tags,title,user_id,user_name,create_date,total_bookmarks,url")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("75523989,private,Fate/GO 漫画,Manga/Test: 1,6996493,Artist,2019-07-14T00:00:00+09:00,100,https://www.pixiv.net/artworks/75523989")
+    );
+    assert_eq!(lines.next(), Some("66024340,public,,,,,,,"));
+
+    let parsed = BookmarkBackup::parse(&csv, BackupFormat::Csv).unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed.bookmarks()[0].illust_id(), 75523989);
+    assert_eq!(parsed.bookmarks()[0].tags().len(), 2);
+    assert_eq!(parsed.bookmarks()[1].restrict(), Visibility::Public);
+    assert!(parsed.bookmarks()[1].tags().is_empty());
+}
+
+#[test]
+fn test_csv_backup_quoting() {
+    let csv = "restrict,title,illust_id,tags\n\
+               public,\"a, \"\"quoted\"\"\nline\",1,x y\n\
+               \n\
+               private,,2,\n";
+    let parsed = BookmarkBackup::parse(csv, BackupFormat::Csv).unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(
+        parsed.bookmarks()[0].tags(),
+        &vec!["x".to_string(), "y".to_string()]
+    );
+    assert_eq!(parsed.bookmarks()[1].restrict(), Visibility::Private);
+
+    let invalid = BookmarkBackup::parse("illust_id,restrict\nabc,public\n", BackupFormat::Csv);
+    assert!(matches!(invalid, Err(SyncError::InvalidBackup(_))));
+    let invalid = BookmarkBackup::parse("illust_id\n1\n", BackupFormat::Csv);
+    assert!(matches!(invalid, Err(SyncError::InvalidBackup(_))));
+}
+
+#[test]
+fn test_export_fetches_pages_and_tags() {
+    let fetch = |request: PixivRequest| -> Result<serde_json::Value, SyncError> {
+        let query = request.url.query().unwrap_or_default().to_string();
+        Ok(match request.url.path() {
+            "/v2/illust/bookmark/detail" => serde_json::json!({
+                "bookmark_detail": {
+                    "is_bookmarked": true,
+                    "restrict": "public",
+                    "tags": [
+                        { "name": "registered", "is_registered": true },
+                        { "name": "suggested", "is_registered": false }
+                    ]
+                }
+            }),
+            _ if query.contains("restrict=private") => {
+                serde_json::json!({ "illusts": [], "next_url": null })
+            }
+            _ if query.contains("max_bookmark_id") => {
+                serde_json::json!({ "illusts": [illustration_json(2)], "next_url": null })
+            }
+            _ => {
+                serde_json::json!({
+                    "illusts": [illustration_json(3)],
+                    "next_url": "https://app-api.pixiv.net/v1/user/bookmarks/illust?user_id=1&restrict=public&max_bookmark_id=9"
+                })
+            }
+        })
+    };
+
+    let backup = BookmarkExporter::new(1)
+        .set_delay(Duration::ZERO)
+        .export_with(fetch)
+        .unwrap();
+    assert_eq!(backup.user_id(), Some(1));
+    let ids: Vec<u32> = backup.bookmarks().iter().map(|b| b.illust_id()).collect();
+    assert_eq!(ids, vec![3, 2]);
+    assert_eq!(
+        backup.bookmarks()[0].tags(),
+        &vec!["registered".to_string()]
+    );
+    assert_eq!(backup.bookmarks()[1].illustration().unwrap().id(), 2);
+}
+
+#[test]
+fn test_restore_dry_run_sends_nothing() {
+    let mut sent = 0;
+    let report = BookmarkRestore::new()
+        .set_dry_run(true)
+        .restore_with(&backup(), |_| {
+            sent += 1;
+            Ok(())
+        });
+
+    assert_eq!(sent, 0);
+    assert!(report.is_dry_run());
+    assert_eq!(report.restored(), &vec![66024340, 75523989]);
+}
+
+#[test]
+fn test_restore_oldest_first_with_tags() {
+    let mut forms = Vec::new();
+    let report = BookmarkRestore::new()
+        .set_delay(Duration::ZERO)
+        .restore_with(&backup(), |request| {
+            forms.push(request.form.clone());
+            if request.form.get("illust_id").unwrap() == "66024340" {
+                return Err(SyncError::Io(std::io::ErrorKind::Other.into()));
+            }
+            Ok(())
+        });
+
+    assert_eq!(forms.len(), 2);
+    assert_eq!(forms[1].get("illust_id").unwrap(), "75523989");
+    assert_eq!(forms[1].get("restrict").unwrap(), "private");
+    assert_eq!(forms[1].get("tags[]").unwrap(), "Fate/GO 漫画");
+    assert_eq!(report.restored(), &vec![75523989]);
+    assert_eq!(report.failed()[0].0, 66024340);
+    assert!(!report.is_success());
+}
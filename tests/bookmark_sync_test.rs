@@ -0,0 +1,226 @@
+mod common;
+
+use common::{illustration_json, paginate, response, serve, temp_dir};
+use pixieve_rs::download::downloader::Downloader;
+use pixieve_rs::download::template::{DownloadLayout, PathTemplate};
+use pixieve_rs::enums::{ImageSize, Visibility};
+use pixieve_rs::errors::SyncError;
+use pixieve_rs::pixiv::arg::user_bookmarks_illustration_request_arg::UserBookmarksIllustrationRequestArg;
+use pixieve_rs::pixiv::request::PixivRequest;
+use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
+use pixieve_rs::pixiv::result::user_illustrations::UserIllustrations;
+use pixieve_rs::sync::bookmarks::BookmarkSync;
+
+/// Serves the bookmark lists two per page, using `max_bookmark_id` as the page offset.
+fn bookmark_pages(
+    public: &[u32],
+    private: &[u32],
+) -> impl FnMut(PixivRequest) -> Result<UserIllustrations, SyncError> {
+    let public = public.to_vec();
+    let private = private.to_vec();
+    move |request| {
+        let (ids, restrict) = if request
+            .url
+            .query()
+            .unwrap_or_default()
+            .contains("restrict=private")
+        {
+            (&private, "private")
+        } else {
+            (&public, "public")
+        };
+        let next_url = format!(
+            "https://app-api.pixiv.net/v1/user/bookmarks/illust?restrict={}",
+            restrict
+        );
+        Ok(paginate(ids, &request, "max_bookmark_id", &next_url)?)
+    }
+}
+
+fn added(report: &pixieve_rs::sync::bookmarks::BookmarkSyncReport) -> Vec<u32> {
+    report
+        .added()
+        .iter()
+        .map(|(_, illust)| illust.id())
+        .collect()
+}
+
+#[test]
+fn test_user_bookmarks_request() {
+    let mut args = UserBookmarksIllustrationRequestArg::new(6996493);
+    args.restrict = Visibility::Private;
+    args.max_bookmark_id = Some(123);
+    let request = PixivRequestBuilder::request_user_bookmarks_illustration(args);
+
+    assert_eq!(request.url.path(), "/v1/user/bookmarks/illust");
+    let query = request.url.query().unwrap();
+    let mut pairs = query.split('&').collect::<Vec<_>>();
+    pairs.sort();
+    assert_eq!(
+        pairs,
+        vec!["max_bookmark_id=123", "restrict=private", "user_id=6996493"]
+    );
+}
+
+#[test]
+fn test_sync_stops_at_synced_bookmark() {
+    let mut sync = BookmarkSync::new(6996493);
+    let report = sync
+        .sync_with(bookmark_pages(&[5, 4, 3, 2, 1], &[9]))
+        .unwrap();
+    assert_eq!(added(&report), vec![5, 4, 3, 2, 1, 9]);
+    assert_eq!(report.added()[5].0, Visibility::Private);
+    assert_eq!(report.pages(), 4);
+    assert!(sync.state().synced_at().is_some());
+
+    // 7 and 6 were bookmarked and 5 removed since.
+    let report = sync
+        .sync_with(bookmark_pages(&[7, 6, 4, 3, 2, 1], &[9]))
+        .unwrap();
+    assert_eq!(added(&report), vec![7, 6]);
+    assert_eq!(report.removed(), &vec![(Visibility::Public, 5)]);
+    // The public walk stops at 2, once 4, 3 and 2 matched in order.
+    assert_eq!(report.pages(), 4);
+    assert_eq!(
+        sync.state().ids(Visibility::Public),
+        &vec![7, 6, 4, 3, 2, 1]
+    );
+
+    let report = sync
+        .sync_with(bookmark_pages(&[7, 6, 4, 3, 2, 1], &[9]))
+        .unwrap();
+    assert!(report.is_empty());
+    assert_eq!(report.pages(), 3);
+}
+
+#[test]
+fn test_full_sync_finds_older_removals() {
+    let mut sync = BookmarkSync::new(6996493).set_visibilities(vec![Visibility::Public]);
+    sync.sync_with(bookmark_pages(&[6, 5, 4, 3, 2, 1], &[]))
+        .unwrap();
+
+    // The walk stops after 6, 5 and 4, above the removal of 3.
+    let report = sync
+        .sync_with(bookmark_pages(&[6, 5, 4, 2, 1], &[]))
+        .unwrap();
+    assert!(report.is_empty());
+    assert_eq!(report.pages(), 2);
+
+    let mut sync = sync.set_full(true);
+    let report = sync
+        .sync_with(bookmark_pages(&[6, 5, 4, 2, 1], &[]))
+        .unwrap();
+    assert_eq!(report.removed(), &vec![(Visibility::Public, 3)]);
+    assert_eq!(sync.state().ids(Visibility::Public), &vec![6, 5, 4, 2, 1]);
+}
+
+#[test]
+fn test_rebookmarked_work_moves_to_the_top() {
+    let mut sync = BookmarkSync::new(6996493).set_visibilities(vec![Visibility::Public]);
+    sync.sync_with(bookmark_pages(&[5, 4, 3, 2, 1], &[]))
+        .unwrap();
+
+    // 1 was removed and bookmarked again, then 6 was added.
+    let report = sync
+        .sync_with(bookmark_pages(&[6, 1, 5, 4, 3, 2], &[]))
+        .unwrap();
+    assert_eq!(added(&report), vec![6]);
+    assert!(report.removed().is_empty());
+    assert_eq!(
+        sync.state().ids(Visibility::Public),
+        &vec![6, 1, 5, 4, 3, 2]
+    );
+
+    let report = sync
+        .sync_with(bookmark_pages(&[6, 1, 5, 4, 3, 2], &[]))
+        .unwrap();
+    assert!(report.is_empty());
+    assert_eq!(report.pages(), 2);
+}
+
+#[test]
+fn test_sync_state_is_persisted() {
+    let path = temp_dir("bookmark-sync")
+        .join("state")
+        .join("bookmarks.json");
+    let mut sync = BookmarkSync::open(6996493, &path).unwrap();
+    sync.sync_with(bookmark_pages(&[2, 1], &[3])).unwrap();
+
+    let mut sync = BookmarkSync::open(6996493, &path).unwrap();
+    assert_eq!(sync.state().ids(Visibility::Private), &vec![3]);
+    let report = sync.sync_with(bookmark_pages(&[4, 2, 1], &[3])).unwrap();
+    assert_eq!(added(&report), vec![4]);
+
+    // A failed sync leaves the saved state alone.
+    let saved = std::fs::read(&path).unwrap();
+    let failed = sync.sync_with(|_| Err(SyncError::Io(std::io::ErrorKind::Other.into())));
+    assert!(failed.is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), saved);
+    assert_eq!(sync.state().ids(Visibility::Public), &vec![4, 2, 1]);
+}
+
+#[test]
+fn test_failed_downloads_are_retried() {
+    let (url, _) = serve(vec![
+        response("500 Internal Server Error", 0, b""),
+        response("200 OK", 3, b"abc"),
+    ]);
+    let dir = temp_dir("bookmark-sync-downloads");
+    let layout = DownloadLayout::new(&dir, PathTemplate::parse("{id}.{ext}").unwrap());
+    let mut sync = BookmarkSync::open(6996493, dir.join("bookmarks.json"))
+        .unwrap()
+        .set_visibilities(vec![Visibility::Public])
+        .set_download(layout, ImageSize::Original);
+    let fetch = |_| {
+        let page = serde_json::json!({ "illusts": [illustration_json(1)], "next_url": null });
+        Ok(serde_json::from_str(
+            &page.to_string().replace("https://i.pximg.net", &url),
+        )?)
+    };
+    let downloader = || Downloader::new(reqwest::blocking::Client::new());
+
+    let report = sync.sync_downloading_with(fetch, downloader()).unwrap();
+    assert_eq!(added(&report), vec![1]);
+    assert_eq!(report.downloads().unwrap().failed().count(), 1);
+    let sync = BookmarkSync::open(6996493, dir.join("bookmarks.json")).unwrap();
+    assert_eq!(sync.state().ids(Visibility::Public), &vec![1]);
+    assert_eq!(sync.state().pending().len(), 1);
+
+    let layout = DownloadLayout::new(&dir, PathTemplate::parse("{id}.{ext}").unwrap());
+    let mut sync = sync
+        .set_visibilities(vec![Visibility::Public])
+        .set_download(layout, ImageSize::Original);
+    let report = sync.sync_downloading_with(fetch, downloader()).unwrap();
+    assert!(report.is_empty());
+    assert!(report.downloads().unwrap().is_success());
+    assert_eq!(std::fs::read(dir.join("1.jpg")).unwrap(), b"abc");
+    assert!(sync.state().pending().is_empty());
+}
+
+#[cfg(feature = "storage")]
+#[test]
+fn test_sync_report_recorded_in_catalog() {
+    use pixieve_rs::storage::catalog::Catalog;
+
+    let mut catalog = Catalog::in_memory().unwrap();
+    let mut sync = BookmarkSync::new(6996493);
+    for ids in [&[2, 1][..], &[1]] {
+        let report = sync.sync_with(bookmark_pages(ids, &[3])).unwrap();
+        catalog
+            .record_bookmark_sync(
+                report
+                    .added()
+                    .iter()
+                    .map(|(visibility, illust)| (*visibility, illust)),
+                report.removed().iter().copied(),
+            )
+            .unwrap();
+    }
+
+    assert_eq!(catalog.bookmarked_ids(None).unwrap(), vec![3, 1]);
+    assert_eq!(
+        catalog.bookmarked_ids(Some(Visibility::Private)).unwrap(),
+        vec![3]
+    );
+    assert!(!catalog.bookmark(2).unwrap().unwrap().is_bookmarked());
+}
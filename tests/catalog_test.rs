@@ -0,0 +1,174 @@
+#![cfg(feature = "storage")]
+
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{illustration, illustration_json, manga, response, serve, MANGA_JSON};
+use pixieve_rs::enums::Visibility;
+use pixieve_rs::pixiv::client::PixivClient;
+use pixieve_rs::pixiv::helper_structs::illustration::Illustration;
+use pixieve_rs::pixiv::request::PixivRequest;
+use pixieve_rs::pixiv::result::user_illustrations::UserIllustrations;
+use pixieve_rs::storage::catalog::Catalog;
+use pixieve_rs::storage::query::IllustrationQuery;
+use std::sync::{Arc, Mutex};
+
+fn with_bookmarks(illust: &str, count: u32) -> Illustration {
+    let mut value: serde_json::Value = serde_json::from_str(illust).unwrap();
+    value["total_bookmarks"] = count.into();
+    serde_json::from_value(value).unwrap()
+}
+
+fn ids(illusts: Vec<Illustration>) -> Vec<u32> {
+    illusts.iter().map(|illust| illust.id()).collect()
+}
+
+#[test]
+fn test_catalog_round_trips_records() {
+    let mut catalog = Catalog::in_memory().unwrap();
+    catalog
+        .record_illustrations(&[manga(), illustration()])
+        .unwrap();
+
+    let manga = catalog.illustration(75523989).unwrap().unwrap();
+    assert_eq!(manga.title(), "Manga/Test: 1");
+    assert_eq!(manga.series().map(|s| s.id()), Some(12345));
+    assert_eq!(catalog.user(6996493).unwrap().unwrap().account(), "artist");
+    assert!(catalog.illustration(1).unwrap().is_none());
+    assert!(catalog.contains_illustration(66024340).unwrap());
+}
+
+#[test]
+fn test_catalog_queries() {
+    let mut catalog = Catalog::in_memory().unwrap();
+    catalog
+        .record_illustrations(&[manga(), illustration()])
+        .unwrap();
+
+    assert_eq!(ids(catalog.by_tag("漫画").unwrap()), vec![75523989]);
+    assert_eq!(ids(catalog.by_tag("scenery").unwrap()), vec![66024340]);
+    assert_eq!(
+        ids(catalog.by_user(6996493).unwrap()),
+        vec![75523989, 66024340]
+    );
+    assert_eq!(
+        ids(catalog.with_min_bookmarks(100).unwrap()),
+        vec![75523989]
+    );
+    let from = Utc.with_ymd_and_hms(2017, 1, 1, 0, 0, 0).unwrap();
+    let to = Utc.with_ymd_and_hms(2018, 1, 1, 0, 0, 0).unwrap();
+    assert_eq!(
+        ids(catalog.created_between(from, to).unwrap()),
+        vec![66024340]
+    );
+
+    let query = IllustrationQuery::new()
+        .set_user_id(6996493)
+        .set_bookmarked(true);
+    assert_eq!(ids(catalog.query(&query).unwrap()), vec![66024340]);
+    let query = IllustrationQuery::new().set_series_id(12345).set_limit(1);
+    assert_eq!(ids(catalog.query(&query).unwrap()), vec![75523989]);
+}
+
+#[test]
+fn test_catalog_upserts_and_keeps_history() {
+    let mut catalog = Catalog::in_memory().unwrap();
+    let json = MANGA_JSON;
+    catalog.record_illustration(&manga()).unwrap();
+    catalog.record_illustration(&manga()).unwrap();
+    catalog
+        .record_illustration(&with_bookmarks(json, 150))
+        .unwrap();
+
+    let history = catalog.history(75523989).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].total_bookmarks(), 100);
+    assert_eq!(history[1].total_bookmarks(), 150);
+    assert_eq!(
+        ids(catalog.with_min_bookmarks(150).unwrap()),
+        vec![75523989]
+    );
+    assert_eq!(ids(catalog.by_tag("original").unwrap()), vec![75523989]);
+    let first = catalog.first_seen(75523989).unwrap().unwrap();
+    assert!(first <= catalog.last_seen(75523989).unwrap().unwrap());
+}
+
+#[test]
+fn test_catalog_bookmark_state() {
+    let mut catalog = Catalog::in_memory().unwrap();
+    catalog.record_illustration(&illustration()).unwrap();
+    catalog.record_illustration(&manga()).unwrap();
+
+    assert!(catalog.bookmark(75523989).unwrap().is_none());
+    assert_eq!(catalog.bookmarked_ids(None).unwrap(), vec![66024340]);
+    assert!(catalog
+        .record_bookmark(66024340, true, Some(Visibility::Private))
+        .unwrap());
+    assert!(!catalog
+        .record_bookmark(66024340, true, Some(Visibility::Private))
+        .unwrap());
+    assert_eq!(
+        catalog.bookmarked_ids(Some(Visibility::Public)).unwrap(),
+        Vec::<u32>::new()
+    );
+    assert!(catalog.record_bookmark(66024340, false, None).unwrap());
+
+    let state = catalog.bookmark(66024340).unwrap().unwrap();
+    assert!(!state.is_bookmarked());
+    assert_eq!(state.visibility(), Some(Visibility::Private));
+    let history = catalog.bookmark_history(66024340).unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|s| s.is_bookmarked())
+            .collect::<Vec<_>>(),
+        vec![true, true, false]
+    );
+    assert!(catalog.bookmarked_ids(None).unwrap().is_empty());
+}
+
+#[test]
+fn test_catalog_records_responses() {
+    let mut user: serde_json::Value = serde_json::from_str(MANGA_JSON).unwrap();
+    let mut user = user["user"].take();
+    user["id"] = 42.into();
+    let response = serde_json::json!({
+        "user_previews": [{
+            "user": user,
+            "illusts": [illustration_json(1), illustration_json(2)],
+            "is_muted": false
+        }],
+        "next_url": null
+    });
+
+    let mut catalog = Catalog::in_memory().unwrap();
+    assert_eq!(catalog.record_response(&response).unwrap(), 3);
+    assert_eq!(catalog.user(42).unwrap().unwrap().account(), "artist");
+    assert!(catalog.contains_illustration(1).unwrap());
+    assert!(catalog.contains_illustration(2).unwrap());
+    assert_eq!(
+        catalog
+            .record_response(&serde_json::json!({ "user": { "id": 7 } }))
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn test_client_records_what_it_sees() {
+    let body = serde_json::json!({ "illusts": [illustration_json(5)], "next_url": null });
+    let body = body.to_string();
+    let (url, _) = serve(vec![response("200 OK", body.len(), body.as_bytes())]);
+
+    let catalog = Arc::new(Mutex::new(Catalog::in_memory().unwrap()));
+    let mut client = PixivClient::new().unwrap();
+    client.set_catalog(catalog.clone());
+    let request = PixivRequest::new(
+        http::Method::GET,
+        format!("{}/v1/user/illusts", url).parse().unwrap(),
+    );
+    let page: UserIllustrations = client.execute_json(request).unwrap();
+
+    assert_eq!(page.illusts().len(), 1);
+    assert!(catalog.lock().unwrap().contains_illustration(5).unwrap());
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::manga;
+use pixieve_rs::download::cbz::{export_illustration, export_series, Cbz, ComicInfo};
+use pixieve_rs::errors::CbzError;
+use pixieve_rs::pixiv::client::PixivClient;
+use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
+use pixieve_rs::pixiv::result::illustration_proxy::IllustrationProxy;
+
+use std::io::{Cursor, Read};
+
+const ILLUST_ID_TEST: usize = 75523989;
+
+#[test]
+fn test_comic_info_from_illustration() {
+    let xml = ComicInfo::from_illustration(&manga())
+        .set_number(2)
+        .to_xml(2);
+
+    assert!(xml.contains("  <Title>Manga/Test: 1</Title>\n"));
+    assert!(xml.contains("  <Series>Series</Series>\n  <Number>2</Number>\n"));
+    assert!(xml.contains("  <Summary>caption\nsecond line</Summary>\n"));
+    assert!(xml.contains("  <Year>2019</Year>\n  <Month>7</Month>\n  <Day>14</Day>\n"));
+    assert!(xml.contains("  <Writer>Artist</Writer>\n"));
+    assert!(xml.contains("  <Tags>オリジナル,漫画</Tags>\n"));
+    assert!(xml.contains("  <Web>https://www.pixiv.net/artworks/75523989</Web>\n"));
+    assert!(xml.contains("  <PageCount>2</PageCount>\n"));
+    assert!(xml.contains("<Page Image=\"0\" Type=\"FrontCover\" />"));
+    assert!(!xml.contains("<AgeRating>"));
+}
+
+#[test]
+fn test_write_cbz() {
+    let mut cbz = Cbz::new(ComicInfo::from_illustration(&manga()));
+    cbz.add_page(
+        "https://i.pximg.net/img-original/img/2019/07/14/00/00/00/75523989_p0.png",
+        include_bytes!("fixtures/pixel.png").to_vec(),
+    );
+    cbz.add_file("tests/fixtures/pixel.jpg")
+        .expect("Failed to read the page.");
+
+    let archive = cbz.write(Vec::new()).expect("Failed to write.");
+    let mut zip = zip::ZipArchive::new(Cursor::new(archive)).expect("Failed to read.");
+
+    let names: Vec<&str> = zip.file_names().collect();
+    assert_eq!(names, vec!["001.png", "002.jpg", "ComicInfo.xml"]);
+
+    let mut page = Vec::new();
+    zip.by_name("001.png")
+        .unwrap()
+        .read_to_end(&mut page)
+        .unwrap();
+    assert_eq!(page, include_bytes!("fixtures/pixel.png"));
+
+    let mut xml = String::new();
+    zip.by_name("ComicInfo.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    assert!(xml.contains("<PageCount>2</PageCount>"));
+}
+
+#[test]
+fn test_export_manga_as_cbz() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let illustration = pixiv
+        .execute_with_auth(PixivRequestBuilder::request_illustration(ILLUST_ID_TEST))
+        .expect("Request failed.")
+        .json::<IllustrationProxy>()
+        .expect("Failed to parse as json.")
+        .into_inner();
+
+    let cbz = export_illustration(&pixiv, &illustration, Vec::new()).expect("Failed to export.");
+
+    assert!(!cbz.is_empty());
+}
+
+#[test]
+fn test_export_empty_series_fails() {
+    let pixiv = PixivClient::new().unwrap();
+
+    let result = export_series(&pixiv, &[], Vec::new());
+
+    assert!(matches!(result, Err(CbzError::Empty)));
+}
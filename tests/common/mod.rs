@@ -0,0 +1,129 @@
+// Helpers shared by the integration tests. Each test binary only uses some of them.
+#![allow(dead_code)]
+
+use pixieve_rs::pixiv::helper_structs::illustration::Illustration;
+use pixieve_rs::pixiv::request::PixivRequest;
+use pixieve_rs::pixiv::result::user_illustrations::UserIllustrations;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Receiver;
+
+pub const MANGA_JSON: &str = include_str!("../fixtures/manga.json");
+pub const ILLUSTRATION_JSON: &str = include_str!("../fixtures/illustration.json");
+
+/// The two page manga 75523989.
+pub fn manga() -> Illustration {
+    serde_json::from_str(MANGA_JSON).expect("Failed to parse as json.")
+}
+
+/// The single page illustration 66024340.
+pub fn illustration() -> Illustration {
+    serde_json::from_str(ILLUSTRATION_JSON).expect("Failed to parse as json.")
+}
+
+/// The json of `illustration` under another id, to build fake result pages.
+pub fn illustration_json(id: u32) -> serde_json::Value {
+    let mut illust: serde_json::Value =
+        serde_json::from_str(ILLUSTRATION_JSON).expect("Failed to parse as json.");
+    illust["id"] = id.into();
+    illust
+}
+
+/// Serves `ids`, newest first, two works per page. The page starts at the `offset_key` value
+/// in the query of `request`, and the next one is `next_url` with the following offset.
+pub fn paginate(
+    ids: &[u32],
+    request: &PixivRequest,
+    offset_key: &str,
+    next_url: &str,
+) -> serde_json::Result<UserIllustrations> {
+    let query = request.url.query().unwrap_or_default();
+    let start = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(offset_key)?.strip_prefix('='))
+        .map_or(0, |offset| offset.parse().unwrap());
+    let end = ids.len().min(start + 2);
+    let illusts = ids[start..end]
+        .iter()
+        .map(|&id| illustration_json(id))
+        .collect::<Vec<_>>();
+    let next_url = (end < ids.len()).then(|| format!("{}&{}={}", next_url, offset_key, end));
+    serde_json::from_value(serde_json::json!({ "illusts": illusts, "next_url": next_url }))
+}
+
+/// An empty directory only used by the calling test.
+/// Tests run in parallel, so the name carries the process id and a counter.
+pub fn temp_dir(name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = std::env::temp_dir().join(format!(
+        "pixieve-rs-{}-{}-{}",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Serves each of `responses` once, in order, on a local port. Returns its base url and the
+/// requests it received, head and body.
+pub fn serve(responses: Vec<Vec<u8>>) -> (String, Receiver<String>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for response in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; content_length];
+            let _ = reader.read_exact(&mut body);
+            request.push_str(&String::from_utf8_lossy(&body));
+            let _ = sender.send(request);
+            let _ = reader.get_mut().write_all(&response);
+        }
+    });
+    (format!("http://{}", addr), receiver)
+}
+
+pub fn response(status: &str, content_length: usize, body: &[u8]) -> Vec<u8> {
+    response_with(status, &[], content_length, body)
+}
+
+pub fn response_with(
+    status: &str,
+    headers: &[&str],
+    content_length: usize,
+    body: &[u8],
+) -> Vec<u8> {
+    let mut response = format!("HTTP/1.1 {}\r\n", status);
+    for header in headers {
+        response.push_str(header);
+        response.push_str("\r\n");
+    }
+    response.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        content_length
+    ));
+    let mut response = response.into_bytes();
+    response.extend_from_slice(body);
+    response
+}
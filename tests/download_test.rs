@@ -0,0 +1,378 @@
+mod common;
+
+use common::{illustration, manga, response, response_with, serve, temp_dir, MANGA_JSON};
+use pixieve_rs::download::archive::{ArchiveKey, DownloadArchive};
+use pixieve_rs::download::downloader::Downloader;
+use pixieve_rs::download::queue::{DownloadEvent, DownloadQueue};
+use pixieve_rs::download::sidecar::SidecarWriter;
+use pixieve_rs::download::store::ContentStore;
+use pixieve_rs::enums::{ImageSize, LinkKind, SidecarFormat};
+use pixieve_rs::errors::DownloadError;
+use pixieve_rs::pixiv::helper_structs::illustration::Illustration;
+
+use std::sync::Arc;
+
+#[test]
+fn test_manga_original_page_urls() {
+    let manga = manga();
+    let urls = manga.page_urls(ImageSize::default());
+
+    assert_eq!(urls.len(), 2);
+    assert_eq!(
+        urls[1].map(String::as_str),
+        Some("https://i.pximg.net/img-original/img/2019/07/14/00/00/00/75523989_p1.png")
+    );
+    assert_eq!(manga.page_file_name(1, urls[1].unwrap()), "75523989_p1.png");
+}
+
+#[test]
+fn test_manga_large_page_urls() {
+    let manga = manga();
+    let urls = manga.page_urls(ImageSize::Large);
+
+    assert_eq!(urls.len(), 2);
+    assert_eq!(manga.page_file_name(0, urls[0].unwrap()), "75523989_p0.jpg");
+}
+
+#[test]
+fn test_single_page_urls() {
+    let illustration = illustration();
+
+    let original = illustration.page_urls(ImageSize::Original);
+    assert_eq!(
+        original,
+        vec![Some(&String::from(
+            "https://i.pximg.net/img-original/img/2017/11/20/00/00/00/66024340_p0.jpg"
+        ))]
+    );
+
+    let medium = illustration.page_urls(ImageSize::Medium);
+    assert_eq!(medium.len(), 1);
+    assert!(medium[0].unwrap().contains("540x540_70"));
+}
+
+#[test]
+fn test_downloader_writes_file() {
+    let body = b"not really a png";
+    let (url, _) = serve(vec![response("200 OK", body.len(), body)]);
+    let dir = temp_dir("download-ok");
+    let dest = dir.join("1_p0.png");
+
+    let downloader = Downloader::new(reqwest::blocking::Client::new());
+    let written = downloader
+        .download(&format!("{}/1_p0.png", url), &dest)
+        .expect("Failed to download.");
+
+    assert_eq!(written, body.len() as u64);
+    assert_eq!(std::fs::read(&dest).unwrap(), body);
+    assert!(!Downloader::part_path(&dest).exists());
+}
+
+#[test]
+fn test_downloader_rejects_truncated_body() {
+    let (url, _) = serve(vec![response("200 OK", 100, b"short")]);
+    let dir = temp_dir("download-truncated");
+    let dest = dir.join("1_p0.png");
+
+    let downloader = Downloader::new(reqwest::blocking::Client::new());
+    let result = downloader.download(&format!("{}/1_p0.png", url), &dest);
+
+    assert!(result.is_err());
+    assert!(!dest.exists());
+    assert!(!Downloader::part_path(&dest).exists());
+}
+
+#[test]
+fn test_download_report_keeps_going_after_failure() {
+    let (url, _) = serve(vec![
+        response("404 Not Found", 0, b""),
+        response("200 OK", 2, b"ok"),
+    ]);
+    let dir = temp_dir("download-report");
+
+    let downloader = Downloader::new(reqwest::blocking::Client::new());
+    let report = downloader.download_all(vec![
+        (format!("{}/a", url), dir.join("a")),
+        (format!("{}/b", url), dir.join("b")),
+    ]);
+
+    assert!(!report.is_success());
+    assert_eq!(report.failed().count(), 1);
+    assert!(matches!(
+        report.files()[0].error(),
+        Some(DownloadError::Request(_))
+    ));
+    assert_eq!(report.paths(), vec![&dir.join("b")]);
+    assert_eq!(report.total_bytes(), 2);
+}
+
+#[test]
+fn test_downloader_resumes_part_file() {
+    let body = b"0123456789";
+    let (url, requests) = serve(vec![
+        response_with("200 OK", &["ETag: \"v1\""], body.len(), &body[..4]),
+        response_with(
+            "206 Partial Content",
+            &["ETag: \"v1\"", "Content-Range: bytes 4-9/10"],
+            6,
+            &body[4..],
+        ),
+    ]);
+    let dir = temp_dir("download-resume");
+    let dest = dir.join("1_ugoira1920x1080.zip");
+    let url = format!("{}/1_ugoira1920x1080.zip", url);
+
+    let downloader = Downloader::new(reqwest::blocking::Client::new());
+    assert!(downloader.download(&url, &dest).is_err());
+    assert!(Downloader::part_path(&dest).exists());
+    assert!(Downloader::validator_path(&dest).exists());
+
+    let written = downloader.download(&url, &dest).expect("Failed to resume.");
+    assert_eq!(written, body.len() as u64);
+    assert_eq!(std::fs::read(&dest).unwrap(), body);
+    assert!(!Downloader::part_path(&dest).exists());
+    assert!(!Downloader::validator_path(&dest).exists());
+
+    let resumed = requests.iter().nth(1).unwrap().to_lowercase();
+    assert!(resumed.contains("range: bytes=4-"));
+    assert!(resumed.contains("if-range: \"v1\""));
+}
+
+#[test]
+fn test_downloader_restarts_without_range_support() {
+    let body = b"0123456789";
+    let (url, _) = serve(vec![
+        response_with("200 OK", &["ETag: \"v1\""], body.len(), &body[..4]),
+        response_with("200 OK", &["ETag: \"v2\""], body.len(), body),
+    ]);
+    let dir = temp_dir("download-restart");
+    let dest = dir.join("1_p0.jpg");
+    let url = format!("{}/1_p0.jpg", url);
+
+    let downloader = Downloader::new(reqwest::blocking::Client::new());
+    assert!(downloader.download(&url, &dest).is_err());
+
+    let written = downloader
+        .download(&url, &dest)
+        .expect("Failed to download.");
+    assert_eq!(written, body.len() as u64);
+    assert_eq!(std::fs::read(&dest).unwrap(), body);
+}
+
+#[test]
+fn test_queue_downloads_with_workers() {
+    let (url, _) = serve(vec![response("200 OK", 3, b"abc"); 3]);
+    let dir = temp_dir("queue");
+
+    let mut queue =
+        DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new())).set_workers(2);
+    for name in ["a", "b", "c"] {
+        queue.push(format!("{}/{}", url, name), dir.join(name));
+    }
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let report = queue.run_with_channel(sender);
+    let events: Vec<DownloadEvent> = receiver.iter().collect();
+
+    assert!(report.is_success());
+    assert_eq!(report.total_bytes(), 9);
+    for name in ["a", "b", "c"] {
+        assert_eq!(std::fs::read(dir.join(name)).unwrap(), b"abc");
+    }
+
+    let count = |f: fn(&DownloadEvent) -> bool| events.iter().filter(|event| f(event)).count();
+    assert_eq!(count(|e| matches!(e, DownloadEvent::Queued { .. })), 3);
+    assert_eq!(count(|e| matches!(e, DownloadEvent::Started { .. })), 3);
+    assert_eq!(
+        count(|e| matches!(e, DownloadEvent::Finished { size: 3, .. })),
+        3
+    );
+    assert!(events.contains(&DownloadEvent::Progress {
+        id: 1,
+        downloaded: 3,
+        total: Some(3)
+    }));
+}
+
+#[test]
+fn test_cancelled_queue_fails_remaining_files() {
+    let dir = temp_dir("queue-cancel");
+
+    let mut queue = DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new()));
+    queue.push("http://127.0.0.1:9/a", dir.join("a"));
+    queue.push("http://127.0.0.1:9/b", dir.join("b"));
+    queue.control().cancel();
+
+    let started = std::sync::atomic::AtomicUsize::new(0);
+    let report = queue.run(|event| {
+        if let DownloadEvent::Started { .. } = event {
+            started.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    });
+
+    assert_eq!(started.into_inner(), 0);
+    assert_eq!(report.failed().count(), 2);
+    assert!(report
+        .files()
+        .iter()
+        .all(|file| matches!(file.error(), Some(DownloadError::Cancelled))));
+}
+
+#[test]
+fn test_queue_writes_sidecars() {
+    let (url, _) = serve(vec![response("200 OK", 3, b"abc"); 2]);
+    let manga: Illustration =
+        serde_json::from_str(&MANGA_JSON.replace("https://i.pximg.net", &url))
+            .expect("Failed to parse as json.");
+    let dir = temp_dir("queue-sidecars");
+
+    let mut queue = DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new()))
+        .set_sidecars(SidecarWriter::new(vec![SidecarFormat::Json]));
+    queue.push_illustration(&manga, &dir, ImageSize::Original);
+    let report = queue.run(|_| {});
+
+    assert!(report.is_success());
+    assert_eq!(
+        report.paths(),
+        vec![
+            &dir.join("75523989_p0.png"),
+            &dir.join("75523989_p1.png"),
+            &dir.join("75523989.json")
+        ]
+    );
+}
+
+#[test]
+fn test_queue_moves_pages_into_store() {
+    let (url, _) = serve(vec![response("200 OK", 3, b"abc"); 2]);
+    let manga: Illustration =
+        serde_json::from_str(&MANGA_JSON.replace("https://i.pximg.net", &url))
+            .expect("Failed to parse as json.");
+    let dir = temp_dir("queue-store");
+    let store = Arc::new(ContentStore::open(dir.join("store")).unwrap());
+
+    let mut queue = DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new()))
+        .set_store(store.clone(), LinkKind::HardLink);
+    queue.push_illustration(&manga, &dir.join("views"), ImageSize::Original);
+    let report = queue.run(|_| {});
+
+    assert!(report.is_success());
+    let hash = store
+        .hash(&ArchiveKey::new(75523989, 1, ImageSize::Original))
+        .unwrap();
+    assert_eq!(store.keys(&hash).len(), 2);
+    assert_eq!(
+        std::fs::read(dir.join("views/75523989_p1.png")).unwrap(),
+        b"abc"
+    );
+}
+
+#[test]
+fn test_queue_keeps_pages_whose_metadata_cannot_be_embedded() {
+    // A PNG signature followed by a truncated chunk.
+    let page = b"\x89PNG\r\n\x1a\n\0\0";
+    let (url, _) = serve(vec![response("200 OK", page.len(), page); 2]);
+    let manga: Illustration =
+        serde_json::from_str(&MANGA_JSON.replace("https://i.pximg.net", &url))
+            .expect("Failed to parse as json.");
+    let dir = temp_dir("queue-embed");
+    let archive = Arc::new(DownloadArchive::in_memory());
+
+    let mut queue = DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new()))
+        .set_archive(archive.clone())
+        .set_embed_metadata(true);
+    queue.push_illustration(&manga, &dir, ImageSize::Original);
+    let events = std::sync::Mutex::new(Vec::new());
+    let report = queue.run(|event| events.lock().unwrap().push(event));
+
+    assert!(report.is_success());
+    assert_eq!(std::fs::read(dir.join("75523989_p0.png")).unwrap(), page);
+    assert!(archive.contains(&ArchiveKey::new(75523989, 1, ImageSize::Original)));
+    let events = events.into_inner().unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, DownloadEvent::EmbedFailed { .. }))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_queue_stores_pages_before_embedding_metadata() {
+    let page = include_bytes!("fixtures/pixel.png");
+    let (url, _) = serve(vec![response("200 OK", page.len(), page); 2]);
+    let manga: Illustration =
+        serde_json::from_str(&MANGA_JSON.replace("https://i.pximg.net", &url))
+            .expect("Failed to parse as json.");
+    let dir = temp_dir("queue-store-embed");
+    let store = Arc::new(ContentStore::open(dir.join("store")).unwrap());
+
+    let mut queue = DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new()))
+        .set_store(store.clone(), LinkKind::HardLink)
+        .set_embed_metadata(true);
+    queue.push_illustration(&manga, &dir.join("views"), ImageSize::Original);
+    let report = queue.run(|_| {});
+
+    assert!(report.is_success());
+    let hash = store
+        .hash(&ArchiveKey::new(75523989, 0, ImageSize::Original))
+        .unwrap();
+    assert_eq!(store.keys(&hash).len(), 2);
+    assert_eq!(std::fs::read(store.object_path(&hash)).unwrap(), page);
+    let view = std::fs::read(dir.join("views/75523989_p0.png")).unwrap();
+    assert_ne!(view, page);
+    assert!(view.len() > page.len());
+}
+
+#[cfg(feature = "phash")]
+#[test]
+fn test_queue_skips_near_duplicate_works() {
+    use pixieve_rs::download::phash::HashIndex;
+    use pixieve_rs::enums::HashAlgorithm;
+
+    // The thumbnail and both pages of the manga, then the thumbnail of the repost.
+    let image = include_bytes!("fixtures/pixel.png");
+    let (url, requests) = serve(vec![response("200 OK", image.len(), image); 4]);
+    let parse = |json: &str| -> Illustration {
+        serde_json::from_str(&json.replace("https://i.pximg.net", &url))
+            .expect("Failed to parse as json.")
+    };
+    let manga = parse(MANGA_JSON);
+    let repost = parse(common::ILLUSTRATION_JSON);
+    let index = Arc::new(HashIndex::in_memory(HashAlgorithm::Perceptual));
+    let dir = temp_dir("queue-duplicates");
+
+    let mut queue = DownloadQueue::new(Downloader::new(reqwest::blocking::Client::new()))
+        .set_workers(1)
+        .set_duplicate_filter(index.clone(), 4);
+    assert_eq!(
+        queue.push_illustration(&manga, &dir, ImageSize::Original),
+        vec![0, 1]
+    );
+    assert_eq!(
+        queue.push_illustration(&repost, &dir, ImageSize::Original),
+        vec![2]
+    );
+    // Thumbnails are only fetched by the workers.
+    assert!(requests.try_recv().is_err());
+
+    let events = std::sync::Mutex::new(Vec::new());
+    let report = queue.run(|event| events.lock().unwrap().push(event));
+
+    assert!(report.is_success());
+    assert_eq!(report.paths().len(), 2);
+    assert_eq!(report.skipped().count(), 1);
+    assert!(events
+        .into_inner()
+        .unwrap()
+        .iter()
+        .any(|event| matches!(event, DownloadEvent::Skipped { id: 2, .. })));
+    // The thumbnail of the manga and its two pages.
+    assert_eq!(index.len(), 3);
+    let page = pixieve_rs::download::phash::hash_bytes(image, HashAlgorithm::Perceptual).unwrap();
+    assert!(index
+        .find(&page, 0)
+        .iter()
+        .any(|(key, _)| *key == ArchiveKey::new(75523989, 1, ImageSize::Original)));
+}
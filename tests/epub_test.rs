@@ -0,0 +1,141 @@
+mod common;
+
+use common::{response, serve};
+use pixieve_rs::novel::document::Image;
+use pixieve_rs::novel::epub::{export_novel, Epub, EpubMetadata};
+use pixieve_rs::novel::parser::parse;
+use pixieve_rs::pixiv::client::PixivClient;
+use pixieve_rs::pixiv::helper_structs::novel::Novel;
+use pixieve_rs::pixiv::result::novel_text::NovelText;
+
+use std::io::{Cursor, Read};
+
+const NOVEL_JSON: &str = r#"{
+    "id": 12438689,
+    "title": "Title & more",
+    "caption": "first<br />second &amp; <a href=\"https://www.pixiv.net\">link</a>",
+    "restrict": 0,
+    "x_restrict": 0,
+    "is_original": true,
+    "image_urls": { "large": "https://i.pximg.net/c/240x480_80/novel-cover-master/img/cover.jpg" },
+    "create_date": "2020-01-01T00:00:00+09:00",
+    "tags": [{ "name": "オリジナル", "translated_name": null }],
+    "page_count": 2,
+    "text_length": 100,
+    "user": {
+        "id": 6996493,
+        "name": "Author",
+        "account": "account",
+        "profile_image_urls": { "medium": "https://i.pximg.net/user-profile/img/profile.jpg" }
+    },
+    "series": null,
+    "is_bookmarked": false,
+    "total_bookmarks": 0,
+    "total_view": 0,
+    "visible": true,
+    "is_muted": false,
+    "is_mypixiv_only": false,
+    "is_x_restricted": false
+}"#;
+
+fn read_entry(archive: &mut zip::ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .expect("Missing entry.")
+        .read_to_string(&mut content)
+        .expect("Failed to read entry.");
+    content
+}
+
+#[test]
+fn test_write_epub() {
+    let novel: Novel = serde_json::from_str(NOVEL_JSON).expect("Failed to parse as json.");
+    let document = parse("[chapter:[[rb:序 > じょ]]章]\nbody\n[uploadedimage:1]\n[newpage]\nend");
+
+    let mut epub = Epub::new(EpubMetadata::from_novel(&novel));
+    epub.set_cover("cover.jpg", vec![0xFF, 0xD8, 0xFF]);
+    let source = epub.add_image("uploaded-1.png", vec![0x89, 0x50, 0x4E, 0x47]);
+    epub.add_document(novel.title(), &document, |image| match image {
+        Image::Uploaded { image_id: 1 } => Some(source.clone()),
+        _ => None,
+    });
+
+    let bytes = epub.write(Vec::new()).expect("Failed to write the EPUB.");
+
+    // The mimetype must be the first, uncompressed, entry.
+    assert_eq!(&bytes[30..38], b"mimetype");
+    assert_eq!(&bytes[38..58], b"application/epub+zip");
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).expect("Invalid archive.");
+    let opf = read_entry(&mut archive, "OEBPS/content.opf");
+    let nav = read_entry(&mut archive, "OEBPS/nav.xhtml");
+    let section = read_entry(&mut archive, "OEBPS/text/section-1.xhtml");
+
+    assert!(opf.contains("<dc:identifier id=\"book-id\">urn:pixiv:novel:12438689</dc:identifier>"));
+    assert!(opf.contains("<dc:title>Title &amp; more</dc:title>"));
+    assert!(opf.contains("<dc:creator>Author</dc:creator>"));
+    assert!(opf.contains("<dc:subject>オリジナル</dc:subject>"));
+    assert!(opf.contains("<dc:date>2020-01-01T00:00:00+09:00</dc:date>"));
+    assert!(opf.contains("<dc:description>first\nsecond &amp; link</dc:description>"));
+    assert!(opf.contains(
+        "href=\"images/cover.jpg\" media-type=\"image/jpeg\" properties=\"cover-image\""
+    ));
+    assert!(opf.contains("href=\"images/uploaded-1.png\" media-type=\"image/png\""));
+    assert!(nav.contains("<a href=\"text/section-1.xhtml#chapter-1\">序章</a>"));
+    assert!(section.contains("<h2 id=\"chapter-1\"><ruby>序<rt>じょ</rt></ruby>章</h2>"));
+    assert!(section.contains("<img src=\"../images/uploaded-1.png\" alt=\"\" />"));
+    assert!(archive.by_name("OEBPS/images/cover.jpg").is_ok());
+}
+
+#[test]
+fn test_images_are_stored_once() {
+    let novel: Novel = serde_json::from_str(NOVEL_JSON).expect("Failed to parse as json.");
+    let mut epub = Epub::new(EpubMetadata::from_novel(&novel));
+
+    let first = epub.add_image("pixiv-1-1.jpg", vec![0xFF, 0xD8, 0xFF]);
+    let second = epub.add_image("pixiv-1-1.jpg", vec![0xFF, 0xD8, 0xFF]);
+    assert_eq!(first, second);
+
+    let bytes = epub.write(Vec::new()).expect("Failed to write the EPUB.");
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).expect("Invalid archive.");
+    let opf = read_entry(&mut archive, "OEBPS/content.opf");
+    assert_eq!(opf.matches("href=\"images/pixiv-1-1.jpg\"").count(), 1);
+}
+
+#[test]
+fn test_export_takes_images_from_the_text() {
+    let (url, requests) = serve(vec![
+        response("200 OK", 3, &[0xFF, 0xD8, 0xFF]),
+        response("200 OK", 3, &[0xFF, 0xD8, 0xFF]),
+        response("200 OK", 4, &[0x89, 0x50, 0x4E, 0x47]),
+    ]);
+    let mut novel: serde_json::Value = serde_json::from_str(NOVEL_JSON).unwrap();
+    novel["image_urls"]["large"] = format!("{}/cover.jpg", url).into();
+    let novel: Novel = serde_json::from_value(novel).unwrap();
+    // 1 is not in the illusts anymore and is left as a link to the artwork.
+    let html = format!(
+        r#"<script>pixiv = {{
+        novel: {{"id":"12438689","title":"title","seriesId":null,"seriesTitle":null,"userId":"6996493","coverUrl":"","tags":[],"caption":"","cdate":"2020-01-01","text":"[pixivimage:75523989-2]\n[pixivimage:1]\n[uploadedimage:123]",
+            "illusts":{{"75523989-2":{{"visible":true,"illust":{{"title":"manga","images":{{"small":"{url}/s.jpg","original":"{url}/o.jpg"}}}}}}}},
+            "images":{{"123":{{"novelImageId":"123","sl":"0","urls":{{"1200x1200":"{url}/1200.png"}}}}}},
+            "seriesNavigation":null}},
+    }}</script>"#,
+        url = url
+    );
+    let text = NovelText::from_webview(&html).expect("Failed to parse as json.");
+
+    let client = PixivClient::new().unwrap();
+    let bytes = export_novel(&client, &novel, &text, Vec::new()).expect("Failed to export.");
+
+    let paths: Vec<String> = requests
+        .try_iter()
+        .map(|request| request.split(' ').nth(1).unwrap().to_string())
+        .collect();
+    assert_eq!(paths, vec!["/cover.jpg", "/o.jpg", "/1200.png"]);
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).expect("Invalid archive.");
+    let section = read_entry(&mut archive, "OEBPS/text/section-1.xhtml");
+    assert!(section.contains("<img src=\"../images/pixiv-75523989-2.jpg\" alt=\"\" />"));
+    assert!(section.contains("<img src=\"../images/uploaded-12438689-123.png\" alt=\"\" />"));
+    assert!(section.contains("https://www.pixiv.net/artworks/1"));
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::{paginate, temp_dir};
+use pixieve_rs::enums::Visibility;
+use pixieve_rs::errors::SyncError;
+use pixieve_rs::pixiv::helper_structs::illustration::Illustration;
+use pixieve_rs::pixiv::request::PixivRequest;
+use pixieve_rs::pixiv::result::user_illustrations::UserIllustrations;
+use pixieve_rs::sync::follow::{FollowEvent, FollowWatcher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Serves the public feed `ids`, newest first, two works per page.
+fn feed(
+    ids: Arc<Mutex<Vec<u32>>>,
+) -> impl FnMut(PixivRequest) -> Result<UserIllustrations, SyncError> {
+    move |request| {
+        assert_eq!(request.url.path(), "/v2/illust/follow");
+        if request
+            .url
+            .query()
+            .unwrap_or_default()
+            .contains("restrict=private")
+        {
+            return Ok(serde_json::from_str(
+                r#"{ "illusts": [], "next_url": null }"#,
+            )?);
+        }
+        Ok(paginate(
+            &ids.lock().unwrap(),
+            &request,
+            "offset",
+            "https://app-api.pixiv.net/v2/illust/follow?restrict=public",
+        )?)
+    }
+}
+
+fn ids(works: Vec<(Visibility, Illustration)>) -> Vec<u32> {
+    works.iter().map(|(_, illust)| illust.id()).collect()
+}
+
+#[test]
+fn test_first_poll_only_records() {
+    let posted = Arc::new(Mutex::new(vec![30, 20, 10]));
+    let mut watcher = FollowWatcher::new();
+
+    // Recording only needs the newest work, the second page of the feed is not fetched.
+    let mut fetch = feed(posted.clone());
+    let mut requests = 0;
+    let first = watcher.poll_with(|request| {
+        requests += 1;
+        fetch(request)
+    });
+    assert!(first.unwrap().is_empty());
+    assert_eq!(requests, 2);
+    assert_eq!(watcher.state().last_seen(Visibility::Public), Some(30));
+    assert_eq!(watcher.state().last_seen(Visibility::Private), None);
+
+    posted.lock().unwrap().splice(0..0, [50, 40]);
+    assert_eq!(
+        ids(watcher.poll_with(feed(posted.clone())).unwrap()),
+        vec![40, 50]
+    );
+    assert!(watcher.poll_with(feed(posted)).unwrap().is_empty());
+}
+
+#[test]
+fn test_emit_initial_and_max_pages() {
+    let posted = Arc::new(Mutex::new(vec![60, 50, 40, 30, 20, 10]));
+    let mut watcher = FollowWatcher::new().set_emit_initial(true).set_max_pages(2);
+
+    assert_eq!(
+        ids(watcher.poll_with(feed(posted)).unwrap()),
+        vec![30, 40, 50, 60]
+    );
+    assert_eq!(watcher.state().last_seen(Visibility::Public), Some(60));
+}
+
+#[test]
+fn test_state_survives_restarts() {
+    let path = temp_dir("follow-watcher").join("follow.json");
+    let posted = Arc::new(Mutex::new(vec![20, 10]));
+    FollowWatcher::open(&path)
+        .unwrap()
+        .poll_with(feed(posted.clone()))
+        .unwrap();
+
+    posted.lock().unwrap().insert(0, 30);
+    let mut watcher = FollowWatcher::open(&path).unwrap();
+    assert_eq!(watcher.state().last_seen(Visibility::Public), Some(20));
+    assert_eq!(ids(watcher.poll_with(feed(posted)).unwrap()), vec![30]);
+
+    // A failed poll keeps the previous state.
+    let failed = watcher.poll_with(|_| Err(SyncError::Io(std::io::ErrorKind::Other.into())));
+    assert!(failed.is_err());
+    assert_eq!(
+        FollowWatcher::open(&path)
+            .unwrap()
+            .state()
+            .last_seen(Visibility::Public),
+        Some(30)
+    );
+}
+
+#[test]
+fn test_watch_emits_events_until_stopped() {
+    let posted = Arc::new(Mutex::new(vec![10]));
+    let mut watcher = FollowWatcher::new().set_interval(Duration::from_millis(1));
+    let control = watcher.control();
+    let mut fetch = feed(posted.clone());
+    let mut polls = 0;
+    let mut events = Vec::new();
+
+    watcher.watch_with(
+        |request| {
+            polls += 1;
+            match polls {
+                // The second poll of the public feed fails, the watch goes on.
+                3 => Err(SyncError::Io(std::io::ErrorKind::Other.into())),
+                5 => {
+                    posted.lock().unwrap().insert(0, 20);
+                    fetch(request)
+                }
+                _ => fetch(request),
+            }
+        },
+        |event| {
+            if let FollowEvent::NewWork { illustration, .. } = &event {
+                assert_eq!(illustration.id(), 20);
+                control.stop();
+            }
+            events.push(event);
+        },
+    );
+
+    assert!(matches!(events[0], FollowEvent::PollFailed(_)));
+    assert!(matches!(
+        events[1],
+        FollowEvent::NewWork {
+            visibility: Visibility::Public,
+            ..
+        }
+    ));
+    assert_eq!(events.len(), 2);
+}
@@ -37,7 +37,17 @@ fn test_fetching_illustration() {
         .expect("Failed to parse as json.")
         .into_inner();
 
-    pixiv.download_illustration(&illustration, &std::env::current_dir().unwrap());
+    let report = pixiv.download_illustration(&illustration, &std::env::current_dir().unwrap());
+
+    assert!(
+        report.is_success(),
+        "Failed downloads: {:?}",
+        report
+            .failed()
+            .map(|file| (file.url(), file.error()))
+            .collect::<Vec<_>>()
+    );
+    assert!(report.paths().iter().all(|path| path.exists()));
 }
 
 #[test]
@@ -218,7 +228,11 @@ fn test_adding_bookmark() {
 
     pixiv.refresh_auth().expect("Failed to log in.");
 
-    let request = PixivRequestBuilder::request_adding_bookmark(ILLUST_ID_TEST, Visibility::Public);
+    let request = PixivRequestBuilder::request_adding_bookmark(
+        ILLUST_ID_TEST,
+        Visibility::Public,
+        ["Fate/GO"],
+    );
 
     println!("request:\n{:?}", request);
 
@@ -0,0 +1,123 @@
+mod common;
+
+use common::manga;
+use pixieve_rs::download::metadata::{embed, embed_file, EmbeddedMetadata};
+use pixieve_rs::errors::MetadataError;
+
+const JPEG: &[u8] = include_bytes!("fixtures/pixel.jpg");
+const PNG: &[u8] = include_bytes!("fixtures/pixel.png");
+
+fn metadata() -> EmbeddedMetadata {
+    EmbeddedMetadata::from_illustration(&manga())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Returns the types of the chunks of a PNG file.
+fn png_chunks(png: &[u8]) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut position = 8;
+    while position < png.len() {
+        let length = u32::from_be_bytes(png[position..position + 4].try_into().unwrap()) as usize;
+        chunks.push(String::from_utf8_lossy(&png[position + 4..position + 8]).into_owned());
+        position += 12 + length;
+    }
+    chunks
+}
+
+#[test]
+fn test_metadata_from_illustration() {
+    let metadata = metadata();
+
+    assert_eq!(metadata.title(), "Manga/Test: 1");
+    assert_eq!(metadata.artist_id(), 6996493);
+    assert_eq!(metadata.tags(), &vec!["オリジナル", "漫画", "original"]);
+    assert_eq!(
+        metadata.source_url(),
+        "https://www.pixiv.net/artworks/75523989"
+    );
+
+    let xmp = metadata.to_xmp();
+    assert!(xmp.contains("<rdf:li>オリジナル</rdf:li>"));
+    assert!(xmp.contains("<dc:source>https://www.pixiv.net/artworks/75523989</dc:source>"));
+    assert!(xmp.contains("<xmp:CreateDate>2019-07-14T00:00:00+09:00</xmp:CreateDate>"));
+}
+
+#[test]
+fn test_embed_jpeg() {
+    let embedded = embed(JPEG, &metadata()).expect("Failed to embed.");
+
+    // SOI, the JFIF APP0 segment, then the EXIF APP1 segment.
+    assert_eq!(&embedded[..4], &[0xFF, 0xD8, 0xFF, 0xE0]);
+    assert_eq!(&embedded[20..22], &[0xFF, 0xE1]);
+    assert_eq!(&embedded[24..30], b"Exif\0\0");
+    assert!(find(&embedded, b"http://ns.adobe.com/xap/1.0/\0").is_some());
+    assert!(find(&embedded, "漫画".as_bytes()).is_some());
+
+    // The scan is copied as is.
+    let scan = find(JPEG, &[0xFF, 0xDA]).unwrap();
+    assert!(embedded.ends_with(&JPEG[scan..]));
+
+    assert_eq!(embed(&embedded, &metadata()).unwrap(), embedded);
+}
+
+#[test]
+fn test_embed_png() {
+    let embedded = embed(PNG, &metadata()).expect("Failed to embed.");
+    let chunks = png_chunks(&embedded);
+
+    assert_eq!(chunks.first().map(String::as_str), Some("IHDR"));
+    let first_data = chunks.iter().position(|chunk| chunk == "IDAT").unwrap();
+    assert_eq!(
+        chunks[..first_data]
+            .iter()
+            .filter(|chunk| *chunk == "iTXt")
+            .count(),
+        6
+    );
+    assert!(find(&embedded, b"XML:com.adobe.xmp\0").is_some());
+
+    let idat = find(PNG, b"IDAT").unwrap() - 4;
+    assert!(embedded.ends_with(&PNG[idat..]));
+
+    assert_eq!(embed(&embedded, &metadata()).unwrap(), embedded);
+}
+
+#[cfg(feature = "ugoira")]
+#[test]
+fn test_embedded_images_still_decode() {
+    for image in [JPEG, PNG] {
+        let embedded = embed(image, &metadata()).unwrap();
+        let decoded = image::load_from_memory(&embedded).expect("Failed to decode.");
+        assert_eq!(decoded.width(), 8);
+    }
+}
+
+#[test]
+fn test_embed_rejects_other_formats() {
+    assert!(matches!(
+        embed(b"GIF89a", &metadata()),
+        Err(MetadataError::UnsupportedFormat)
+    ));
+    assert!(matches!(
+        embed(&JPEG[..30], &metadata()),
+        Err(MetadataError::Malformed(_))
+    ));
+}
+
+#[test]
+fn test_embed_file() {
+    let path = std::env::temp_dir().join(format!("pixieve-rs-{}.png", std::process::id()));
+    std::fs::write(&path, PNG).unwrap();
+
+    embed_file(&path, &metadata()).expect("Failed to embed.");
+
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        embed(PNG, &metadata()).unwrap()
+    );
+}
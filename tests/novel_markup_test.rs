@@ -0,0 +1,148 @@
+use pixieve_rs::novel::document::{Block, Image, Inline};
+use pixieve_rs::novel::parser::parse;
+use pixieve_rs::novel::render::{to_html, to_markdown, to_markdown_with, to_plain_text};
+
+const NOVEL_TEXT: &str = "[chapter:第一章 [[rb:始 > はじ]]まり]\n\
+    [[rb:漢字 > かんじ]]を読む。\n\
+    \n\
+    [pixivimage:12345-2]\n\
+    [newpage]\n\
+    [uploadedimage:678]\n\
+    [[jumpuri:pixiv > https://www.pixiv.net]]と[jump:1]と[notatag]";
+
+#[test]
+fn test_parse_pages_and_chapters() {
+    let document = parse(NOVEL_TEXT);
+
+    assert_eq!(document.pages().len(), 2);
+    assert_eq!(
+        document.pages()[0].blocks()[0],
+        Block::Chapter(vec![
+            Inline::Text(String::from("第一章 ")),
+            Inline::Ruby {
+                base: String::from("始"),
+                reading: String::from("はじ"),
+            },
+            Inline::Text(String::from("まり")),
+        ])
+    );
+    assert_eq!(document.chapters().len(), 1);
+}
+
+#[test]
+fn test_parse_inline_markup() {
+    let document = parse(NOVEL_TEXT);
+    let first_page = document.pages()[0].blocks();
+    let second_page = document.pages()[1].blocks();
+
+    assert_eq!(
+        first_page[1],
+        Block::Paragraph(vec![
+            Inline::Ruby {
+                base: String::from("漢字"),
+                reading: String::from("かんじ"),
+            },
+            Inline::Text(String::from("を読む。")),
+        ])
+    );
+    assert_eq!(first_page[2], Block::Paragraph(vec![]));
+    assert_eq!(
+        second_page[1],
+        Block::Paragraph(vec![
+            Inline::Link {
+                text: String::from("pixiv"),
+                url: String::from("https://www.pixiv.net"),
+            },
+            Inline::Text(String::from("と")),
+            Inline::PageJump(1),
+            Inline::Text(String::from("と[notatag]")),
+        ])
+    );
+}
+
+#[test]
+fn test_parse_images() {
+    let document = parse(NOVEL_TEXT);
+
+    assert_eq!(
+        document.images(),
+        vec![
+            &Image::Pixiv {
+                illust_id: 12345,
+                page: Some(2),
+            },
+            &Image::Uploaded { image_id: 678 },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_unterminated_tags_as_text() {
+    let document = parse("[[rb:漢字 かんじ]] [chapter:title");
+
+    assert_eq!(
+        document.pages()[0].blocks(),
+        &vec![Block::Paragraph(vec![Inline::Text(String::from(
+            "[[rb:漢字 かんじ]] [chapter:title"
+        ))])]
+    );
+}
+
+#[test]
+fn test_render_plain_text() {
+    assert_eq!(
+        to_plain_text(&parse(NOVEL_TEXT)),
+        "第一章 始(はじ)まり\n漢字(かんじ)を読む。\n\n\npixiv (https://www.pixiv.net)と(p. 1)と[notatag]"
+    );
+}
+
+#[test]
+fn test_render_markdown() {
+    let markdown = to_markdown(&parse(NOVEL_TEXT));
+
+    assert!(markdown.contains("## 第一章 <ruby>始<rt>はじ</rt></ruby>まり"));
+    assert!(markdown.contains("[https://www.pixiv.net/artworks/12345]"));
+    assert!(markdown.contains("[pixiv](https://www.pixiv.net)"));
+    assert!(markdown.contains("\\[notatag\\]"));
+    assert!(markdown.contains("\n---\n"));
+    assert!(markdown.starts_with("<a id=\"page-1\"></a>\n"));
+    assert!(markdown.contains("\n---\n\n<a id=\"page-2\"></a>\n"));
+    assert!(markdown.contains("[p. 1](#page-1)"));
+}
+
+#[test]
+fn test_render_markdown_image_sources() {
+    let markdown = to_markdown_with(&parse("[uploadedimage:678]"), |_| {
+        Some("images/my image (1).png".to_string())
+    });
+
+    assert!(markdown.contains("![](images/my%20image%20%281%29.png)"));
+}
+
+#[test]
+fn test_render_html() {
+    let html = to_html(&parse("<b>&</b>\n[newpage]\n[jump:1]"));
+
+    assert_eq!(
+        html,
+        "<section id=\"page-1\">\n<p>&lt;b&gt;&amp;&lt;/b&gt;</p>\n</section>\n\
+         <section id=\"page-2\">\n<p><a href=\"#page-1\">1</a></p>\n</section>\n"
+    );
+}
+
+#[test]
+fn test_render_only_web_links() {
+    let document = parse(
+        "[[jumpuri:a > javascript:alert(1)]][[jumpuri:b > data:text/html,x]][[jumpuri:c > HTTPS://example.com]]",
+    );
+
+    let html = to_html(&document);
+    assert!(!html.contains("javascript:"));
+    assert!(!html.contains("data:"));
+    assert!(html.contains("<p>ab<a href=\"HTTPS://example.com\">c</a></p>"));
+
+    let markdown = to_markdown(&document);
+    assert!(!markdown.contains("javascript:"));
+    assert!(!markdown.contains("data:"));
+    assert!(markdown.contains("ab[c](HTTPS://example.com)"));
+}
@@ -0,0 +1,335 @@
+use pixieve_rs::enums::Visibility;
+use pixieve_rs::novel::epub::export_novel;
+use pixieve_rs::pixiv::arg::novel_ranking_request_arg::NovelRankingRequestArg;
+use pixieve_rs::pixiv::arg::novel_search_request_arg::NovelSearchRequestArg;
+use pixieve_rs::pixiv::arg::user_novel_bookmarks_request_arg::UserNovelBookmarksRequestArg;
+use pixieve_rs::pixiv::client::PixivClient;
+use pixieve_rs::pixiv::helper_structs::novel::Novel;
+use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
+use pixieve_rs::pixiv::result::novel_list::NovelList;
+use pixieve_rs::pixiv::result::novel_proxy::NovelProxy;
+use pixieve_rs::pixiv::result::novel_search_proxy::NovelSearchProxy;
+use pixieve_rs::pixiv::result::novel_series::NovelSeries;
+use pixieve_rs::pixiv::result::novel_text::NovelText;
+use pixieve_rs::pixiv::result::user_novels::UserNovels;
+
+const NOVEL_ID_TEST: usize = 12438689;
+const NOVEL_SERIES_ID_TEST: usize = 1206946;
+const USER_ID_TEST: usize = 6996493;
+
+const NOVEL_JSON: &str = r#"{
+    "id": 12438689,
+    "title": "title",
+    "caption": "",
+    "restrict": 0,
+    "x_restrict": 0,
+    "is_original": true,
+    "image_urls": {
+        "square_medium": "https://i.pximg.net/c/128x128/novel-cover-master/img/cover.jpg",
+        "medium": "https://i.pximg.net/c/176x352/novel-cover-master/img/cover.jpg",
+        "large": "https://i.pximg.net/c/240x480_80/novel-cover-master/img/cover.jpg"
+    },
+    "create_date": "2020-01-01T00:00:00+09:00",
+    "tags": [
+        { "name": "オリジナル", "translated_name": "original", "added_by_uploaded_user": true }
+    ],
+    "page_count": 3,
+    "text_length": 12000,
+    "user": {
+        "id": 6996493,
+        "name": "name",
+        "account": "account",
+        "profile_image_urls": { "medium": "https://i.pximg.net/user-profile/img/profile.jpg" },
+        "is_followed": false
+    },
+    "series": {},
+    "is_bookmarked": false,
+    "total_bookmarks": 10,
+    "total_view": 100,
+    "visible": true,
+    "total_comments": 0,
+    "is_muted": false,
+    "is_mypixiv_only": false,
+    "is_x_restricted": false,
+    "novel_ai_type": 1
+}"#;
+
+#[test]
+fn test_parse_novel_without_series() {
+    let novel: Novel = serde_json::from_str(NOVEL_JSON).expect("Failed to parse as json.");
+
+    assert!(novel.series().is_none());
+    assert_eq!(novel.text_length(), 12000);
+    assert_eq!(
+        novel.tags()[0].translated_name(),
+        &Some(vec![String::from("original")])
+    );
+}
+
+#[test]
+fn test_parse_novel_text_from_webview() {
+    let html = r#"<html><script>
+        Object.defineProperty(window, 'pixiv', { value: {
+            novel: {"id":"12438689","title":"title","seriesId":null,"seriesTitle":null,"userId":"6996493","coverUrl":"https://i.pximg.net/cover.jpg","tags":["オリジナル"],"caption":"","cdate":"2020-01-01","text":"first page\n[newpage]\nsecond page","illusts":[],"images":[],"seriesNavigation":null},
+            isOwnWork: false,
+        }});
+    </script></html>"#;
+
+    let text = NovelText::from_webview(html).expect("Failed to parse as json.");
+
+    assert_eq!(text.id(), "12438689");
+    assert_eq!(text.text(), "first page\n[newpage]\nsecond page");
+    assert!(text.series_navigation().is_none());
+}
+
+#[test]
+fn test_parse_novel_text_images() {
+    let html = r#"<script>pixiv = {
+        novel: {"id":"1","title":"title","seriesId":null,"seriesTitle":null,"userId":"2","coverUrl":"","tags":[],"caption":"","cdate":"2020-01-01","text":"[pixivimage:75523989-2]\n[uploadedimage:123]",
+            "illusts":{"75523989-2":{"visible":true,"illust":{"title":"manga","images":{"small":"https://i.pximg.net/s.jpg","medium":"https://i.pximg.net/m.jpg","original":"https://i.pximg.net/o.jpg"}}}},
+            "images":{"123":{"novelImageId":"123","sl":"0","urls":{"240mw":"https://i.pximg.net/240.jpg","1200x1200":"https://i.pximg.net/1200.jpg"}}},
+            "seriesNavigation":null},
+    }</script>"#;
+
+    let text = NovelText::from_webview(html).expect("Failed to parse as json.");
+
+    let illust = text
+        .illust(75523989, Some(2))
+        .expect("Expected the illustration.");
+    assert!(illust.is_visible());
+    assert_eq!(illust.illust().unwrap().title(), "manga");
+    assert_eq!(
+        illust.illust().unwrap().images().largest().unwrap(),
+        "https://i.pximg.net/o.jpg"
+    );
+    assert!(text.illust(75523989, None).is_none());
+
+    let image = text.image(123).expect("Expected the image.");
+    assert_eq!(image.novel_image_id(), "123");
+    assert_eq!(
+        image.urls().largest().unwrap(),
+        "https://i.pximg.net/1200.jpg"
+    );
+}
+
+#[test]
+fn test_parse_novel_text_from_unrelated_page() {
+    assert!(NovelText::from_webview("<html></html>").is_err());
+}
+
+#[test]
+fn test_fetch_novel() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_novel(NOVEL_ID_TEST);
+
+    let novel = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<NovelProxy>()
+        .expect("Failed to parse as json.")
+        .into_inner();
+
+    assert_eq!(novel.id() as usize, NOVEL_ID_TEST);
+}
+
+#[test]
+fn test_fetch_novel_text() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_novel_text(NOVEL_ID_TEST);
+
+    let html = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .text()
+        .expect("Failed to read body.");
+
+    NovelText::from_webview(&html).expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_fetch_novel_series() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_novel_series(NOVEL_SERIES_ID_TEST, None);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<NovelSeries>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_search_novel() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let args = NovelSearchRequestArg::new("Pretty Cure");
+
+    let request = PixivRequestBuilder::request_novel_search(args);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<NovelSearchProxy>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_fetch_user_novels() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_user_novels(USER_ID_TEST, 0);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserNovels>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_fetch_user_novel_bookmarks() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let args = UserNovelBookmarksRequestArg::new(USER_ID_TEST as u32);
+
+    let request = PixivRequestBuilder::request_user_novel_bookmarks(args);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<NovelList>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_fetch_novel_ranking() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let args = NovelRankingRequestArg::default();
+
+    let request = PixivRequestBuilder::request_novel_ranking(args);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<NovelList>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_adding_and_deleting_novel_bookmark() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_adding_novel_bookmark(
+        NOVEL_ID_TEST,
+        Visibility::Private,
+        ["オリジナル"],
+    );
+
+    println!("request:\n{:?}", request);
+
+    let result = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<serde_json::Value>()
+        .expect("Failed to parse as json.");
+    println!("result:\n{}", result);
+
+    let request = PixivRequestBuilder::request_delete_novel_bookmark(NOVEL_ID_TEST);
+
+    let result = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<serde_json::Value>()
+        .expect("Failed to parse as json.");
+    println!("result:\n{}", result);
+}
+
+#[test]
+fn test_export_novel_as_epub() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let novel = pixiv
+        .execute_with_auth(PixivRequestBuilder::request_novel(NOVEL_ID_TEST))
+        .expect("Request failed.")
+        .json::<NovelProxy>()
+        .expect("Failed to parse as json.")
+        .into_inner();
+
+    let html = pixiv
+        .execute_with_auth(PixivRequestBuilder::request_novel_text(NOVEL_ID_TEST))
+        .expect("Request failed.")
+        .text()
+        .expect("Failed to read body.");
+    let text = NovelText::from_webview(&html).expect("Failed to parse as json.");
+
+    let epub = export_novel(&pixiv, &novel, &text, Vec::new()).expect("Failed to export.");
+
+    assert!(!epub.is_empty());
+}
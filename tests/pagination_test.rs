@@ -0,0 +1,38 @@
+use pixieve_rs::pixiv::result::paginated::Paginated;
+use pixieve_rs::pixiv::result::user_previews::UserPreviews;
+
+#[test]
+fn test_next_request_keeps_query() {
+    let json = r#"{
+        "user_previews": [],
+        "next_url": "https://app-api.pixiv.net/v1/user/following?user_id=6996493&restrict=public&offset=30"
+    }"#;
+
+    let page: UserPreviews = serde_json::from_str(json).expect("Failed to parse as json.");
+    let request = page.next_request().expect("Expected a next page.");
+
+    assert_eq!(request.method, http::Method::GET);
+    assert_eq!(request.url.path(), "/v1/user/following");
+    assert_eq!(
+        request.url.query(),
+        Some("user_id=6996493&restrict=public&offset=30")
+    );
+}
+
+#[test]
+fn test_last_page_has_no_next_request() {
+    let json = r#"{ "user_previews": [], "next_url": null }"#;
+
+    let page: UserPreviews = serde_json::from_str(json).expect("Failed to parse as json.");
+
+    assert!(page.next_request().is_none());
+}
+
+#[test]
+fn test_invalid_next_url_ends_pagination() {
+    let json = r#"{ "user_previews": [], "next_url": "not a url" }"#;
+
+    let page: UserPreviews = serde_json::from_str(json).expect("Failed to parse as json.");
+
+    assert!(page.next_request().is_none());
+}
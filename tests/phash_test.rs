@@ -0,0 +1,108 @@
+#![cfg(feature = "phash")]
+
+use pixieve_rs::download::archive::ArchiveKey;
+use pixieve_rs::download::phash::{hash_bytes, hash_image, HashIndex, ImageHash};
+use pixieve_rs::enums::{HashAlgorithm, ImageSize};
+
+use image::{DynamicImage, Rgb, RgbImage};
+
+const ALGORITHMS: [HashAlgorithm; 3] = [
+    HashAlgorithm::Average,
+    HashAlgorithm::Difference,
+    HashAlgorithm::Perceptual,
+];
+
+/// A picture with a few large shapes.
+fn picture(width: u32, height: u32, brightness: i32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        let (x, y) = (x as f32 / width as f32, y as f32 / height as f32);
+        let mut value = if (x - 0.3).powi(2) + (y - 0.4).powi(2) < 0.05 {
+            220
+        } else if x > 0.6 && y > 0.5 {
+            40
+        } else {
+            (x * 120.0 + y * 60.0) as i32
+        };
+        value = (value + brightness).clamp(0, 255);
+        Rgb([value as u8, value as u8, (value / 2) as u8])
+    }))
+}
+
+fn key(illust_id: u32) -> ArchiveKey {
+    ArchiveKey::new(illust_id, 0, ImageSize::Original)
+}
+
+#[test]
+fn test_similar_images_have_close_hashes() {
+    let original = picture(400, 300, 0);
+    let reupload = picture(200, 150, 12);
+    let other = original.fliph().rotate90();
+
+    for algorithm in ALGORITHMS {
+        let hash = hash_image(&original, algorithm);
+        let near = hash.distance(&hash_image(&reupload, algorithm)).unwrap();
+        let far = hash.distance(&hash_image(&other, algorithm)).unwrap();
+
+        assert!(near <= 6, "{:?}: {} bits apart", algorithm, near);
+        assert!(far > 16, "{:?}: only {} bits apart", algorithm, far);
+    }
+}
+
+#[test]
+fn test_hash_bytes() {
+    let hash = hash_bytes(include_bytes!("fixtures/pixel.png"), HashAlgorithm::Average)
+        .expect("Failed to decode.");
+    assert_eq!(hash.algorithm(), HashAlgorithm::Average);
+    assert!(hash_bytes(b"not an image", HashAlgorithm::Average).is_err());
+}
+
+#[test]
+fn test_hash_format() {
+    let hash = ImageHash::new(HashAlgorithm::Perceptual, 0xc3d1e0f0f0e0d1c3);
+
+    assert_eq!(hash.to_string(), "phash:c3d1e0f0f0e0d1c3");
+    assert_eq!("phash:c3d1e0f0f0e0d1c3".parse::<ImageHash>().unwrap(), hash);
+    assert!("phash:c3d1".parse::<ImageHash>().is_err());
+    assert_eq!(
+        hash.distance(&ImageHash::new(HashAlgorithm::Average, hash.bits())),
+        None
+    );
+}
+
+#[test]
+fn test_index_finds_near_duplicates() {
+    let index = HashIndex::in_memory(HashAlgorithm::Perceptual);
+    let hash = |bits| ImageHash::new(HashAlgorithm::Perceptual, bits);
+    index.insert(key(1), hash(0b1111)).unwrap();
+    index.insert(key(2), hash(0b0111)).unwrap();
+    index.insert(key(3), hash(!0)).unwrap();
+    assert!(index
+        .insert(key(4), ImageHash::new(HashAlgorithm::Average, 0))
+        .is_err());
+
+    assert_eq!(index.find(&hash(0b1111), 1), vec![(key(1), 0), (key(2), 1)]);
+    assert!(!index.contains_near(&hash(1 << 40), 2));
+    assert_eq!(index.near_duplicates(4), vec![(key(1), key(2), 1)]);
+}
+
+#[test]
+fn test_index_persists() {
+    let path = std::env::temp_dir().join(format!("pixieve-rs-phash-{}.txt", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let hash = ImageHash::new(HashAlgorithm::Difference, 42);
+
+    HashIndex::open(&path, HashAlgorithm::Difference)
+        .unwrap()
+        .insert(key(1), hash)
+        .unwrap();
+
+    assert_eq!(
+        HashIndex::open(&path, HashAlgorithm::Difference)
+            .unwrap()
+            .find(&hash, 0),
+        vec![(key(1), 0)]
+    );
+    assert!(HashIndex::open(&path, HashAlgorithm::Average)
+        .unwrap()
+        .is_empty());
+}
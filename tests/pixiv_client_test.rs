@@ -1,4 +1,9 @@
+mod common;
+
+use common::{response, serve};
+use pixieve_rs::errors::ApiError;
 use pixieve_rs::pixiv::client::PixivClient;
+use pixieve_rs::pixiv::request::PixivRequest;
 use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
 
 use serde_json::Value;
@@ -124,3 +129,21 @@ fn test_login_fail() {
     let mut pixiv: PixivClient = PixivClient::new().unwrap();
     pixiv.login("", "").expect("Failed to log in.");
 }
+
+#[test]
+fn test_execute_json_errors() {
+    let (url, _) = serve(vec![
+        response("404 Not Found", 0, b""),
+        response("200 OK", 8, b"not json"),
+    ]);
+    let client = PixivClient::new().unwrap();
+    let request = || PixivRequest::new(http::Method::GET, url.parse().unwrap());
+
+    let error = client.execute_json::<Value>(request()).unwrap_err();
+    assert!(matches!(error, ApiError::Request(_)));
+    let error = client.execute_json::<Value>(request()).unwrap_err();
+    assert!(matches!(error, ApiError::Json(_)));
+    assert!(error
+        .to_string()
+        .starts_with("Failed to parse the response. Reason:"));
+}
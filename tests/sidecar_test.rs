@@ -0,0 +1,66 @@
+mod common;
+
+use common::manga;
+use pixieve_rs::download::sidecar::{render, SidecarWriter};
+use pixieve_rs::enums::SidecarFormat;
+use pixieve_rs::pixiv::helper_structs::illustration::Illustration;
+use pixieve_rs::utils::caption_to_text;
+
+#[test]
+fn test_json_sidecar_round_trips() {
+    let manga = manga();
+    let json = render(&manga, SidecarFormat::Json);
+    let parsed: Illustration = serde_json::from_str(&json).expect("Failed to parse as json.");
+
+    assert_eq!(parsed.id(), manga.id());
+    assert_eq!(parsed.tags().len(), 2);
+}
+
+#[test]
+fn test_toml_sidecar() {
+    let toml = render(&manga(), SidecarFormat::Toml);
+
+    assert!(toml.contains("title = \"Manga/Test: 1\"\n"));
+    assert!(toml.contains("url = \"https://www.pixiv.net/artworks/75523989\"\n"));
+    assert!(toml.contains("caption = \"caption\\nsecond line\"\n"));
+    assert!(toml.contains("\n[user]\nid = 6996493\nname = \"Artist\"\n"));
+    assert!(toml.contains("\n[series]\nid = 12345\n"));
+    assert!(toml.contains("\n[[tags]]\nname = \"オリジナル\"\ntranslated_name = \"original\"\n"));
+    assert!(toml.contains("\n[[tags]]\nname = \"漫画\"\n"));
+}
+
+#[test]
+fn test_text_sidecar() {
+    let text = render(&manga(), SidecarFormat::Text);
+
+    assert!(text.starts_with("Title: Manga/Test: 1\n"));
+    assert!(text.contains("Artist: Artist (id 6996493, @artist)\n"));
+    assert!(text.contains("Tags: オリジナル (original), 漫画\n"));
+    assert!(text.ends_with("\ncaption\nsecond line\n"));
+}
+
+#[test]
+fn test_caption_to_text() {
+    assert_eq!(
+        caption_to_text("a<br />b <a href=\"https://example.com\">link</a> &amp; &lt;3"),
+        "a\nb link & <3"
+    );
+}
+
+#[test]
+fn test_write_sidecars() {
+    let dir = std::env::temp_dir().join(format!("pixieve-rs-sidecar-{}", std::process::id()));
+    let writer = SidecarWriter::new(vec![
+        SidecarFormat::Json,
+        SidecarFormat::Text,
+        SidecarFormat::Json,
+    ]);
+
+    let paths = writer.write(&manga(), &dir).expect("Failed to write.");
+
+    assert_eq!(
+        paths,
+        vec![dir.join("75523989.json"), dir.join("75523989.txt")]
+    );
+    assert!(paths.iter().all(|path| path.exists()));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::temp_dir;
+use pixieve_rs::download::archive::ArchiveKey;
+use pixieve_rs::download::store::{hash_reader, ContentStore};
+use pixieve_rs::enums::{ImageSize, LinkKind};
+
+#[test]
+fn test_hash_reader() {
+    assert_eq!(
+        hash_reader(&b"abc"[..]).unwrap(),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn test_store_deduplicates() {
+    let dir = temp_dir("store");
+    let store = ContentStore::open(dir.join("store")).unwrap();
+    let original = ArchiveKey::new(1, 0, ImageSize::Original);
+    let repost = ArchiveKey::new(2, 0, ImageSize::Original);
+
+    std::fs::write(dir.join("1_p0.png"), b"same bytes").unwrap();
+    std::fs::write(dir.join("2_p0.png"), b"same bytes").unwrap();
+    let first = store.insert_file(original, dir.join("1_p0.png")).unwrap();
+    let second = store.insert_file(repost, dir.join("2_p0.png")).unwrap();
+
+    assert_eq!(first, second);
+    assert!(store.contains_object(&first));
+    assert!(!dir.join("1_p0.png").exists());
+    assert!(!dir.join("2_p0.png").exists());
+    assert_eq!(store.keys(&first), vec![original, repost]);
+
+    let other = store
+        .insert_bytes(ArchiveKey::new(3, 0, ImageSize::Large), b"other bytes")
+        .unwrap();
+    assert_ne!(other, first);
+    assert_eq!(
+        std::fs::read(store.object_path(&other)).unwrap(),
+        b"other bytes"
+    );
+}
+
+#[test]
+fn test_store_links_views() {
+    let dir = temp_dir("store-links");
+    let store = ContentStore::open(dir.join("store")).unwrap();
+    let key = ArchiveKey::new(1, 0, ImageSize::Original);
+    store.insert_bytes(key, b"content").unwrap();
+
+    for (kind, name) in [
+        (LinkKind::HardLink, "hard/1_p0.png"),
+        (LinkKind::Symlink, "sym/1_p0.png"),
+        (LinkKind::Copy, "copy/1_p0.png"),
+    ] {
+        let view = dir.join(name);
+        store.link(&key, &view, kind).expect("Failed to link.");
+        // Linking again replaces the view.
+        store
+            .link(&key, &view, kind)
+            .expect("Failed to link again.");
+        assert_eq!(std::fs::read(&view).unwrap(), b"content");
+    }
+    assert!(std::fs::symlink_metadata(dir.join("sym/1_p0.png"))
+        .unwrap()
+        .file_type()
+        .is_symlink());
+
+    let missing = ArchiveKey::new(9, 0, ImageSize::Original);
+    assert!(store
+        .link(&missing, dir.join("missing.png"), LinkKind::Copy)
+        .is_err());
+}
+
+#[test]
+fn test_store_index_persists() {
+    let dir = temp_dir("store-index");
+    let key = ArchiveKey::new(1, 2, ImageSize::Medium);
+
+    let hash = ContentStore::open(&dir)
+        .unwrap()
+        .insert_bytes(key, b"content")
+        .unwrap();
+    let store = ContentStore::open(&dir).unwrap();
+
+    assert_eq!(store.hash(&key), Some(hash.clone()));
+    assert_eq!(
+        std::fs::read_to_string(dir.join("index.txt")).unwrap(),
+        format!("{} 1_p2 medium\n", hash)
+    );
+}
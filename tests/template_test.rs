@@ -0,0 +1,123 @@
+mod common;
+
+use common::{illustration, manga};
+use pixieve_rs::download::template::{
+    sanitize_name, DownloadLayout, PathTemplate, TemplateContext, MAX_NAME_LENGTH,
+};
+use pixieve_rs::enums::{CollisionPolicy, ImageSize};
+use pixieve_rs::errors::TemplateError;
+use pixieve_rs::pixiv::helper_structs::illustration::Illustration;
+
+use std::path::{Path, PathBuf};
+
+fn render(template: &str, illustration: &Illustration, page: usize) -> PathBuf {
+    let url = illustration.page_urls(ImageSize::Original)[page].unwrap();
+    let context = TemplateContext::new(illustration, page, url, ImageSize::Original);
+    PathTemplate::parse(template)
+        .expect("Failed to parse the template.")
+        .render(&context)
+}
+
+#[test]
+fn test_render_template() {
+    let manga = manga();
+
+    assert_eq!(
+        render("{user.name}/{id}_{title}_p{page}.{ext}", &manga, 1),
+        Path::new("Artist/75523989_Manga_Test_ 1_p1.png")
+    );
+    assert_eq!(
+        render(
+            "{date}/{{{tags.0.translated_name}}} {tags.1}.{ext}",
+            &manga,
+            0
+        ),
+        Path::new("2019-07-14/{original} 漫画.png")
+    );
+    assert_eq!(
+        render("{user.id}/{series.title}/{id}.{ext}", &illustration(), 0),
+        Path::new("6996493/66024340.jpg")
+    );
+    assert_eq!(
+        render("{id}_p{page}.{ext}", &manga, 0),
+        PathTemplate::default().render(&TemplateContext::new(
+            &manga,
+            0,
+            manga.page_urls(ImageSize::Original)[0].unwrap(),
+            ImageSize::Original
+        ))
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        PathTemplate::parse("{id"),
+        Err(TemplateError::UnclosedField(0))
+    );
+    assert_eq!(
+        PathTemplate::parse("id}"),
+        Err(TemplateError::UnmatchedBrace(2))
+    );
+    assert_eq!(
+        PathTemplate::parse("{user.email}"),
+        Err(TemplateError::UnknownField(String::from("user.email")))
+    );
+    assert!(PathTemplate::parse("{tags.x}").is_err());
+}
+
+#[test]
+fn test_sanitize_name() {
+    assert_eq!(
+        sanitize_name("a/b\\c:d*e?f\"g<h>i|j", 100),
+        "a_b_c_d_e_f_g_h_i_j"
+    );
+    assert_eq!(sanitize_name("title. ", 100), "title");
+    assert_eq!(sanitize_name("CON", 100), "CON_");
+    assert_eq!(sanitize_name("nul.txt", 100), "nul_.txt");
+    assert_eq!(sanitize_name("console", 100), "console");
+    assert_eq!(sanitize_name("..", 100), "_");
+    assert_eq!(sanitize_name("", 100), "_");
+
+    let long = format!("{}.png", "あ".repeat(100));
+    let short = sanitize_name(&long, MAX_NAME_LENGTH);
+    assert!(short.len() <= MAX_NAME_LENGTH);
+    assert!(short.ends_with("あ.png"));
+}
+
+#[test]
+fn test_layout_collisions() {
+    let manga = manga();
+    let template = PathTemplate::parse("{user.name}.{ext}").unwrap();
+    let root = std::env::temp_dir().join(format!("pixieve-rs-layout-{}", std::process::id()));
+
+    let mut layout = DownloadLayout::new(&root, template.clone());
+    let targets = layout.targets(&manga, ImageSize::Original);
+    assert_eq!(
+        targets
+            .iter()
+            .map(|(_, path)| path.clone())
+            .collect::<Vec<_>>(),
+        vec![root.join("Artist.png"), root.join("Artist (1).png")]
+    );
+
+    let mut layout = DownloadLayout::new(&root, template).set_collision(CollisionPolicy::Skip);
+    assert_eq!(layout.targets(&manga, ImageSize::Original).len(), 1);
+}
+
+#[test]
+fn test_renamed_collisions_fit_the_name_length() {
+    let manga = manga();
+    let template = PathTemplate::parse("{title}.{ext}")
+        .unwrap()
+        .set_max_name_length(12);
+    let root = std::env::temp_dir().join(format!("pixieve-rs-layout-{}", std::process::id()));
+
+    let names: Vec<String> = DownloadLayout::new(&root, template)
+        .targets(&manga, ImageSize::Original)
+        .iter()
+        .map(|(_, path)| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names, vec!["Manga_Te.png", "Mang (1).png"]);
+    assert!(names.iter().all(|name| name.len() <= 12));
+}
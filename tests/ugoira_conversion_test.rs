@@ -0,0 +1,106 @@
+#![cfg(feature = "ugoira")]
+
+use pixieve_rs::enums::AnimationFormat;
+use pixieve_rs::errors::ConversionError;
+use pixieve_rs::pixiv::helper_structs::ugoira_metadata::UgoiraMetadata;
+use pixieve_rs::ugoira::convert::{convert, ConversionOptions};
+
+use std::io::{Cursor, Write};
+
+const METADATA_JSON: &str = r#"{
+    "zip_urls": { "medium": "https://i.pximg.net/img-zip-ugoira/img/44298467_ugoira600x600.zip" },
+    "frames": [
+        { "file": "000000.png", "delay": 100 },
+        { "file": "000001.png", "delay": 250 },
+        { "file": "000002.png", "delay": 50 }
+    ]
+}"#;
+
+fn frame_zip(files: &[&str]) -> Cursor<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (index, file) in files.iter().enumerate() {
+        let shade = (index * 100) as u8;
+        let image = image::RgbImage::from_pixel(8, 4, image::Rgb([shade, 255 - shade, 0]));
+        let mut png = Cursor::new(Vec::new());
+        image
+            .write_to(&mut png, image::ImageFormat::Png)
+            .expect("Failed to encode the frame.");
+
+        zip.start_file(*file, zip::write::SimpleFileOptions::default())
+            .expect("Failed to add the frame.");
+        zip.write_all(png.get_ref())
+            .expect("Failed to add the frame.");
+    }
+    let mut cursor = zip.finish().expect("Failed to write the zip.");
+    cursor.set_position(0);
+    cursor
+}
+
+fn metadata() -> UgoiraMetadata {
+    serde_json::from_str(METADATA_JSON).expect("Failed to parse as json.")
+}
+
+fn convert_frames(options: ConversionOptions) -> Vec<u8> {
+    let zip = frame_zip(&["000000.png", "000001.png", "000002.png"]);
+    convert(zip, &metadata(), &options, Vec::new()).expect("Failed to convert.")
+}
+
+#[test]
+fn test_convert_to_gif() {
+    let gif = convert_frames(
+        ConversionOptions::new(AnimationFormat::Gif)
+            .set_loop_count(3)
+            .set_palette_quality(1),
+    );
+
+    let mut decoder = gif::DecodeOptions::new()
+        .read_info(Cursor::new(gif))
+        .expect("Invalid GIF.");
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().expect("Invalid frame.") {
+        delays.push(frame.delay);
+    }
+
+    assert_eq!(delays, vec![10, 25, 5]);
+    assert_eq!(decoder.repeat(), gif::Repeat::Finite(3));
+}
+
+#[test]
+fn test_convert_to_apng() {
+    let apng = convert_frames(ConversionOptions::new(AnimationFormat::Apng));
+
+    let reader = png::Decoder::new(Cursor::new(apng))
+        .read_info()
+        .expect("Invalid PNG.");
+    let animation = reader.info().animation_control().expect("Not animated.");
+
+    assert_eq!(animation.num_frames, 3);
+    assert_eq!(animation.num_plays, 0);
+    assert_eq!(reader.info().width, 8);
+}
+
+#[test]
+fn test_convert_to_webp() {
+    let webp = convert_frames(ConversionOptions::new(AnimationFormat::WebP).set_size(4, 4));
+
+    let mut decoder = image_webp::WebPDecoder::new(Cursor::new(webp)).expect("Invalid WebP.");
+    assert!(decoder.is_animated());
+    assert_eq!(decoder.num_frames(), 3);
+    assert_eq!(decoder.dimensions(), (4, 2));
+
+    let mut buffer = vec![0; decoder.output_buffer_size().unwrap()];
+    let mut delays = Vec::new();
+    for _ in 0..3 {
+        delays.push(decoder.read_frame(&mut buffer).expect("Invalid frame."));
+    }
+    assert_eq!(delays, vec![100, 250, 50]);
+}
+
+#[test]
+fn test_convert_with_missing_frame() {
+    let zip = frame_zip(&["000000.png", "000001.png"]);
+
+    let result = convert(zip, &metadata(), &ConversionOptions::default(), Vec::new());
+
+    assert!(matches!(result, Err(ConversionError::MissingFrame(file)) if file == "000002.png"));
+}
@@ -0,0 +1,62 @@
+use pixieve_rs::enums::UgoiraSize;
+use pixieve_rs::pixiv::client::PixivClient;
+use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
+use pixieve_rs::pixiv::result::ugoira_metadata_proxy::UgoiraMetadataProxy;
+
+const UGOIRA_ID_TEST: usize = 44298467;
+
+#[test]
+fn test_parse_ugoira_metadata() {
+    let json = r#"{
+        "ugoira_metadata": {
+            "zip_urls": {
+                "medium": "https://i.pximg.net/img-zip-ugoira/img/2014/06/24/00/00/00/44298467_ugoira600x600.zip"
+            },
+            "frames": [
+                { "file": "000000.jpg", "delay": 70 },
+                { "file": "000001.jpg", "delay": 30 }
+            ]
+        }
+    }"#;
+
+    let metadata = serde_json::from_str::<UgoiraMetadataProxy>(json)
+        .expect("Failed to parse as json.")
+        .into_inner();
+
+    assert_eq!(metadata.frames().len(), 2);
+    assert_eq!(metadata.frames()[1].file(), "000001.jpg");
+    assert_eq!(metadata.duration(), 100);
+    assert_eq!(
+        metadata.zip_urls().get(UgoiraSize::Original),
+        "https://i.pximg.net/img-zip-ugoira/img/2014/06/24/00/00/00/44298467_ugoira1920x1080.zip"
+    );
+}
+
+#[test]
+fn test_download_ugoira() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_ugoira_metadata(UGOIRA_ID_TEST);
+
+    let metadata = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UgoiraMetadataProxy>()
+        .expect("Failed to parse as json.")
+        .into_inner();
+
+    pixiv
+        .download_ugoira(
+            &metadata,
+            UgoiraSize::Medium,
+            &std::env::current_dir().unwrap(),
+        )
+        .expect("Failed to download.");
+}
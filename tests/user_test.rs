@@ -0,0 +1,202 @@
+use pixieve_rs::enums::{ContentType, Visibility};
+use pixieve_rs::pixiv::arg::user_following_request_arg::UserFollowingRequestArgs;
+use pixieve_rs::pixiv::arg::user_illustrations_request_arg::UserIllustrationsRequestArg;
+use pixieve_rs::pixiv::arg::user_search_request_arg::UserSearchRequestArg;
+use pixieve_rs::pixiv::client::PixivClient;
+use pixieve_rs::pixiv::request_builder::PixivRequestBuilder;
+use pixieve_rs::pixiv::result::paginated::Paginated;
+use pixieve_rs::pixiv::result::user_detail::UserDetail;
+use pixieve_rs::pixiv::result::user_illustrations::UserIllustrations;
+use pixieve_rs::pixiv::result::user_previews::UserPreviews;
+
+const USER_ID_TEST: usize = 6996493;
+
+#[test]
+fn test_fetch_user_detail() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_user_detail(USER_ID_TEST);
+
+    let detail = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserDetail>()
+        .expect("Failed to parse as json.");
+
+    assert_eq!(detail.user().id() as usize, USER_ID_TEST);
+}
+
+#[test]
+fn test_fetch_user_illustrations() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let args = UserIllustrationsRequestArg::new(USER_ID_TEST as u32)
+        .set_content_type(ContentType::Manga)
+        .set_offset(30);
+
+    let request = PixivRequestBuilder::request_user_illustrations(args);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserIllustrations>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_fetch_user_following() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let args = UserFollowingRequestArgs::new(USER_ID_TEST as u32);
+
+    let request = PixivRequestBuilder::request_user_following(args);
+
+    let first_page = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserPreviews>()
+        .expect("Failed to parse as json.");
+
+    if let Some(request) = first_page.next_request() {
+        pixiv
+            .execute_with_auth(request)
+            .expect("Request failed.")
+            .json::<UserPreviews>()
+            .expect("Failed to parse as json.");
+    }
+}
+
+#[test]
+fn test_fetch_user_follower() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_user_follower(USER_ID_TEST, 0);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserPreviews>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_fetch_user_mypixiv() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_user_mypixiv(USER_ID_TEST, 0);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserPreviews>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_follow_and_unfollow_user() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_adding_follow(USER_ID_TEST, Visibility::Private);
+
+    println!("request:\n{:?}", request);
+
+    let result = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<serde_json::Value>()
+        .expect("Failed to parse as json.");
+    println!("result:\n{}", result);
+
+    let request = PixivRequestBuilder::request_delete_follow(USER_ID_TEST);
+
+    let result = pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<serde_json::Value>()
+        .expect("Failed to parse as json.");
+    println!("result:\n{}", result);
+}
+
+#[test]
+fn test_fetch_recommended_users() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let request = PixivRequestBuilder::request_recommended_users(0);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserPreviews>()
+        .expect("Failed to parse as json.");
+}
+
+#[test]
+fn test_search_user() {
+    dotenv::dotenv().ok();
+
+    let mut pixiv: PixivClient = PixivClient::new().unwrap();
+
+    let refresh_token = std::env::var("REFRESH_TOKEN").expect("REFRESH_TOKEN isn't set!");
+    *pixiv.refresh_token_mut() = refresh_token;
+
+    pixiv.refresh_auth().expect("Failed to log in.");
+
+    let args = UserSearchRequestArg::new("Pretty Cure");
+
+    let request = PixivRequestBuilder::request_user_search(args);
+
+    pixiv
+        .execute_with_auth(request)
+        .expect("Request failed.")
+        .json::<UserPreviews>()
+        .expect("Failed to parse as json.");
+}
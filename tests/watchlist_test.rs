@@ -0,0 +1,373 @@
+mod common;
+
+use common::{illustration_json, manga, paginate, response, serve, temp_dir};
+use pixieve_rs::enums::SearchTarget;
+use pixieve_rs::errors::SyncError;
+use pixieve_rs::pixiv::request::PixivRequest;
+use pixieve_rs::pixiv::result::user_illustrations::UserIllustrations;
+use pixieve_rs::sync::sink::{JsonLinesSink, WebhookSink};
+use pixieve_rs::sync::watchlist::{
+    WatchFailure, WatchFilter, WatchHit, WatchSource, Watchlist, WatchlistReport,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// An illustration with the given id, tags and sanity level.
+fn work(id: u32, tags: &[&str], sanity_level: u32) -> serde_json::Value {
+    let mut illust = illustration_json(id);
+    illust["sanity_level"] = sanity_level.into();
+    illust["tags"] = tags
+        .iter()
+        .map(|tag| serde_json::json!({ "name": tag }))
+        .collect();
+    illust
+}
+
+type Feeds = Arc<Mutex<Vec<(&'static str, Vec<serde_json::Value>)>>>;
+
+/// Serves one page per request, picking the feed whose path matches.
+fn serve_feeds(feeds: Feeds) -> impl FnMut(PixivRequest) -> Result<UserIllustrations, SyncError> {
+    move |request| {
+        let feeds = feeds.lock().unwrap();
+        let (_, works) = feeds
+            .iter()
+            .find(|(path, _)| request.url.path() == *path)
+            .expect("Unexpected request.");
+        Ok(serde_json::from_value(
+            serde_json::json!({ "illusts": works, "next_url": null }),
+        )?)
+    }
+}
+
+fn hit_ids(report: &WatchlistReport) -> Vec<u32> {
+    report
+        .hits()
+        .iter()
+        .map(|hit| hit.illustration().id())
+        .collect()
+}
+
+#[test]
+fn test_watch_source_requests() {
+    let request = WatchSource::user_manga(6996493).request();
+    assert_eq!(request.url.path(), "/v1/user/illusts");
+    assert_eq!(request.params.get("user_id").unwrap(), "6996493");
+    assert_eq!(request.params.get("type").unwrap(), "manga");
+
+    let source = WatchSource::search_with("風景", SearchTarget::TagsExact);
+    let request = source.request();
+    assert_eq!(request.url.path(), "/v1/search/illust");
+    assert_eq!(request.params.get("word").unwrap(), "風景");
+    assert_eq!(source.key(), "search:exact_match_for_tags:風景");
+    assert_eq!(WatchSource::user(1).to_string(), "user:illust:1");
+}
+
+#[test]
+fn test_watch_filter() {
+    let manga = manga();
+    assert!(WatchFilter::new().matches(&manga));
+    assert!(WatchFilter::new()
+        .add_include_tag("Original")
+        .add_include_tag("漫画")
+        .matches(&manga));
+    assert!(!WatchFilter::new().add_include_tag("風景").matches(&manga));
+    assert!(!WatchFilter::new()
+        .add_exclude_tag("ORIGINAL")
+        .matches(&manga));
+    assert!(!WatchFilter::new().set_max_sanity_level(0).matches(&manga));
+    assert!(WatchFilter::new().set_max_sanity_level(2).matches(&manga));
+}
+
+#[test]
+fn test_poll_dispatches_new_matches() {
+    let feeds: Feeds = Arc::new(Mutex::new(vec![
+        ("/v1/user/illusts", vec![work(10, &["a"], 2)]),
+        ("/v1/search/illust", vec![work(12, &["tag"], 2)]),
+    ]));
+    let hits = Arc::new(Mutex::new(Vec::new()));
+    let sink_hits = hits.clone();
+    let mut watchlist = Watchlist::new()
+        .set_delay(Duration::ZERO)
+        .add(
+            WatchSource::user(1),
+            WatchFilter::new().add_exclude_tag("excluded"),
+        )
+        .add(
+            WatchSource::search("tag"),
+            WatchFilter::new().set_max_sanity_level(4),
+        )
+        .add_sink(move |hit: &WatchHit| {
+            sink_hits
+                .lock()
+                .unwrap()
+                .push((hit.source().key(), hit.illustration().id()));
+            Ok(())
+        });
+
+    let report = watchlist.poll_with(serve_feeds(feeds.clone()));
+    assert!(report.is_success());
+    assert!(report.hits().is_empty());
+    assert_eq!(watchlist.state().last_seen(&WatchSource::user(1)), Some(10));
+
+    *feeds.lock().unwrap() = vec![
+        (
+            "/v1/user/illusts",
+            vec![
+                work(14, &["a"], 2),
+                work(13, &["excluded"], 2),
+                work(11, &["a"], 2),
+                work(10, &["a"], 2),
+            ],
+        ),
+        (
+            "/v1/search/illust",
+            vec![
+                work(15, &["tag"], 6),
+                work(14, &["tag"], 2),
+                work(12, &["tag"], 2),
+            ],
+        ),
+    ];
+    let report = watchlist.poll_with(serve_feeds(feeds));
+    assert_eq!(hit_ids(&report), vec![11, 14]);
+    assert_eq!(
+        *hits.lock().unwrap(),
+        vec![
+            ("user:illust:1".to_string(), 11),
+            ("user:illust:1".to_string(), 14)
+        ]
+    );
+    assert_eq!(
+        watchlist.state().last_seen(&WatchSource::search("tag")),
+        Some(15)
+    );
+}
+
+#[test]
+fn test_first_poll_only_records() {
+    let posted = Arc::new(Mutex::new(vec![30, 20, 10]));
+    let mut requests = 0;
+    let mut fetch = |request: PixivRequest| {
+        requests += 1;
+        Ok(paginate(
+            &posted.lock().unwrap(),
+            &request,
+            "offset",
+            "https://app-api.pixiv.net/v1/user/illusts?user_id=1",
+        )?)
+    };
+    let mut watchlist = Watchlist::new()
+        .set_delay(Duration::ZERO)
+        .add(WatchSource::user(1), WatchFilter::new());
+
+    // Recording only needs the newest work, the second page of the source is not fetched.
+    let report = watchlist.poll_with(&mut fetch);
+    assert!(report.is_success());
+    assert!(report.hits().is_empty());
+    assert_eq!(watchlist.state().last_seen(&WatchSource::user(1)), Some(30));
+
+    posted.lock().unwrap().splice(0..0, [50, 40]);
+    let report = watchlist.poll_with(&mut fetch);
+    assert_eq!(hit_ids(&report), vec![40, 50]);
+    assert_eq!(requests, 3);
+}
+
+#[test]
+fn test_failures_do_not_stop_other_sources() {
+    let path = temp_dir("watchlist").join("watchlist.json");
+    let feeds: Feeds = Arc::new(Mutex::new(vec![(
+        "/v1/search/illust",
+        vec![work(3, &[], 2), work(2, &[], 2)],
+    )]));
+    let mut fetch = serve_feeds(feeds);
+    let mut watchlist = Watchlist::open(&path)
+        .unwrap()
+        .set_delay(Duration::ZERO)
+        .set_emit_initial(true)
+        .add(WatchSource::user(1), WatchFilter::new())
+        .add(WatchSource::search("tag"), WatchFilter::new())
+        .add_sink(|_: &WatchHit| Err(SyncError::Io(std::io::ErrorKind::Other.into())));
+
+    let report = watchlist.poll_with(|request| {
+        if request.url.path() == "/v1/user/illusts" {
+            return Err(SyncError::Io(std::io::ErrorKind::Other.into()));
+        }
+        fetch(request)
+    });
+    assert_eq!(hit_ids(&report), vec![2, 3]);
+    assert!(matches!(
+        report.failures()[0],
+        WatchFailure::Source(WatchSource::User { user_id: 1, .. }, _)
+    ));
+    assert!(matches!(report.failures()[1], WatchFailure::Sink(_)));
+    assert!(matches!(report.failures()[2], WatchFailure::Sink(_)));
+    assert_eq!(report.failures().len(), 3);
+
+    let watchlist = Watchlist::open(&path).unwrap();
+    assert_eq!(watchlist.state().last_seen(&WatchSource::user(1)), None);
+    assert_eq!(
+        watchlist.state().last_seen(&WatchSource::search("tag")),
+        Some(3)
+    );
+    assert_eq!(watchlist.state().pending(), 2);
+}
+
+#[test]
+fn test_rejected_hits_are_retried_for_their_sink() {
+    let feeds: Feeds = Arc::new(Mutex::new(vec![(
+        "/v1/search/illust",
+        vec![work(4, &[], 2), work(3, &[], 2), work(2, &[], 2)],
+    )]));
+    let healthy = Arc::new(Mutex::new(Vec::new()));
+    let healthy_sent = healthy.clone();
+    let flaky = Arc::new(Mutex::new(Vec::new()));
+    let flaky_sent = flaky.clone();
+    let mut down = true;
+    let mut watchlist = Watchlist::new()
+        .set_delay(Duration::ZERO)
+        .set_emit_initial(true)
+        .add(WatchSource::search("tag"), WatchFilter::new())
+        .add_sink(move |hit: &WatchHit| {
+            healthy_sent.lock().unwrap().push(hit.illustration().id());
+            Ok(())
+        })
+        .add_sink(move |hit: &WatchHit| {
+            let id = hit.illustration().id();
+            // The sink is down when 3 comes, and back up for the next poll.
+            if id == 3 && std::mem::take(&mut down) {
+                return Err(SyncError::Io(std::io::ErrorKind::Other.into()));
+            }
+            flaky_sent.lock().unwrap().push(id);
+            Ok(())
+        });
+
+    let report = watchlist.poll_with(serve_feeds(feeds.clone()));
+    assert_eq!(hit_ids(&report), vec![2, 3, 4]);
+    assert_eq!(report.failures().len(), 1);
+    assert_eq!(*healthy.lock().unwrap(), vec![2, 3, 4]);
+    assert_eq!(*flaky.lock().unwrap(), vec![2, 4]);
+    assert_eq!(
+        watchlist.state().last_seen(&WatchSource::search("tag")),
+        Some(4)
+    );
+    assert_eq!(watchlist.state().pending(), 1);
+
+    let report = watchlist.poll_with(serve_feeds(feeds));
+    assert!(report.is_success());
+    assert!(report.hits().is_empty());
+    assert_eq!(*healthy.lock().unwrap(), vec![2, 3, 4]);
+    assert_eq!(*flaky.lock().unwrap(), vec![2, 4, 3]);
+    assert_eq!(watchlist.state().pending(), 0);
+}
+
+#[test]
+fn test_hits_are_dropped_after_max_retries() {
+    let feeds: Feeds = Arc::new(Mutex::new(vec![(
+        "/v1/search/illust",
+        vec![work(2, &[], 2)],
+    )]));
+    let attempts = Arc::new(Mutex::new(0));
+    let sink_attempts = attempts.clone();
+    let mut watchlist = Watchlist::new()
+        .set_delay(Duration::ZERO)
+        .set_emit_initial(true)
+        .set_max_retries(2)
+        .add(WatchSource::search("tag"), WatchFilter::new())
+        .add_sink(move |_: &WatchHit| {
+            *sink_attempts.lock().unwrap() += 1;
+            Err(SyncError::Io(std::io::ErrorKind::Other.into()))
+        });
+
+    for _ in 0..2 {
+        let report = watchlist.poll_with(serve_feeds(feeds.clone()));
+        assert!(matches!(
+            report.failures().as_slice(),
+            [WatchFailure::Sink(_)]
+        ));
+    }
+    let report = watchlist.poll_with(serve_feeds(feeds.clone()));
+    match report.failures().as_slice() {
+        [WatchFailure::Sink(_), WatchFailure::Dropped(hit)] => {
+            assert_eq!(hit.illustration().id(), 2)
+        }
+        failures => panic!("{:?}", failures),
+    }
+
+    let report = watchlist.poll_with(serve_feeds(feeds));
+    assert!(report.is_success());
+    assert_eq!(*attempts.lock().unwrap(), 3);
+    assert_eq!(watchlist.state().pending(), 0);
+}
+
+#[test]
+fn test_file_and_webhook_sinks() {
+    let (url, requests) = serve(vec![response("204 No Content", 0, b"")]);
+    let url = format!("{}/hook", url);
+
+    let path = temp_dir("watchlist-sinks").join("hits.jsonl");
+    let mut watchlist = Watchlist::new()
+        .set_delay(Duration::ZERO)
+        .set_emit_initial(true)
+        .add(WatchSource::user(1), WatchFilter::new())
+        .add_sink(JsonLinesSink::append(&path).unwrap())
+        .add_sink(WebhookSink::new(&url));
+    let feeds: Feeds = Arc::new(Mutex::new(vec![(
+        "/v1/user/illusts",
+        vec![work(7, &[], 2)],
+    )]));
+    let report = watchlist.poll_with(serve_feeds(feeds));
+    assert!(report.is_success(), "{:?}", report.failures());
+
+    let lines = std::fs::read_to_string(&path).unwrap();
+    let line: serde_json::Value = serde_json::from_str(lines.trim_end()).unwrap();
+    assert_eq!(line["source"], "user:illust:1");
+    assert_eq!(line["url"], "https://www.pixiv.net/artworks/7");
+    assert_eq!(line["illustration"]["id"], 7);
+
+    let request = requests.recv().unwrap();
+    let (head, body) = request.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("POST /hook HTTP/1.1"));
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body, line);
+}
+
+#[test]
+fn test_webhook_sink_errors() {
+    let (url, _requests) = serve(vec![response("500 Internal Server Error", 0, b"")]);
+    let mut watchlist = Watchlist::new()
+        .set_delay(Duration::ZERO)
+        .set_emit_initial(true)
+        .add(WatchSource::user(1), WatchFilter::new())
+        .add_sink(WebhookSink::new(&url));
+    let feeds: Feeds = Arc::new(Mutex::new(vec![(
+        "/v1/user/illusts",
+        vec![work(7, &[], 2)],
+    )]));
+    let report = watchlist.poll_with(serve_feeds(feeds));
+
+    let error = match report.failures().as_slice() {
+        [WatchFailure::Sink(error)] => error,
+        failures => panic!("{:?}", failures),
+    };
+    assert!(matches!(error, SyncError::Sink(_)));
+    assert!(error.to_string().starts_with("Failed to deliver the hit."));
+}
+
+#[test]
+fn test_watch_polls_until_stopped() {
+    let feeds: Feeds = Arc::new(Mutex::new(vec![("/v1/user/illusts", vec![])]));
+    let mut watchlist = Watchlist::new()
+        .set_delay(Duration::ZERO)
+        .set_interval(Duration::from_millis(1))
+        .add(WatchSource::user(1), WatchFilter::new());
+    let control = watchlist.control();
+    let mut polls = 0;
+
+    watchlist.watch_with(serve_feeds(feeds), |_| {
+        polls += 1;
+        if polls == 3 {
+            control.stop();
+        }
+    });
+    assert_eq!(polls, 3);
+}